@@ -0,0 +1,32 @@
+//! Time `batch_roots_to_coeffs_parallel` against sequential naive expansion
+//! for batches either side of `PARALLEL_SEQ_CUTOFF`. Run with
+//! `cargo run --release --example batch_parallel`.
+
+use std::time::{Duration, Instant};
+
+use accum::poly::{batch_roots_to_coeffs_parallel, roots_to_coeffs, PARALLEL_SEQ_CUTOFF};
+use pasta_curves::vesta::Scalar as FrVesta;
+
+fn time<F: Fn() -> Vec<Vec<FrVesta>>>(f: F) -> Duration {
+    let reps = 3;
+    let start = Instant::now();
+    for _ in 0..reps { std::hint::black_box(f()); }
+    start.elapsed() / reps
+}
+
+fn main() {
+    println!("cutoff: {}", PARALLEL_SEQ_CUTOFF);
+    println!("{:>8} {:>6} {:>14} {:>14}", "batches", "n", "batch parallel", "sequential");
+    for (count, n) in [(2000u64, 128u64), (500, 512)] {
+        let batches: Vec<Vec<FrVesta>> = (0..count)
+            .map(|b| (0..n).map(|i| FrVesta::from(b * 1000 + i + 1)).collect())
+            .collect();
+        println!(
+            "{:>8} {:>6} {:>14?} {:>14?}",
+            count,
+            n,
+            time(|| batch_roots_to_coeffs_parallel(&batches)),
+            time(|| batches.iter().map(|r| roots_to_coeffs(r)).collect()),
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Time the roots_to_coeffs variants across sizes to tune the
+//! `roots_to_coeffs_auto` crossovers. Run with `cargo run --release --example poly_crossover`.
+
+use std::time::{Duration, Instant};
+
+use accum::poly::{roots_to_coeffs, roots_to_coeffs_auto, roots_to_coeffs_fft, roots_to_coeffs_parallel, AUTO_NAIVE_MAX, AUTO_PARALLEL_MAX};
+use pasta_curves::vesta::Scalar as FrVesta;
+
+fn time<F: Fn() -> Vec<FrVesta>>(f: F) -> Duration {
+    let reps = 5;
+    let start = Instant::now();
+    for _ in 0..reps { std::hint::black_box(f()); }
+    start.elapsed() / reps
+}
+
+fn main() {
+    println!("crossovers: naive < {}, parallel <= {}, fft above", AUTO_NAIVE_MAX, AUTO_PARALLEL_MAX);
+    println!("{:>6} {:>12} {:>12} {:>12} {:>12}", "n", "naive", "parallel", "fft", "auto");
+    for n in [8usize, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096] {
+        let roots: Vec<FrVesta> = (0..n as u64).map(|i| FrVesta::from(i * 7919 + 3)).collect();
+        println!(
+            "{:>6} {:>12?} {:>12?} {:>12?} {:>12?}",
+            n,
+            time(|| roots_to_coeffs(&roots)),
+            time(|| roots_to_coeffs_parallel(&roots)),
+            time(|| roots_to_coeffs_fft(&roots).unwrap()),
+            time(|| roots_to_coeffs_auto(&roots)),
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Lowercase hex formatting and parsing for 32-byte newtypes.
+//!
+//! `impl_hex32!` gives a newtype over `[u8; 32]` a `hex()` / `from_hex()` pair
+//! plus `Display` and `FromStr`, for logs and test vectors. `Debug` stays derived.
+
+/// Error parsing a 32-byte value from hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum HexError {
+    #[error("expected 64 hex characters, got {0}")]
+    Length(usize),
+    #[error("invalid hex character {c:?} at index {index}")]
+    InvalidChar { c: char, index: usize },
+}
+
+/// Parse exactly 64 hex characters (either case) into 32 bytes.
+pub fn parse_hex32(s: &str) -> Result<[u8; 32], HexError> {
+    if s.len() != 64 { return Err(HexError::Length(s.len())); }
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(s, &mut out).map_err(|e| match e {
+        hex::FromHexError::InvalidHexCharacter { c, index } => HexError::InvalidChar { c, index },
+        _ => HexError::Length(s.len()),
+    })?;
+    Ok(out)
+}
+
+/// Write 32 bytes as lowercase hex.
+pub fn fmt_hex32(bytes: &[u8; 32], f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for b in bytes { write!(f, "{:02x}", b)?; }
+    Ok(())
+}
+
+/// Implement `hex()`, `from_hex()`, `Display` and `FromStr` for `[u8; 32]` newtypes.
+#[macro_export]
+macro_rules! impl_hex32 {
+    ($($t:ident),* $(,)?) => {$(
+        impl $t {
+            /// Lowercase hex of the 32 bytes.
+            pub fn hex(&self) -> String { self.to_string() }
+
+            /// Parse from 64 hex characters.
+            pub fn from_hex(s: &str) -> Result<Self, $crate::HexError> {
+                $crate::hexfmt::parse_hex32(s).map($t)
+            }
+        }
+
+        impl ::std::fmt::Display for $t {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                $crate::hexfmt::fmt_hex32(&self.0, f)
+            }
+        }
+
+        impl ::std::str::FromStr for $t {
+            type Err = $crate::HexError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> { Self::from_hex(s) }
+        }
+    )*};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{genesis_accumulator, AState, Commitment, PallasPointBytes, Root, SState};
+
+    #[test]
+    fn hex_newtypes_roundtrip_and_reject_malformed() {
+        let mut bytes = [0u8; 32];
+        for (i, b) in bytes.iter_mut().enumerate() { *b = (i as u8).wrapping_mul(37); }
+        let root = Root(bytes);
+        assert_eq!(root.hex(), hex::encode(bytes));
+        assert_eq!(format!("{}", root), root.hex());
+        assert_eq!(Root::from_hex(&root.hex()).unwrap(), root);
+        assert_eq!(Root::from_hex(&root.hex().to_uppercase()).unwrap(), root);
+        assert_eq!(Commitment::from_hex(&Commitment(bytes).hex()).unwrap(), Commitment(bytes));
+        assert_eq!(AState::from_hex(&AState(bytes).hex()).unwrap(), AState(bytes));
+        assert_eq!(SState::from_hex(&SState(bytes).hex()).unwrap(), SState(bytes));
+        let genesis = genesis_accumulator();
+        assert_eq!(genesis.hex().parse::<PallasPointBytes>().unwrap(), genesis);
+
+        assert_eq!(Root::from_hex(&root.hex()[2..]), Err(HexError::Length(62)));
+        assert_eq!(Root::from_hex(""), Err(HexError::Length(0)));
+        let mut bad = root.hex();
+        bad.replace_range(10..11, "g");
+        assert_eq!(Root::from_hex(&bad), Err(HexError::InvalidChar { c: 'g', index: 10 }));
+    }
+}
@@ -9,6 +9,8 @@ use ff::{Field, FromUniformBytes, PrimeField};
 use group::{Curve, Group, GroupEncoding};
 use group::prime::PrimeCurveAffine;
 use pasta_curves::pallas;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use subtle::{ConditionallySelectable, ConstantTimeEq};
 
 /// Maximum degree bound for per-block polynomial (number of roots per block).
 pub const DEGREE_N: usize = 4096;
@@ -22,14 +24,22 @@ pub const NUM_CHUNKS: usize = (NUM_COEFFICIENTS + CHUNK - 1) / CHUNK;
 const H2C_DOMAIN: &[u8] = b"tachyon/ipa:base-derivation";
 
 /// Derive a deterministic Pallas scalar from (chunk, idx).
+///
+/// s = from_uniform_bytes(Blake2b-512(H2C_DOMAIN || le32(chunk) || le32(idx))),
+/// unkeyed and unpersonalized. This replaces the original
+/// `personal(H2C_DOMAIN)` form, which could never run (the domain exceeds the
+/// 16-byte personalization), so no commitment was ever made under other bases.
+/// Any change here changes every commitment; `base_derivation_known_answer`
+/// pins the output.
 fn derive_scalar(chunk: u32, idx: u32) -> pallas::Scalar {
     let _buf = [0u8; 8];
     let mut le = [0u8; 8];
     le[..4].copy_from_slice(&chunk.to_le_bytes());
     le[4..].copy_from_slice(&idx.to_le_bytes());
 
-    // Blake2b-512(domain || le(chunk)||le(idx)) as uniform 64 bytes
-    let hash = Blake2bParams::new().hash_length(64).personal(H2C_DOMAIN).hash(&le);
+    // Blake2b-512(domain || le(chunk)||le(idx)) as uniform 64 bytes. The domain is
+    // longer than the 16-byte personalization limit, so it is absorbed as a prefix.
+    let hash = Blake2bParams::new().hash_length(64).to_state().update(H2C_DOMAIN).update(&le).finalize();
     let mut wide = [0u8; 64];
     wide.copy_from_slice(hash.as_bytes());
     <pallas::Scalar as FromUniformBytes<64>>::from_uniform_bytes(&wide)
@@ -60,14 +70,64 @@ pub fn derive_all_bases() -> Vec<pallas::Affine> {
 /// Derive the first `n` bases G_0..G_{n-1}.
 pub fn derive_bases_len(n: usize) -> Vec<pallas::Affine> {
     let mut bases = Vec::with_capacity(n);
-    let mut i = 0usize;
+    extend_bases(&mut bases, n);
+    bases
+}
+
+/// Append G_{len}..G_{n-1} to `bases`, which must hold G_0..G_{len-1}.
+fn extend_bases(bases: &mut Vec<pallas::Affine>, n: usize) {
+    let mut i = bases.len();
     while i < n {
         let chunk = (i / CHUNK) as u32;
         let idx = (i % CHUNK) as u32;
         bases.push(derive_base(chunk, idx));
         i += 1;
     }
-    bases
+}
+
+/// Cache of derived bases. Bases are a fixed sequence, so one prefix (the
+/// longest requested so far, up to `max_len`) serves every shorter length:
+/// asking for 100 bases after 4096 is a copy, not a derivation.
+///
+/// Thread-safe: the prefix sits behind a `Mutex` and is extended under the
+/// lock, so concurrent callers never derive the same base twice. Requests
+/// beyond `max_len` derive the excess tail without caching it.
+pub struct IpaBases {
+    max_len: usize,
+    prefix: Mutex<Vec<pallas::Affine>>,
+}
+
+impl IpaBases {
+    /// Empty cache holding at most `max_len` bases.
+    pub fn new(max_len: usize) -> Self {
+        Self { max_len, prefix: Mutex::new(Vec::new()) }
+    }
+
+    /// Process-wide cache sized for `NUM_COEFFICIENTS`, used by `commit_coeffs`.
+    pub fn global() -> &'static IpaBases {
+        static GLOBAL: OnceLock<IpaBases> = OnceLock::new();
+        GLOBAL.get_or_init(|| IpaBases::new(NUM_COEFFICIENTS))
+    }
+
+    /// Number of bases currently cached.
+    pub fn cached_len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// The first `n` bases G_0..G_{n-1}; equal to `derive_bases_len(n)`.
+    pub fn bases(&self, n: usize) -> Vec<pallas::Affine> {
+        let mut prefix = self.lock();
+        extend_bases(&mut prefix, n.min(self.max_len));
+        let mut out = prefix[..n.min(prefix.len())].to_vec();
+        drop(prefix);
+        extend_bases(&mut out, n);
+        out
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<pallas::Affine>> {
+        // A panic mid-extension leaves a valid (shorter) prefix, so poisoning is harmless.
+        self.prefix.lock().unwrap_or_else(|e| e.into_inner())
+    }
 }
 
 /// The distinguished generator G_0 := base for (chunk=0, idx=0).
@@ -78,44 +138,74 @@ pub fn g0() -> pallas::Affine { derive_base(0, 0) }
 pub fn commit_coeffs(coeffs: &[pallas::Scalar]) -> pallas::Affine {
     let m = coeffs.len();
     if m == 0 { return pallas::Point::identity().to_affine(); }
-    let bases = derive_bases_len(m);
+    let bases = IpaBases::global().bases(m);
     msm_pippenger(&bases, coeffs)
 }
 
-/// Windowed Pippenger MSM over Pallas: returns sum_i scalars[i] * bases[i].
-pub fn msm_pippenger(bases: &[pallas::Affine], scalars: &[pallas::Scalar]) -> pallas::Affine {
-    let m = bases.len().min(scalars.len());
-    if m == 0 { return pallas::Point::identity().to_affine(); }
+/// Constant-time `commit_coeffs` (via `msm_ct`) for secret coefficients.
+///
+/// No block path needs it: block coefficients are public, so the publisher,
+/// block prover and wallet step all use the faster variable-time
+/// `commit_coeffs`. Use this only for vectors that are actually secret.
+pub fn commit_coeffs_ct(coeffs: &[pallas::Scalar]) -> pallas::Affine {
+    let bases = IpaBases::global().bases(coeffs.len());
+    msm_ct(&bases, coeffs)
+}
 
-    // Heuristic window size based on input size.
-    fn optimal_window(n: usize) -> usize {
-        match n {
-            0..=32 => 3,
-            33..=128 => 5,
-            129..=512 => 7,
-            513..=2048 => 11,
-            2049..=8192 => 13,
-            _ => 15,
-        }
+/// Recommit a prover-supplied coefficient vector under `bases` and compare it to
+/// `commitment` in constant time. Returns false if there are more coefficients
+/// than bases.
+///
+/// This only checks a claimed opening of P_i; verifying a `BlockAccumRecord`
+/// without the coefficients uses `circuit::prove_ipa_opening` /
+/// `circuit::verify_ipa_opening` instead.
+pub fn open_and_check(bases: &[pallas::Affine], commitment: &pallas::Affine, coeffs: &[pallas::Scalar]) -> bool {
+    if coeffs.len() > bases.len() { return false; }
+    let recomputed = msm_pippenger(&bases[..coeffs.len()], coeffs);
+    bool::from(recomputed.ct_eq(commitment))
+}
+
+/// Heuristic Pippenger window size based on input size.
+fn optimal_window(n: usize) -> usize {
+    match n {
+        0..=32 => 3,
+        33..=128 => 5,
+        129..=512 => 7,
+        513..=2048 => 11,
+        2049..=8192 => 13,
+        _ => 15,
     }
+}
 
-    // Extract w-bit window value from scalar's little-endian bytes at window index `win`.
-    #[inline]
-    fn window_value(bytes_le: &[u8; 32], win: usize, w: usize) -> u32 {
-        let start = win * w;
-        let mut acc: u32 = 0;
-        for i in 0..w {
-            let bit_idx = start + i;
-            let byte = bit_idx >> 3; // /8
-            if byte >= 32 { break; }
-            let bit_in_byte = bit_idx & 7; // %8
-            let b = (bytes_le[byte] >> bit_in_byte) & 1;
-            acc |= (b as u32) << i;
-        }
-        acc
+/// Extract w-bit window value from scalar's little-endian bytes at window index `win`.
+#[inline]
+fn window_value(bytes_le: &[u8; 32], win: usize, w: usize) -> u32 {
+    let start = win * w;
+    let mut acc: u32 = 0;
+    for i in 0..w {
+        let bit_idx = start + i;
+        let byte = bit_idx >> 3; // /8
+        if byte >= 32 { break; }
+        let bit_in_byte = bit_idx & 7; // %8
+        let b = (bytes_le[byte] >> bit_in_byte) & 1;
+        acc |= (b as u32) << i;
     }
+    acc
+}
+
+/// Windowed Pippenger MSM over Pallas: returns sum_i scalars[i] * bases[i].
+pub fn msm_pippenger(bases: &[pallas::Affine], scalars: &[pallas::Scalar]) -> pallas::Affine {
+    let m = bases.len().min(scalars.len());
+    msm_pippenger_with_window(bases, scalars, optimal_window(m)).expect("heuristic window is in range")
+}
+
+/// Pippenger MSM with a caller-chosen window `w` in `1..=16` (for tuning and
+/// benchmarks); the result is independent of `w`.
+pub fn msm_pippenger_with_window(bases: &[pallas::Affine], scalars: &[pallas::Scalar], w: usize) -> anyhow::Result<pallas::Affine> {
+    if !(1..=16).contains(&w) { anyhow::bail!("MSM window {} outside 1..=16", w); }
+    let m = bases.len().min(scalars.len());
+    if m == 0 { return Ok(pallas::Point::identity().to_affine()); }
 
-    let w = optimal_window(m);
     let num_bits = pallas::Scalar::NUM_BITS as usize; // 255
     let num_windows = (num_bits + w - 1) / w;
 
@@ -154,6 +244,53 @@ pub fn msm_pippenger(bases: &[pallas::Affine], scalars: &[pallas::Scalar]) -> pa
         }
     }
 
+    Ok(acc.to_affine())
+}
+
+/// Window of `msm_ct`.
+const CT_WINDOW: usize = 4;
+
+/// Constant-time MSM: sum_i scalars[i] * bases[i] by interleaved fixed-window
+/// (Straus) multiplication. Each window digit is looked up by scanning the
+/// whole per-base table with `conditional_select`, and every window performs
+/// the same doublings and complete additions, so neither the memory access
+/// pattern nor the operation sequence depends on the scalars. Much slower than
+/// `msm_pippenger`; use it only for secret scalars.
+pub fn msm_ct(bases: &[pallas::Affine], scalars: &[pallas::Scalar]) -> pallas::Affine {
+    let m = bases.len().min(scalars.len());
+    let table_len = 1usize << CT_WINDOW;
+    // tables[i][d] = d * bases[i].
+    let tables: Vec<Vec<pallas::Point>> = bases[..m]
+        .iter()
+        .map(|b| {
+            let mut t = Vec::with_capacity(table_len);
+            t.push(pallas::Point::identity());
+            for d in 1..table_len { t.push(t[d - 1] + b); }
+            t
+        })
+        .collect();
+    let scalars_le: Vec<[u8; 32]> = scalars[..m]
+        .iter()
+        .map(|s| {
+            let mut le = [0u8; 32];
+            le.copy_from_slice(<pallas::Scalar as PrimeField>::to_repr(s).as_ref());
+            le
+        })
+        .collect();
+
+    let num_windows = (pallas::Scalar::NUM_BITS as usize).div_ceil(CT_WINDOW);
+    let mut acc = pallas::Point::identity();
+    for win in (0..num_windows).rev() {
+        for _ in 0..CT_WINDOW { acc = acc.double(); }
+        for (table, le) in tables.iter().zip(&scalars_le) {
+            let digit = window_value(le, win, CT_WINDOW);
+            let mut sel = pallas::Point::identity();
+            for (d, entry) in table.iter().enumerate() {
+                sel.conditional_assign(entry, (d as u32).ct_eq(&digit));
+            }
+            acc += sel;
+        }
+    }
     acc.to_affine()
 }
 
@@ -181,6 +318,14 @@ pub fn mul_point(a: &pallas::Affine, s: &pallas::Scalar) -> pallas::Affine {
     (a.to_curve() * *s).to_affine()
 }
 
+/// Canonical embedding Fr(Vesta) -> Fr(Pallas): the same integer read in the
+/// larger field (the Vesta scalar modulus is below the Pallas one). Block
+/// commitments embed coefficients this way, so an opening of P_i evaluates the
+/// block polynomial's own coefficients rather than hashes of them.
+pub fn embed_vesta_scalar(x: &pasta_curves::vesta::Scalar) -> pallas::Scalar {
+    Option::from(pallas::Scalar::from_repr(x.to_repr())).expect("Fr(Vesta) is smaller than Fr(Pallas)")
+}
+
 const DS_COEFF_MAP: &[u8] = b"tachyon/coeff-map";
 
 /// Deterministically map a Vesta field element (32-byte repr) into a Pallas scalar
@@ -188,12 +333,43 @@ const DS_COEFF_MAP: &[u8] = b"tachyon/coeff-map";
 /// This is only suitable for off-circuit testing and placeholder flows; real circuits
 /// must bind the same bit-decomposition consistently.
 pub fn map_vesta_scalar_to_pallas(vesta_bytes32: &[u8; 32]) -> pallas::Scalar {
-    let hash = Blake2bParams::new().hash_length(64).personal(DS_COEFF_MAP).hash(vesta_bytes32);
+    let hash = Blake2bParams::new().hash_length(64).to_state().update(DS_COEFF_MAP).update(vesta_bytes32).finalize();
     let mut wide = [0u8; 64];
     wide.copy_from_slice(hash.as_bytes());
     <pallas::Scalar as FromUniformBytes<64>>::from_uniform_bytes(&wide)
 }
 
+const DS_IPA_TRANSCRIPT_V1: &[u8; 16] = b"tachyon.ipa.tr\0\0"; // 14 + 2 = 16
+
+/// Fiat-Shamir transcript for IPA rounds: a running BLAKE2b-512 state.
+/// Challenges are wide-reduced and absorbed back, so each challenge depends on
+/// every message and challenge before it. Prover and verifier must absorb the
+/// same messages in the same order.
+#[derive(Clone)]
+pub struct Transcript(blake2b_simd::State);
+
+impl Transcript {
+    /// Start a transcript for the protocol named by `label`.
+    pub fn new(label: &[u8]) -> Self {
+        let mut state = Blake2bParams::new().hash_length(64).personal(DS_IPA_TRANSCRIPT_V1).to_state();
+        state.update(&(label.len() as u64).to_le_bytes());
+        state.update(label);
+        Self(state)
+    }
+
+    pub fn absorb_point(&mut self, p: &pallas::Affine) { self.0.update(&encode_point(p)); }
+
+    pub fn absorb_scalar(&mut self, s: &pallas::Scalar) { self.0.update(s.to_repr().as_ref()); }
+
+    pub fn challenge_scalar(&mut self) -> pallas::Scalar {
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(self.0.clone().finalize().as_bytes());
+        let c = <pallas::Scalar as FromUniformBytes<64>>::from_uniform_bytes(&wide);
+        self.absorb_scalar(&c);
+        c
+    }
+}
+
 /// Circuit-facing stubs for chunked MSM. Wiring and constraints will be added later.
 pub mod circuit {
     use super::*;
@@ -290,7 +466,7 @@ pub mod circuit {
     }
 
     /// Pairing-free IPA opening proof over the vector commitment C = <c, G>.
-    /// Gadget skeleton: O(log n) rounds with challenges from a Blake2b transcript.
+    /// Gadget skeleton: O(log n) rounds with challenges from a `Transcript`.
     #[derive(Clone, Debug, Default)]
     pub struct IpaProof {
         pub l_vec: Vec<pallas::Affine>,
@@ -307,19 +483,314 @@ pub mod circuit {
         pub u: pallas::Affine,
     }
 
+    impl IpaVerifierParams {
+        /// Parameters for openings of up to `n` coefficients (`n` a power of
+        /// two): G_0..G_{n-1} as used by `commit_coeffs`, and an inner-product
+        /// base U derived outside the coefficient chunk range.
+        pub fn new(n: usize) -> anyhow::Result<Self> {
+            Self::with_bases(IpaBases::global(), n)
+        }
+
+        /// As `new`, taking G_0..G_{n-1} from `bases` instead of the global cache.
+        pub fn with_bases(bases: &IpaBases, n: usize) -> anyhow::Result<Self> {
+            if !n.is_power_of_two() { anyhow::bail!("IPA size {} is not a power of two", n); }
+            Ok(Self { g_bases: bases.bases(n), h_bases: vec![], u: derive_base(u32::MAX, 0) })
+        }
+    }
+
+    fn inner_product(a: &[pallas::Scalar], b: &[pallas::Scalar]) -> pallas::Scalar {
+        a.iter().zip(b).map(|(x, y)| *x * y).sum()
+    }
+
+    fn points_msm(points: &[pallas::Point], scalars: &[pallas::Scalar]) -> pallas::Point {
+        points.iter().zip(scalars).map(|(p, s)| p * s).sum()
+    }
+
+    /// Prove the opening of the vector commitment C = <coeffs, G> at `x`:
+    /// v = sum_i coeffs[i] * x^i. Returns (v, proof). Coefficients are padded
+    /// with zeros to `params.g_bases.len()`, so C equals `commit_coeffs(coeffs)`.
+    ///
+    /// Each round halves the vectors with L = <a_lo, G_hi> + <a_lo, b_hi>U',
+    /// R = <a_hi, G_lo> + <a_hi, b_lo>U' and challenge u:
+    /// a' = u a_lo + u^-1 a_hi, b' = u^-1 b_lo + u b_hi, G' = u^-1 G_lo + u G_hi,
+    /// where U' = [w]U for a transcript challenge w binding (C, x, v).
+    pub fn prove_ipa_opening(
+        params: &IpaVerifierParams,
+        coeffs: &[pallas::Scalar],
+        point_x: &pallas::Scalar,
+    ) -> anyhow::Result<(pallas::Scalar, IpaProof)> {
+        let n = params.g_bases.len();
+        if !n.is_power_of_two() { anyhow::bail!("IPA size {} is not a power of two", n); }
+        if coeffs.len() > n { anyhow::bail!("{} coefficients exceed {} IPA bases", coeffs.len(), n); }
+        let mut a = coeffs.to_vec();
+        a.resize(n, pallas::Scalar::ZERO);
+        let mut b: Vec<pallas::Scalar> = std::iter::successors(Some(pallas::Scalar::ONE), |p| Some(*p * point_x)).take(n).collect();
+        let mut g: Vec<pallas::Point> = params.g_bases.iter().map(|p| p.to_curve()).collect();
+        let commitment = msm_pippenger(&params.g_bases, &a);
+        let value = inner_product(&a, &b);
+
+        let mut tr = Transcript::new(b"ipa.opening");
+        tr.absorb_point(&commitment);
+        tr.absorb_scalar(point_x);
+        tr.absorb_scalar(&value);
+        let u_prime = params.u.to_curve() * tr.challenge_scalar();
+
+        let mut proof = IpaProof::default();
+        while a.len() > 1 {
+            let half = a.len() / 2;
+            let (a_lo, a_hi) = a.split_at(half);
+            let (b_lo, b_hi) = b.split_at(half);
+            let (g_lo, g_hi) = g.split_at(half);
+            let l = (points_msm(g_hi, a_lo) + u_prime * inner_product(a_lo, b_hi)).to_affine();
+            let r = (points_msm(g_lo, a_hi) + u_prime * inner_product(a_hi, b_lo)).to_affine();
+            tr.absorb_point(&l);
+            tr.absorb_point(&r);
+            let u = tr.challenge_scalar();
+            let u_inv = Option::<pallas::Scalar>::from(u.invert()).ok_or_else(|| anyhow::anyhow!("zero IPA challenge"))?;
+            a = (0..half).map(|i| a_lo[i] * u + a_hi[i] * u_inv).collect();
+            b = (0..half).map(|i| b_lo[i] * u_inv + b_hi[i] * u).collect();
+            g = (0..half).map(|i| g_lo[i] * u_inv + g_hi[i] * u).collect();
+            proof.l_vec.push(l);
+            proof.r_vec.push(r);
+        }
+        proof.a_final = a[0];
+        proof.b_final = b[0];
+        Ok((value, proof))
+    }
+
+    /// Scalars of one opening's check, which holds iff
+    /// sum_i [a s_i]G_i + [w(a b - v)]U - C - sum_j ([u_j^2]L_j + [u_j^-2]R_j) = 0
+    /// with s_i = prod_j u_j^{+-1} (sign by bit j of i, top bit first).
+    struct OpeningCheck {
+        g_scalars: Vec<pallas::Scalar>,
+        u_scalar: pallas::Scalar,
+        /// Scalars for C, then L_j, R_j interleaved.
+        points: Vec<(pallas::Affine, pallas::Scalar)>,
+    }
+
+    fn opening_check(
+        n: usize,
+        commitment: &pallas::Affine,
+        point_x: &pallas::Scalar,
+        value_v: &pallas::Scalar,
+        proof: &IpaProof,
+    ) -> Option<OpeningCheck> {
+        let rounds = n.trailing_zeros() as usize;
+        if proof.l_vec.len() != rounds || proof.r_vec.len() != rounds { return None; }
+        let mut tr = Transcript::new(b"ipa.opening");
+        tr.absorb_point(commitment);
+        tr.absorb_scalar(point_x);
+        tr.absorb_scalar(value_v);
+        let w = tr.challenge_scalar();
+
+        let mut points = vec![(*commitment, -pallas::Scalar::ONE)];
+        let mut challenges = Vec::with_capacity(rounds);
+        for (l, r) in proof.l_vec.iter().zip(&proof.r_vec) {
+            tr.absorb_point(l);
+            tr.absorb_point(r);
+            let u = tr.challenge_scalar();
+            let u_inv = Option::<pallas::Scalar>::from(u.invert())?;
+            points.push((*l, -u.square()));
+            points.push((*r, -u_inv.square()));
+            challenges.push((u, u_inv));
+        }
+
+        // b folds like G: b_final = prod_j (u_j^-1 + u_j x^{2^(k-1-j)}).
+        let mut b_final = pallas::Scalar::ONE;
+        for (j, (u, u_inv)) in challenges.iter().enumerate() {
+            let x_pow = point_x.pow_vartime([1u64 << (rounds - 1 - j)]);
+            b_final *= *u_inv + *u * x_pow;
+        }
+        if b_final != proof.b_final { return None; }
+
+        let mut s = vec![pallas::Scalar::ONE; n];
+        for (j, (u, u_inv)) in challenges.iter().enumerate() {
+            let bit = rounds - 1 - j;
+            for (i, si) in s.iter_mut().enumerate() {
+                *si *= if (i >> bit) & 1 == 1 { *u } else { *u_inv };
+            }
+        }
+        let g_scalars = s.into_iter().map(|si| si * proof.a_final).collect();
+        let u_scalar = w * (proof.a_final * b_final - value_v);
+        Some(OpeningCheck { g_scalars, u_scalar, points })
+    }
+
     /// Verify an IPA opening for value v at evaluation point x against commitment C.
-    /// This is a stub for the in-circuit folding logic; no constraints here yet.
+    /// Off-circuit reference for the in-circuit folding logic; a batch of one.
+    /// A proof whose `l_vec`/`r_vec` do not both hold log2 of the padded size
+    /// (`g_bases.len()`) rounds is rejected before any folding.
     pub fn verify_ipa_opening(
-        _params: &IpaVerifierParams,
-        _commitment: &pallas::Affine,
-        _point_x: &pallas::Scalar,
-        _value_v: &pallas::Scalar,
-        _proof: &IpaProof,
+        params: &IpaVerifierParams,
+        commitment: &pallas::Affine,
+        point_x: &pallas::Scalar,
+        value_v: &pallas::Scalar,
+        proof: &IpaProof,
+    ) -> bool {
+        verify_ipa_batch(params, &[(*commitment, *point_x, *value_v, proof.clone())])
+    }
+
+    /// Verify many openings with one MSM: each opening's check is scaled by
+    /// rho^k for a transcript challenge rho over all items and the checks are
+    /// summed, so one bad opening fails the batch except with negligible
+    /// probability. An empty batch verifies.
+    pub fn verify_ipa_batch(
+        params: &IpaVerifierParams,
+        items: &[(pallas::Affine, pallas::Scalar, pallas::Scalar, IpaProof)],
     ) -> bool {
-        // Placeholder: real verifier will perform round-by-round folding using transcript
-        // challenges and finally check <g_final, h_final> relation with (a_final, b_final).
-        true
+        let n = params.g_bases.len();
+        if !n.is_power_of_two() { return false; }
+        let mut tr = Transcript::new(b"ipa.batch");
+        for (c, x, v, proof) in items {
+            tr.absorb_point(c);
+            tr.absorb_scalar(x);
+            tr.absorb_scalar(v);
+            for (l, r) in proof.l_vec.iter().zip(&proof.r_vec) {
+                tr.absorb_point(l);
+                tr.absorb_point(r);
+            }
+            tr.absorb_scalar(&proof.a_final);
+        }
+        let rho = tr.challenge_scalar();
+
+        let mut g_scalars = vec![pallas::Scalar::ZERO; n];
+        let mut u_scalar = pallas::Scalar::ZERO;
+        let mut bases = params.g_bases.clone();
+        bases.push(params.u);
+        let mut scalars = Vec::new();
+        let mut weight = pallas::Scalar::ONE;
+        for (c, x, v, proof) in items {
+            let check = match opening_check(n, c, x, v, proof) { Some(check) => check, None => return false };
+            for (acc, s) in g_scalars.iter_mut().zip(&check.g_scalars) { *acc += weight * s; }
+            u_scalar += weight * check.u_scalar;
+            for (p, s) in check.points {
+                bases.push(p);
+                scalars.push(weight * s);
+            }
+            weight *= rho;
+        }
+        g_scalars.push(u_scalar);
+        g_scalars.extend(scalars);
+        bool::from(msm_pippenger(&bases, &g_scalars).is_identity())
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    #[test]
+    fn base_derivation_known_answer() {
+        let cases = [
+            (0, 0, "c1f9d4d413768788ddf8dd2c16e8d92fb2c6a8286492c17977be216da407e591"),
+            (0, 1, "a85fe8c6592a39b0ec96a6b581a781d6b99e0d27992556b9a9898f0a0fa192b3"),
+            (1, 0, "82afdccf205ffae8fbcee21d974c63cb44082948f32c48a6b74dec41cc4abd07"),
+        ];
+        for (chunk, idx, expected) in cases {
+            assert_eq!(hex::encode(encode_point(&derive_base(chunk, idx))), expected, "G[{chunk}][{idx}]");
+        }
+        // G_256 is the first base of chunk 1.
+        assert_eq!(derive_bases_len(CHUNK + 1)[CHUNK], derive_base(1, 0));
+    }
+
+    #[test]
+    fn open_and_check_detects_perturbed_coeff() {
+        let mut coeffs = [5u64, 7, 11, 13].map(pallas::Scalar::from).to_vec();
+        let bases = derive_bases_len(coeffs.len());
+        let c = commit_coeffs(&coeffs);
+        assert!(open_and_check(&bases, &c, &coeffs));
+        coeffs[2] += pallas::Scalar::from(1u64);
+        assert!(!open_and_check(&bases, &c, &coeffs));
+    }
+
+    #[test]
+    fn cached_bases_match_fresh_derivation() {
+        let cache = IpaBases::new(300);
+        // Grow, shrink, then exceed the cap: every answer must equal a fresh derivation.
+        for n in [0, 5, 260, 100, 1, 300, 310] {
+            assert_eq!(cache.bases(n), derive_bases_len(n), "n = {n}");
+        }
+        assert_eq!(cache.cached_len(), 300);
+    }
+
+    #[test]
+    fn commit_coeffs_ct_matches_variable_time() {
+        for n in [0usize, 1, 3, 17] {
+            let coeffs: Vec<pallas::Scalar> = (0..n as u64).map(|i| pallas::Scalar::from(i * 7919 + 1).square() - pallas::Scalar::from(i)).collect();
+            assert_eq!(commit_coeffs_ct(&coeffs), commit_coeffs(&coeffs), "n = {n}");
+        }
+        // Top-window digits set (scalar = -1).
+        assert_eq!(commit_coeffs_ct(&[-pallas::Scalar::from(1u64)]), commit_coeffs(&[-pallas::Scalar::from(1u64)]));
+    }
+
+    #[test]
+    fn ipa_batch_accepts_valid_openings_and_rejects_flipped_value() {
+        use super::circuit::{prove_ipa_opening, verify_ipa_batch, verify_ipa_opening, IpaVerifierParams};
+        let params = IpaVerifierParams::new(8).unwrap();
+        let mut items = Vec::new();
+        for k in 0u64..3 {
+            let coeffs: Vec<pallas::Scalar> = (0..(k + 5)).map(|i| pallas::Scalar::from(i * 31 + k + 1)).collect();
+            let x = pallas::Scalar::from(k * 1000 + 17);
+            let (v, proof) = prove_ipa_opening(&params, &coeffs, &x).unwrap();
+            let c = commit_coeffs(&coeffs);
+            assert!(verify_ipa_opening(&params, &c, &x, &v, &proof));
+            items.push((c, x, v, proof));
+        }
+        assert!(verify_ipa_batch(&params, &items));
+
+        items[1].2 += pallas::Scalar::from(1u64);
+        assert!(!verify_ipa_batch(&params, &items));
+        assert!(!verify_ipa_opening(&params, &items[1].0, &items[1].1, &items[1].2, &items[1].3));
+    }
+
+    #[test]
+    fn ipa_rejects_proof_with_wrong_round_count() {
+        use super::circuit::{prove_ipa_opening, verify_ipa_opening, IpaVerifierParams};
+        let params = IpaVerifierParams::new(8).unwrap();
+        let coeffs: Vec<pallas::Scalar> = (1u64..=5).map(pallas::Scalar::from).collect();
+        let x = pallas::Scalar::from(9u64);
+        let (v, proof) = prove_ipa_opening(&params, &coeffs, &x).unwrap();
+        let c = commit_coeffs(&coeffs);
+        assert_eq!(proof.l_vec.len(), 3);
+
+        let mut short = proof.clone();
+        short.l_vec.pop();
+        short.r_vec.pop();
+        assert!(!verify_ipa_opening(&params, &c, &x, &v, &short));
+        let mut ragged = proof.clone();
+        ragged.r_vec.pop();
+        assert!(!verify_ipa_opening(&params, &c, &x, &v, &ragged));
+        // The same proof checked against a larger IPA size is also short.
+        assert!(!verify_ipa_opening(&IpaVerifierParams::new(16).unwrap(), &c, &x, &v, &proof));
+    }
+
+    #[test]
+    fn transcripts_agree_on_challenge_sequence() {
+        let run = |label: &[u8], value: u64| {
+            let mut tr = Transcript::new(label);
+            let mut out = Vec::new();
+            for i in 0..3u64 {
+                tr.absorb_point(&g0());
+                tr.absorb_scalar(&pallas::Scalar::from(value + i));
+                out.push(tr.challenge_scalar());
+            }
+            out
+        };
+        let prover = run(b"ipa.opening", 5);
+        assert_eq!(prover, run(b"ipa.opening", 5));
+        assert_ne!(prover[0], prover[1]);
+        assert_ne!(prover, run(b"ipa.opening", 6));
+        assert_ne!(prover, run(b"ipa.batch", 5));
+    }
+
+    #[test]
+    fn msm_window_sizes_agree() {
+        let bases = derive_bases_len(40);
+        let scalars: Vec<pallas::Scalar> = (0u64..40).map(|i| pallas::Scalar::from(i * 7919 + 3).square()).collect();
+        let expected = msm_pippenger(&bases, &scalars);
+        for w in [1, 2, 4, 8, 12, 16] {
+            assert_eq!(msm_pippenger_with_window(&bases, &scalars, w).unwrap(), expected);
+        }
+        assert!(msm_pippenger_with_window(&bases, &scalars, 0).is_err());
+        assert!(msm_pippenger_with_window(&bases, &scalars, 17).is_err());
+    }
+}
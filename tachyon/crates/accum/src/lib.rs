@@ -5,11 +5,21 @@
 //! consensus.
 
 pub mod poseidon;
+pub mod hexfmt;
 pub mod ipa;
+pub mod merkle;
+pub mod mmr;
 pub mod poly;
+pub mod prf;
+pub mod sma;
 
 use serde::{Deserialize, Serialize};
 
+pub use hexfmt::HexError;
+pub use mmr::{Mmr, MmrProof};
+pub use poly::merge_sorted_roots;
+pub use sma::MerkleSMA;
+
 /// Accumulator parameters (opinionated defaults for Tachyon v1).
 pub mod params {
     /// Tree height (depth) = 32 → 2^32 leaves.
@@ -25,6 +35,8 @@ pub mod params {
     pub const CHUNK: usize = crate::ipa::CHUNK;
     /// Number of chunks.
     pub const NUM_CHUNKS: usize = crate::ipa::NUM_CHUNKS;
+    /// Most items in one batch update: a block commits at most `DEGREE_N` tachygrams.
+    pub const MAX_BATCH_ITEMS: usize = DEGREE_N;
 }
 
 /// Pallas commitment types (opaque for now; exposed for consensus I/O later).
@@ -37,10 +49,49 @@ pub struct AState(pub [u8; 32]);
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug, Default)]
 pub struct SState(pub [u8; 32]);
 
+/// Compressed Pallas point (accumulator states A_i and block commitments P_i).
+/// Serializes as a lowercase hex string in human-readable formats (JSON) and
+/// as raw bytes otherwise.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PallasPointBytes(pub [u8; 32]);
+
+impl Serialize for PallasPointBytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&self.hex())
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for PallasPointBytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            let s = String::deserialize(deserializer)?;
+            Self::from_hex(&s).map_err(serde::de::Error::custom)
+        } else {
+            <[u8; 32]>::deserialize(deserializer).map(Self)
+        }
+    }
+}
+
+/// Genesis accumulator A_0: hash-to-curve of "genesis" under the
+/// "tachyon:accum" domain, so nobody knows its discrete log relative to the
+/// commitment bases. Chains start here rather than at `ipa::g0()`.
+pub fn genesis_accumulator() -> PallasPointBytes {
+    use group::Curve;
+    use pasta_curves::arithmetic::CurveExt;
+    let p = pasta_curves::pallas::Point::hash_to_curve("tachyon:accum")(b"genesis");
+    PallasPointBytes(ipa::encode_point(&p.to_affine()))
+}
+
 /// 32-byte accumulator root (Poseidon-based tree root, Pasta field domain).
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug, Default)]
 pub struct Root(pub [u8; 32]);
 
+impl_hex32!(Commitment, AState, SState, PallasPointBytes, Root);
+
 /// Sparse Merkle path element.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct PathElem {
@@ -77,6 +128,44 @@ pub struct BatchItem {
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct BatchUpdate(pub Vec<BatchItem>);
 
+impl BatchUpdate {
+    /// Batch inserting `keys`, sorted by key with duplicates collapsed.
+    pub fn inserts(keys: &[[u8; 32]]) -> Self { Self::uniform(keys, true) }
+
+    /// Batch deleting `keys`, sorted by key with duplicates collapsed.
+    pub fn deletions(keys: &[[u8; 32]]) -> Self { Self::uniform(keys, false) }
+
+    fn uniform(keys: &[[u8; 32]], present: bool) -> Self {
+        let mut keys = keys.to_vec();
+        keys.sort_unstable();
+        keys.dedup();
+        Self(keys.into_iter().map(|key_hash| BatchItem { key_hash, present }).collect())
+    }
+
+    /// Check the batch fits `params::MAX_BATCH_ITEMS` and touches each key once.
+    pub fn validate(&self) -> Result<(), SmaError> {
+        if self.0.len() > params::MAX_BATCH_ITEMS {
+            return Err(SmaError::BatchTooLarge { len: self.0.len(), max: params::MAX_BATCH_ITEMS });
+        }
+        let mut seen = std::collections::BTreeSet::new();
+        for it in &self.0 {
+            if !seen.insert(it.key_hash) { return Err(SmaError::DuplicateKey(it.key_hash)); }
+        }
+        Ok(())
+    }
+}
+
+/// Errors applying a batch to an accumulator. A failed batch leaves the state unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SmaError {
+    #[error("batch has {len} items, max {max}")]
+    BatchTooLarge { len: usize, max: usize },
+    #[error("duplicate key in batch: {}", hex::encode(.0))]
+    DuplicateKey([u8; 32]),
+    #[error("leaf position {position} already holds a different key")]
+    PositionCollision { position: u64 },
+}
+
 /// SMA interface for consensus and circuits.
 pub trait SparseMerkleAccumulator {
     /// Domain size exponent (tree height k so 2^k leaves).
@@ -91,8 +180,8 @@ pub trait SparseMerkleAccumulator {
     /// Prove non-membership of key.
     fn prove_non_membership(&self, key_hash: [u8; 32]) -> NonMembershipProof;
 
-    /// Apply a canonical batch and return the new root.
-    fn apply_batch(&mut self, batch: &BatchUpdate) -> Root;
+    /// Apply a canonical batch and return the new root; on error nothing is applied.
+    fn apply_batch(&mut self, batch: &BatchUpdate) -> Result<Root, SmaError>;
 }
 
 /// Verkle-ready interface (no pairings): allows swapping a vector-commitment
@@ -126,6 +215,23 @@ pub mod ser {
     }
 }
 
+const DS_WINDOW_ROOT_V1: &[u8; 16] = b"tachyon.nfwin.v1"; // 16
+
+/// Window root after `batch`: until the SMA backend is wired, a BLAKE2b chain
+/// over the prior root and the canonical batch encoding.
+fn window_next_root(prev: &Root, batch: &BatchUpdate) -> Root {
+    let h = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(DS_WINDOW_ROOT_V1)
+        .to_state()
+        .update(&prev.0)
+        .update(&ser::serialize_batch(batch))
+        .finalize();
+    let mut next = [0u8; 32];
+    next.copy_from_slice(h.as_bytes());
+    Root(next)
+}
+
 /// Rolling window of nullifiers backed by an SMA root history.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct NullifierSMAWindow {
@@ -135,28 +241,128 @@ pub struct NullifierSMAWindow {
     pub recent_roots: Vec<Root>,
     /// Height of the underlying accumulator.
     pub height: usize,
+    /// Most roots `window_len` may cover (current included); older batches
+    /// are forgotten once exceeded. `None` retains every batch.
+    #[serde(default)]
+    pub max_window: Option<usize>,
+    /// Keys present under `current_root`: the accumulator's full membership,
+    /// not just keys touched by retained batches. Entries leave only through a
+    /// removal (`present: false`) or `rollback`, never when their batch ages
+    /// out, so a spent nullifier stays non-fresh; the set therefore grows with
+    /// every inserted key, and `max_window` bounds only the roots and undo logs.
+    #[serde(default)]
+    present: std::collections::BTreeSet<[u8; 32]>,
+    /// Per-batch undo log aligned with `recent_roots`: prior presence of each touched key.
+    #[serde(default)]
+    undo: Vec<Vec<([u8; 32], bool)>>,
+    /// MMR over every root pushed into `recent_roots`, oldest first.
+    #[serde(default)]
+    history: Mmr,
 }
 
 impl NullifierSMAWindow {
     /// Create a new window with the given height and initial root.
     pub fn new(height: usize, initial_root: Root) -> Self {
-        Self { current_root: initial_root, recent_roots: Vec::new(), height }
+        Self { current_root: initial_root, recent_roots: Vec::new(), height, ..Default::default() }
+    }
+
+    /// Window retaining exactly enough history to undo `max_reorg` batches:
+    /// `max_window = max_reorg + 1` (the current root plus `max_reorg` prior ones).
+    pub fn for_reorg_depth(height: usize, initial_root: Root, max_reorg: usize) -> Self {
+        Self { max_window: Some(max_reorg.saturating_add(1)), ..Self::new(height, initial_root) }
     }
 
     /// Advance the window by applying a deterministic batch of nullifier insertions.
-    /// Returns the new root.
-    pub fn apply_batch(&mut self, batch: &BatchUpdate) -> Root {
-        // Placeholder: in a full implementation this would delegate to an SMA backend.
-        // Here we simply record the previous root and return the unchanged root.
-        let _ = batch;
+    /// Returns the new root; an invalid batch (see `BatchUpdate::validate`) is rejected.
+    pub fn apply_batch(&mut self, batch: &BatchUpdate) -> Result<Root, SmaError> {
+        batch.validate()?;
+        // Presence is tracked locally; the root comes from `window_next_root`.
+        let mut undo = Vec::with_capacity(batch.0.len());
+        for it in &batch.0 {
+            let was_present = if it.present {
+                !self.present.insert(it.key_hash)
+            } else {
+                self.present.remove(&it.key_hash)
+            };
+            undo.push((it.key_hash, was_present));
+        }
+        let next = window_next_root(&self.current_root, batch);
         self.recent_roots.insert(0, self.current_root);
-        self.current_root
+        self.history.append(self.current_root.0);
+        self.undo.insert(0, undo);
+        if let Some(max) = self.max_window {
+            let keep = max.saturating_sub(1);
+            self.recent_roots.truncate(keep);
+            self.undo.truncate(keep);
+        }
+        self.current_root = next;
+        Ok(self.current_root)
     }
 
-    /// Whether a key hash is fresh within the current window (non-membership check).
-    pub fn is_fresh(&self, _key_hash: &[u8; 32]) -> bool {
-        // Placeholder until SMA backend is wired; treat unknown as fresh.
-        true
+    /// (old_root, new_root) for applying `batch` to this window, without
+    /// changing it. The new root depends only on the old root and the batch,
+    /// so anyone can check the pair with `verify_transition`.
+    pub fn prove_transition(&self, batch: &BatchUpdate) -> Result<(Root, Root), SmaError> {
+        batch.validate()?;
+        Ok((self.current_root, window_next_root(&self.current_root, batch)))
+    }
+
+    /// Whether applying `batch` to a window at `old` yields `new`.
+    pub fn verify_transition(old: &Root, batch: &BatchUpdate, new: &Root) -> bool {
+        batch.validate().is_ok() && window_next_root(old, batch) == *new
+    }
+
+    /// Revert the last `steps` applied batches, restoring `current_root` from
+    /// `recent_roots`. Errors without changing state if fewer batches are retained.
+    /// A window from `for_reorg_depth` can always revert up to `max_reorg` batches.
+    pub fn rollback(&mut self, steps: usize) -> anyhow::Result<()> {
+        if steps > self.recent_roots.len() || steps > self.undo.len() {
+            anyhow::bail!("cannot roll back {} batches: only {} retained", steps, self.undo.len().min(self.recent_roots.len()));
+        }
+        for undo in self.undo.drain(..steps) {
+            // `validate` keeps keys unique within a batch, so its entries are
+            // independent. Batches drain newest first, so a key touched by
+            // several of them ends in its state before the oldest one.
+            for (key, was_present) in undo {
+                if was_present { self.present.insert(key); } else { self.present.remove(&key); }
+            }
+        }
+        if steps > 0 {
+            self.current_root = self.recent_roots[steps - 1];
+            self.recent_roots.drain(..steps);
+            self.history.truncate(self.history.len().saturating_sub(steps as u64));
+        }
+        Ok(())
+    }
+
+    /// Whether a key hash is fresh within the whole window: absent under
+    /// `current_root` and under every root in `recent_roots`.
+    ///
+    /// Each retained state is evaluated and OR-ed with `subtle::Choice`, with
+    /// no early exit on a hit, so timing does not reveal which root matched.
+    /// Cost is linear in the window (every undo-log entry is visited).
+    pub fn is_fresh(&self, key_hash: &[u8; 32]) -> bool {
+        use subtle::{ConditionallySelectable, ConstantTimeEq, Choice};
+        let mut state = Choice::from(self.present.contains(key_hash) as u8);
+        let mut seen = state;
+        // Walk back one batch at a time; `state` becomes membership under the prior root.
+        for undo in &self.undo {
+            for (key, was_present) in undo.iter().rev() {
+                let hit = key[..].ct_eq(&key_hash[..]);
+                state = Choice::conditional_select(&state, &Choice::from(*was_present as u8), hit);
+            }
+            seen |= state;
+        }
+        !bool::from(seen)
+    }
+
+    /// Root of the MMR committing to every historical root, oldest first.
+    pub fn history_root(&self) -> [u8; 32] { self.history.root() }
+
+    /// Inclusion proof that `root` was a historical window root, checkable
+    /// against `history_root()` with `MmrProof::verify(.., &root.0)`.
+    pub fn prove_historical_root(&self, root: &Root) -> Option<MmrProof> {
+        self.history.prove(self.history.position(&root.0)?)
     }
 
     /// Returns the maximum number of historical roots retained.
@@ -180,6 +386,23 @@ mod tests {
         assert_eq!(enc.len(), 8 + 2 * (32 + 1));
     }
 
+    #[test]
+    fn batch_byte_layout_is_pinned() {
+        // Item count is a little-endian u64, unlike the big-endian prefixes in
+        // `primitives::encode`; window roots hash these exact bytes.
+        let batch = BatchUpdate(vec![
+            BatchItem { key_hash: [0xaa; 32], present: true },
+            BatchItem { key_hash: [0xbb; 32], present: false },
+        ]);
+        let mut expected = vec![2, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(&[0xaa; 32]);
+        expected.push(1);
+        expected.extend_from_slice(&[0xbb; 32]);
+        expected.push(0);
+        assert_eq!(ser::serialize_batch(&batch), expected);
+        assert_eq!(ser::serialize_batch(&BatchUpdate(vec![])), [0u8; 8]);
+    }
+
     #[test]
     fn poly_roots_and_eval_match() {
         use ff::Field;
@@ -200,4 +423,146 @@ mod tests {
         let bytes = encode_point(&c);
         assert!(bytes.iter().any(|&b| b != 0));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn window_rollback_restores_freshness() {
+        let batch = |k: u8| BatchUpdate(vec![BatchItem { key_hash: [k; 32], present: true }]);
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        let r1 = w.apply_batch(&batch(1)).unwrap();
+        w.apply_batch(&batch(2)).unwrap();
+        w.apply_batch(&batch(3)).unwrap();
+        assert!(!w.is_fresh(&[2u8; 32]) && !w.is_fresh(&[3u8; 32]));
+
+        w.rollback(2).unwrap();
+        assert_eq!(w.current_root, r1);
+        assert_eq!(w.window_len(), 2);
+        assert!(w.is_fresh(&[2u8; 32]) && w.is_fresh(&[3u8; 32]));
+        assert!(!w.is_fresh(&[1u8; 32]));
+        assert!(w.rollback(2).is_err());
+    }
+
+    #[test]
+    fn window_checks_every_retained_root() {
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        w.apply_batch(&BatchUpdate(vec![BatchItem { key_hash: [5u8; 32], present: true }])).unwrap();
+        w.apply_batch(&BatchUpdate(vec![BatchItem { key_hash: [5u8; 32], present: false }])).unwrap();
+        w.apply_batch(&BatchUpdate(vec![BatchItem { key_hash: [6u8; 32], present: true }])).unwrap();
+        // [5; 32] is only present under an older recent root.
+        assert!(!w.is_fresh(&[5u8; 32]));
+        assert!(!w.is_fresh(&[6u8; 32]));
+        assert!(w.is_fresh(&[7u8; 32]));
+    }
+
+    #[test]
+    fn window_proves_historical_root() {
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        let mut roots = vec![w.current_root];
+        for i in 0..5u8 {
+            roots.push(w.apply_batch(&BatchUpdate(vec![BatchItem { key_hash: [i; 32], present: true }])).unwrap());
+        }
+        let history = w.history_root();
+        // roots[0..5] are historical; the current root is not in the history yet.
+        for (i, r) in roots[..5].iter().enumerate() {
+            let proof = w.prove_historical_root(r).unwrap();
+            assert_eq!(proof.leaf_index, i as u64);
+            assert!(proof.verify(&history, &r.0));
+            assert!(!proof.verify(&history, &roots[5].0));
+        }
+        assert!(w.prove_historical_root(&roots[5]).is_none());
+
+        let proof = w.prove_historical_root(&roots[1]).unwrap();
+        w.rollback(2).unwrap();
+        assert_ne!(w.history_root(), history);
+        assert!(!proof.verify(&w.history_root(), &roots[1].0));
+        assert!(w.prove_historical_root(&roots[4]).is_none());
+        let again = w.prove_historical_root(&roots[1]).unwrap();
+        assert!(again.verify(&w.history_root(), &roots[1].0));
+    }
+
+    #[test]
+    fn apply_batch_rejects_invalid_batches() {
+        let item = |k: u8| BatchItem { key_hash: [k; 32], present: true };
+        let dup = BatchUpdate(vec![item(1), item(2), item(1)]);
+        let big = BatchUpdate((0..=params::MAX_BATCH_ITEMS as u32).map(|i| {
+            let mut key_hash = [0u8; 32];
+            key_hash[28..].copy_from_slice(&i.to_be_bytes());
+            BatchItem { key_hash, present: true }
+        }).collect());
+        let too_large = Err(SmaError::BatchTooLarge { len: params::MAX_BATCH_ITEMS + 1, max: params::MAX_BATCH_ITEMS });
+
+        let mut sma = MerkleSMA::new(16);
+        let before = sma.root();
+        assert_eq!(sma.apply_batch(&dup), Err(SmaError::DuplicateKey([1u8; 32])));
+        assert_eq!(sma.apply_batch(&big), too_large);
+        assert_eq!(sma.root(), before);
+
+        // [0x01, 0x01, ..] and [0x01, 0x01, 0xff, ..] share the top 16 bits.
+        let mut other = [1u8; 32];
+        other[2] = 0xff;
+        sma.apply_batch(&BatchUpdate(vec![item(1)])).unwrap();
+        let root = sma.root();
+        let clash = BatchUpdate(vec![item(7), BatchItem { key_hash: other, present: true }]);
+        assert_eq!(sma.apply_batch(&clash), Err(SmaError::PositionCollision { position: 0x0101 }));
+        assert_eq!(sma.root(), root);
+
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        assert_eq!(w.apply_batch(&dup), Err(SmaError::DuplicateKey([1u8; 32])));
+        assert_eq!(w.apply_batch(&big), too_large);
+        assert_eq!((w.current_root, w.window_len()), (Root::default(), 1));
+        assert!(w.is_fresh(&[2u8; 32]));
+    }
+
+    #[test]
+    fn reorg_sized_window_rolls_back_to_its_depth() {
+        let mut w = NullifierSMAWindow::for_reorg_depth(params::ACCUM_HEIGHT, Root([1u8; 32]), 10);
+        assert_eq!(w.max_window, Some(11));
+        let mut roots = vec![w.current_root];
+        for i in 0u8..15 {
+            w.apply_batch(&BatchUpdate::inserts(&[[i; 32]])).unwrap();
+            roots.push(w.current_root);
+        }
+        assert_eq!(w.window_len(), 11);
+        assert!(w.clone().rollback(11).is_err());
+        // Forgetting old batches keeps their keys; only their undo entries go.
+        assert!(!w.is_fresh(&[0u8; 32]));
+        w.rollback(10).unwrap();
+        assert_eq!(w.current_root, roots[5]);
+        assert!(!w.is_fresh(&[4u8; 32]) && w.is_fresh(&[5u8; 32]));
+        let unbounded = NullifierSMAWindow::for_reorg_depth(params::ACCUM_HEIGHT, Root::default(), usize::MAX);
+        assert_eq!(unbounded.max_window, Some(usize::MAX));
+    }
+
+    #[test]
+    fn batch_inserts_are_sorted_and_deduped() {
+        let keys = [[9u8; 32], [1u8; 32], [5u8; 32], [1u8; 32]];
+        let batch = BatchUpdate::inserts(&keys);
+        let sorted: Vec<[u8; 32]> = batch.0.iter().map(|it| it.key_hash).collect();
+        assert_eq!(sorted, vec![[1u8; 32], [5u8; 32], [9u8; 32]]);
+        assert!(batch.0.iter().all(|it| it.present));
+        assert!(batch.validate().is_ok());
+        assert!(BatchUpdate::deletions(&keys).0.iter().all(|it| !it.present));
+        assert_eq!(BatchUpdate::deletions(&keys).0.len(), 3);
+
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        w.apply_batch(&BatchUpdate::inserts(&keys)).unwrap();
+        assert!(!w.is_fresh(&[5u8; 32]));
+    }
+
+    #[test]
+    fn window_transition_recomputes_new_root() {
+        let mut w = NullifierSMAWindow::new(params::ACCUM_HEIGHT, Root::default());
+        w.apply_batch(&BatchUpdate::inserts(&[[3u8; 32]])).unwrap();
+        let batch = BatchUpdate::inserts(&[[7u8; 32], [1u8; 32]]);
+
+        let (old, new) = w.prove_transition(&batch).unwrap();
+        assert_eq!(old, w.current_root);
+        assert_eq!(w.apply_batch(&batch).unwrap(), new);
+        assert!(NullifierSMAWindow::verify_transition(&old, &batch, &new));
+
+        assert!(!NullifierSMAWindow::verify_transition(&old, &batch, &Root([9u8; 32])));
+        assert!(!NullifierSMAWindow::verify_transition(&new, &batch, &new));
+        assert!(!NullifierSMAWindow::verify_transition(&old, &BatchUpdate::inserts(&[[7u8; 32]]), &new));
+        let dup = BatchUpdate(vec![batch.0[0].clone(), batch.0[0].clone()]);
+        assert_eq!(w.prove_transition(&dup), Err(SmaError::DuplicateKey(batch.0[0].key_hash)));
+    }
+}
@@ -0,0 +1,79 @@
+//! Count-bound Merkle trees over `poseidon::compress_nodes`.
+//!
+//! Leaves are padded with zeros to a power of two (at least one) and the root
+//! is `compress(leaf_count, tree root)`, so zero padding is never mistaken for
+//! a leaf. Used for the sorted txid set of an aggregate and the per-block
+//! commitment delta.
+
+use crate::poseidon::compress_nodes;
+
+/// Tree levels over `leaves`, leaves first; the last level holds the tree root.
+pub fn levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut level = leaves.to_vec();
+    level.resize(leaves.len().next_power_of_two(), [0u8; 32]);
+    let mut levels = vec![level];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1].chunks(2).map(|p| compress_nodes(&p[0], &p[1])).collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root = compress(leaf_count as 32 LE bytes, tree root).
+pub fn bind_leaf_count(leaf_count: u64, tree_root: &[u8; 32]) -> [u8; 32] {
+    let mut count = [0u8; 32];
+    count[..8].copy_from_slice(&leaf_count.to_le_bytes());
+    compress_nodes(&count, tree_root)
+}
+
+/// Count-bound root of `levels` built over `leaf_count` leaves.
+pub fn levels_root(levels: &[Vec<[u8; 32]>], leaf_count: u64) -> [u8; 32] {
+    bind_leaf_count(leaf_count, &levels[levels.len() - 1][0])
+}
+
+/// Count-bound root over `leaves`.
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    levels_root(&levels(leaves), leaves.len() as u64)
+}
+
+/// Siblings of leaf `index` from the leaf up to the tree root.
+pub fn siblings(levels: &[Vec<[u8; 32]>], index: usize) -> Vec<[u8; 32]> {
+    levels[..levels.len() - 1].iter().enumerate().map(|(h, level)| level[(index >> h) ^ 1]).collect()
+}
+
+/// Whether `leaf` sits at `index` of a `leaf_count`-leaf tree with count-bound
+/// root `root`. Rejects out-of-range indices and sibling paths of the wrong depth.
+pub fn verify(root: &[u8; 32], leaf: &[u8; 32], index: u64, leaf_count: u64, siblings: &[[u8; 32]]) -> bool {
+    if index >= leaf_count { return false; }
+    let depth = leaf_count.next_power_of_two().trailing_zeros() as usize;
+    if siblings.len() != depth { return false; }
+    let mut node = *leaf;
+    let mut idx = index;
+    for sib in siblings {
+        node = if idx & 1 == 1 { compress_nodes(sib, &node) } else { compress_nodes(&node, sib) };
+        idx >>= 1;
+    }
+    bind_leaf_count(leaf_count, &node) == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_verify_and_padding_is_not_a_leaf() {
+        let leaves: Vec<[u8; 32]> = (1..=5u8).map(|i| [i; 32]).collect();
+        let levels = levels(&leaves);
+        let r = root(&leaves);
+        assert_eq!(levels_root(&levels, 5), r);
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert!(verify(&r, leaf, i as u64, 5, &siblings(&levels, i)));
+        }
+        // Padding slot 5 is out of range, and a trailing zero leaf changes the root.
+        assert!(!verify(&r, &[0u8; 32], 5, 5, &siblings(&levels, 5)));
+        let mut padded = leaves.clone();
+        padded.push([0u8; 32]);
+        assert_ne!(root(&padded), r);
+        assert!(!verify(&r, &leaves[0], 0, 5, &siblings(&levels, 0)[1..]));
+    }
+}
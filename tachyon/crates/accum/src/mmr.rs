@@ -0,0 +1,223 @@
+//! Append-only Merkle Mountain Range over 32-byte leaves.
+//!
+//! Peaks are perfect binary trees in decreasing size order; the root binds the
+//! leaf count and the right-to-left bagged peaks. Leaves and subtree nodes are
+//! kept so the range can be truncated (for reorgs) and proofs produced for any
+//! position without rehashing.
+
+use serde::{Deserialize, Serialize};
+
+use crate::prf::blake2b_32;
+
+const DS_MMR_LEAF_V1: &[u8; 16] = b"tachyon.mmr.leaf"; // 16
+const DS_MMR_NODE_V1: &[u8; 16] = b"tachyon.mmr.node"; // 16
+const DS_MMR_ROOT_V1: &[u8; 16] = b"tachyon.mmr.root"; // 16
+
+fn hash_leaf(leaf: &[u8; 32]) -> [u8; 32] { blake2b_32(DS_MMR_LEAF_V1, leaf) }
+
+fn hash_node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut m = [0u8; 64];
+    m[..32].copy_from_slice(left);
+    m[32..].copy_from_slice(right);
+    blake2b_32(DS_MMR_NODE_V1, &m)
+}
+
+/// Heights of the peaks for `count` leaves, largest first.
+fn peak_heights(count: u64) -> Vec<u32> {
+    (0..64).rev().filter(|h| (count >> h) & 1 == 1).collect()
+}
+
+fn bag_root(count: u64, peaks: &[[u8; 32]]) -> [u8; 32] {
+    let mut bag = [0u8; 32];
+    if let Some((last, rest)) = peaks.split_last() {
+        bag = *last;
+        for p in rest.iter().rev() { bag = hash_node(p, &bag); }
+    }
+    let mut m = [0u8; 40];
+    m[..8].copy_from_slice(&count.to_be_bytes());
+    m[8..].copy_from_slice(&bag);
+    blake2b_32(DS_MMR_ROOT_V1, &m)
+}
+
+/// Inclusion proof for one leaf of an `Mmr` with `leaf_count` leaves.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct MmrProof {
+    pub leaf_index: u64,
+    pub leaf_count: u64,
+    /// Siblings from the leaf up to its peak.
+    pub siblings: Vec<[u8; 32]>,
+    /// All peaks, largest first.
+    pub peaks: Vec<[u8; 32]>,
+}
+
+impl MmrProof {
+    /// Check that `leaf` sits at `leaf_index` under MMR root `root`.
+    pub fn verify(&self, root: &[u8; 32], leaf: &[u8; 32]) -> bool {
+        if self.leaf_index >= self.leaf_count { return false; }
+        let heights = peak_heights(self.leaf_count);
+        if heights.len() != self.peaks.len() { return false; }
+        // Locate the peak covering `leaf_index`.
+        let mut start = 0u64;
+        let mut target = None;
+        for (i, h) in heights.iter().enumerate() {
+            let size = 1u64 << h;
+            if self.leaf_index < start + size { target = Some((i, *h, self.leaf_index - start)); break; }
+            start += size;
+        }
+        let (peak_idx, height, mut offset) = match target { Some(t) => t, None => return false };
+        if self.siblings.len() != height as usize { return false; }
+        let mut node = hash_leaf(leaf);
+        for sib in &self.siblings {
+            node = if offset & 1 == 1 { hash_node(sib, &node) } else { hash_node(&node, sib) };
+            offset >>= 1;
+        }
+        node == self.peaks[peak_idx] && bag_root(self.leaf_count, &self.peaks) == *root
+    }
+}
+
+/// Merkle Mountain Range keeping its leaves and every perfect-subtree node,
+/// so `append` is amortized O(1) hashes and `root`/`prove` are O(log n).
+/// Serializes as its leaves; the nodes are rebuilt on deserialization.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "MmrLeaves", into = "MmrLeaves")]
+pub struct Mmr {
+    leaves: Vec<[u8; 32]>,
+    /// `nodes[h][i]`: root of the `i`-th perfect subtree of height `h`, over
+    /// leaves `i·2^h .. (i+1)·2^h`. Level 0 holds the hashed leaves.
+    nodes: Vec<Vec<[u8; 32]>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MmrLeaves {
+    leaves: Vec<[u8; 32]>,
+}
+
+impl From<MmrLeaves> for Mmr {
+    fn from(repr: MmrLeaves) -> Self {
+        let mut mmr = Mmr::new();
+        for leaf in repr.leaves { mmr.append(leaf); }
+        mmr
+    }
+}
+
+impl From<Mmr> for MmrLeaves {
+    fn from(mmr: Mmr) -> Self { Self { leaves: mmr.leaves } }
+}
+
+impl Mmr {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn len(&self) -> u64 { self.leaves.len() as u64 }
+
+    pub fn is_empty(&self) -> bool { self.leaves.is_empty() }
+
+    /// Append a leaf and return its index.
+    pub fn append(&mut self, leaf: [u8; 32]) -> u64 {
+        self.leaves.push(leaf);
+        // Carry up while the level has a complete pair, like a binary counter.
+        let mut node = hash_leaf(&leaf);
+        let mut h = 0;
+        loop {
+            if self.nodes.len() == h { self.nodes.push(Vec::new()); }
+            let level = &mut self.nodes[h];
+            level.push(node);
+            if level.len() % 2 == 1 { break; }
+            node = hash_node(&level[level.len() - 2], &level[level.len() - 1]);
+            h += 1;
+        }
+        self.len() - 1
+    }
+
+    /// Drop leaves beyond the first `len` (no-op if already shorter).
+    pub fn truncate(&mut self, len: u64) {
+        self.leaves.truncate(len as usize);
+        let len = self.len();
+        for (h, level) in self.nodes.iter_mut().enumerate() { level.truncate((len >> h) as usize); }
+        while self.nodes.last().is_some_and(Vec::is_empty) { self.nodes.pop(); }
+    }
+
+    /// Leaf at `index`, if present.
+    pub fn leaf(&self, index: u64) -> Option<&[u8; 32]> { self.leaves.get(index as usize) }
+
+    /// Index of the most recent occurrence of `leaf`.
+    pub fn position(&self, leaf: &[u8; 32]) -> Option<u64> {
+        self.leaves.iter().rposition(|l| l == leaf).map(|i| i as u64)
+    }
+
+    /// (first leaf, height, root) of each peak, largest first.
+    fn peaks(&self) -> Vec<(u64, u32, [u8; 32])> {
+        let mut start = 0u64;
+        peak_heights(self.len())
+            .into_iter()
+            .map(|h| {
+                let out = (start, h, self.nodes[h as usize][(start >> h) as usize]);
+                start += 1u64 << h;
+                out
+            })
+            .collect()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        let peaks: Vec<[u8; 32]> = self.peaks().into_iter().map(|(_, _, p)| p).collect();
+        bag_root(self.len(), &peaks)
+    }
+
+    /// Inclusion proof for the leaf at `index`.
+    pub fn prove(&self, index: u64) -> Option<MmrProof> {
+        if index >= self.len() { return None; }
+        let peaks = self.peaks();
+        let (_, height, _) = *peaks.iter().find(|(s, h, _)| index < s + (1u64 << h))?;
+        let siblings = (0..height as usize).map(|h| self.nodes[h][((index >> h) ^ 1) as usize]).collect();
+        Some(MmrProof {
+            leaf_index: index,
+            leaf_count: self.len(),
+            siblings,
+            peaks: peaks.into_iter().map(|(_, _, p)| p).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The recompute-everything root, as `Mmr::root` was first written.
+    fn naive_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+        let mut start = 0usize;
+        let peaks: Vec<[u8; 32]> = peak_heights(leaves.len() as u64)
+            .into_iter()
+            .map(|h| {
+                let mut level: Vec<[u8; 32]> = leaves[start..start + (1usize << h)].iter().map(hash_leaf).collect();
+                while level.len() > 1 { level = level.chunks(2).map(|c| hash_node(&c[0], &c[1])).collect(); }
+                start += 1usize << h;
+                level[0]
+            })
+            .collect();
+        bag_root(leaves.len() as u64, &peaks)
+    }
+
+    #[test]
+    fn incremental_nodes_match_naive_root_and_survive_truncate() {
+        let leaves: Vec<[u8; 32]> = (0..40u8).map(|i| [i; 32]).collect();
+        let mut mmr = Mmr::new();
+        assert_eq!(mmr.root(), naive_root(&[]));
+        for (n, leaf) in leaves.iter().enumerate() {
+            mmr.append(*leaf);
+            let root = mmr.root();
+            assert_eq!(root, naive_root(&leaves[..=n]));
+            for i in 0..=n as u64 {
+                assert!(mmr.prove(i).unwrap().verify(&root, &leaves[i as usize]));
+            }
+        }
+
+        let mut cut = mmr.clone();
+        cut.truncate(21);
+        let mut fresh = Mmr::new();
+        for leaf in &leaves[..21] { fresh.append(*leaf); }
+        assert_eq!(cut, fresh);
+        assert_eq!(cut.root(), naive_root(&leaves[..21]));
+
+        let json = serde_json::to_string(&mmr).unwrap();
+        assert_eq!(serde_json::from_str::<Mmr>(&json).unwrap(), mmr);
+    }
+}
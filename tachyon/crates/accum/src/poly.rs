@@ -2,6 +2,9 @@
 //!
 //! - Convert monic polynomial defined by its roots into coefficient vector.
 //! - Evaluate polynomial at a point using Horner's method.
+//! - Merge canonically sorted root sets.
+
+use std::cmp::Ordering;
 
 use pasta_curves::vesta::Scalar as FrVesta;
 use rayon::prelude::*;
@@ -29,6 +32,8 @@ pub fn roots_to_coeffs(roots: &[FrVesta]) -> Vec<FrVesta> {
 }
 
 /// Evaluate polynomial with coefficients c[0..=k] at point x using Horner's method.
+/// Runs one field multiply and add per coefficient whatever `x` is, so it is
+/// constant-time in `x` (pasta field arithmetic does not branch on values).
 pub fn eval_horner(coeffs: &[FrVesta], x: FrVesta) -> FrVesta {
     let mut acc = FrVesta::ZERO;
     for &c in coeffs.iter().rev() {
@@ -42,6 +47,31 @@ pub fn eval_from_roots(roots: &[FrVesta], x: FrVesta) -> FrVesta {
     roots.iter().fold(FrVesta::ONE, |acc, a| acc * (x - *a))
 }
 
+/// Canonical consensus order on roots: ascending integer value of the
+/// little-endian `to_repr()` encoding.
+pub fn canonical_root_cmp(a: &FrVesta, b: &FrVesta) -> Ordering {
+    a.to_repr().iter().rev().cmp(b.to_repr().iter().rev())
+}
+
+/// Merge two root slices into one sorted deduped vector in O(n + m). Each
+/// input must already be sorted by canonical byte order (`canonical_root_cmp`,
+/// as the publisher's `canonical_sort_roots` does) and deduped; otherwise the
+/// result is unspecified.
+pub fn merge_sorted_roots(a: &[FrVesta], b: &[FrVesta]) -> Vec<FrVesta> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match canonical_root_cmp(&a[i], &b[j]) {
+            Ordering::Less => { out.push(a[i]); i += 1; }
+            Ordering::Greater => { out.push(b[j]); j += 1; }
+            Ordering::Equal => { out.push(a[i]); i += 1; j += 1; }
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
 /// Pad coefficient vector to a target length with zeros (no-op if already longer).
 pub fn pad_coeffs_to(coeffs: &mut Vec<FrVesta>, target_len: usize) {
     if coeffs.len() < target_len {
@@ -49,6 +79,22 @@ pub fn pad_coeffs_to(coeffs: &mut Vec<FrVesta>, target_len: usize) {
     }
 }
 
+/// Coefficient equality without an early exit: every position up to the
+/// longer length is compared via `subtle` over its repr, the shorter vector
+/// read as zero-padded (so trailing zeros do not matter). Use it when either
+/// side derives from wallet secrets; public block polynomials can use `==`.
+/// Only the lengths leak through timing.
+pub fn ct_eq_coeffs(a: &[FrVesta], b: &[FrVesta]) -> bool {
+    use subtle::{Choice, ConstantTimeEq};
+    let zero = FrVesta::ZERO;
+    let mut eq = Choice::from(1u8);
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(i).unwrap_or(&zero), b.get(i).unwrap_or(&zero));
+        eq &= x.to_repr().as_ref().ct_eq(y.to_repr().as_ref());
+    }
+    bool::from(eq)
+}
+
 fn convolve(a: &[FrVesta], b: &[FrVesta]) -> Vec<FrVesta> {
     let mut out = vec![FrVesta::ZERO; a.len() + b.len() - 1];
     for (i, &ai) in a.iter().enumerate() {
@@ -59,9 +105,12 @@ fn convolve(a: &[FrVesta], b: &[FrVesta]) -> Vec<FrVesta> {
     out
 }
 
+/// Below this many roots the divide-and-conquer recursion stops spawning rayon
+/// tasks and uses the naive product; splitting further costs more than it saves.
+pub const PARALLEL_SEQ_CUTOFF: usize = 256;
+
 fn roots_to_coeffs_divide_conquer(roots: &[FrVesta]) -> Vec<FrVesta> {
-    if roots.is_empty() { return vec![FrVesta::ONE]; }
-    if roots.len() == 1 { return vec![-roots[0], FrVesta::ONE]; }
+    if roots.len() < PARALLEL_SEQ_CUTOFF { return roots_to_coeffs(roots); }
     let mid = roots.len() / 2;
     let (left, right) = roots.split_at(mid);
     let (a, b) = rayon::join(|| roots_to_coeffs_divide_conquer(left), || roots_to_coeffs_divide_conquer(right));
@@ -96,13 +145,12 @@ fn fft_in_place(a: &mut [FrVesta], omega: FrVesta) {
         if i < j { a.swap(i, j); }
     }
     let mut len = 2;
-    let mut w_m = omega;
     while len <= n {
         let half = len / 2;
+        // Primitive len-th root of unity for this stage.
+        let w_len = omega.pow_vartime([(n / len) as u64]);
         let mut w = FrVesta::ONE;
         for j in 0..half {
-            let step = j * (n / len);
-            if j == 0 { w = FrVesta::ONE; } else { w *= w_m; }
             let mut i = j;
             while i < n {
                 let u = a[i];
@@ -111,8 +159,8 @@ fn fft_in_place(a: &mut [FrVesta], omega: FrVesta) {
                 a[i + half] = u - v;
                 i += len;
             }
+            w *= w_len;
         }
-        w_m = w_m * w_m; // square root progression
         len <<= 1;
     }
 }
@@ -124,22 +172,32 @@ fn ifft_in_place(a: &mut [FrVesta], omega_inv: FrVesta) {
     for v in a.iter_mut() { *v *= n_inv; }
 }
 
-#[inline]
-fn omega_for_size(n: usize) -> (FrVesta, FrVesta) {
+/// FFT domain larger than the field's two-adicity allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("FFT size 2^{lg_n} exceeds the 2^{max} roots of unity in Fr(Vesta)")]
+pub struct FftSizeError {
+    pub lg_n: u32,
+    pub max: u32,
+}
+
+/// Primitive n-th root of unity and its inverse for a power-of-two `n`, or an
+/// error when `n` exceeds `2^S` (S = 32 for Vesta).
+pub fn omega_for_size(n: usize) -> Result<(FrVesta, FrVesta), FftSizeError> {
     // ROOT_OF_UNITY is 2^S primitive root; need omega = root^(2^{S - log2(n)})
     let s_total: u32 = pasta_curves::vesta::Scalar::S;
-    let lg_n = n.trailing_zeros() as u32;
+    let lg_n = n.trailing_zeros();
+    if lg_n > s_total { return Err(FftSizeError { lg_n, max: s_total }); }
     let pow = 1u64 << (s_total - lg_n);
     let root = pasta_curves::vesta::Scalar::ROOT_OF_UNITY;
-    let omega = root.pow_vartime(&[pow]);
+    let omega = root.pow_vartime([pow]);
     let omega_inv = omega.invert().unwrap();
-    (omega, omega_inv)
+    Ok((omega, omega_inv))
 }
 
-fn convolution_fft(a: &[FrVesta], b: &[FrVesta]) -> Vec<FrVesta> {
+fn convolution_fft(a: &[FrVesta], b: &[FrVesta]) -> Result<Vec<FrVesta>, FftSizeError> {
     let needed = a.len() + b.len() - 1;
     let n = needed.next_power_of_two();
-    let (omega, omega_inv) = omega_for_size(n);
+    let (omega, omega_inv) = omega_for_size(n)?;
     let mut fa = vec![FrVesta::ZERO; n];
     let mut fb = vec![FrVesta::ZERO; n];
     fa[..a.len()].copy_from_slice(a);
@@ -149,31 +207,146 @@ fn convolution_fft(a: &[FrVesta], b: &[FrVesta]) -> Vec<FrVesta> {
     for i in 0..n { fa[i] *= fb[i]; }
     ifft_in_place(&mut fa, omega_inv);
     fa.truncate(needed);
-    fa
+    Ok(fa)
 }
 
 /// FFT-accelerated coefficient generation using product tree + NTT convolution.
-pub fn roots_to_coeffs_fft(roots: &[FrVesta]) -> Vec<FrVesta> {
-    if roots.is_empty() { return vec![FrVesta::ONE]; }
+/// Errors if a product needs an FFT domain beyond the field's two-adicity.
+pub fn roots_to_coeffs_fft(roots: &[FrVesta]) -> Result<Vec<FrVesta>, FftSizeError> {
+    if roots.is_empty() { return Ok(vec![FrVesta::ONE]); }
     // Build leaves: (X - r)
     let mut polys: Vec<Vec<FrVesta>> = roots.iter().map(|&r| vec![-r, FrVesta::ONE]).collect();
     while polys.len() > 1 {
         let mut next = Vec::with_capacity((polys.len() + 1) / 2);
         for chunk in polys.chunks(2) {
             if chunk.len() == 2 {
-                next.push(convolution_fft(&chunk[0], &chunk[1]));
+                next.push(convolution_fft(&chunk[0], &chunk[1])?);
             } else {
                 next.push(chunk[0].clone());
             }
         }
         polys = next;
     }
-    polys.pop().unwrap()
+    Ok(polys.pop().unwrap())
+}
+
+/// Below this many roots `roots_to_coeffs_auto` uses the naive product.
+pub const AUTO_NAIVE_MAX: usize = 32;
+/// Up to this many roots `roots_to_coeffs_auto` uses divide-and-conquer; FFT
+/// above. The product-tree FFT only overtakes it past ~2k roots.
+pub const AUTO_PARALLEL_MAX: usize = 2048;
+
+/// Coefficients via whichever method is fastest for `roots.len()`; crossovers
+/// are `AUTO_NAIVE_MAX` and `AUTO_PARALLEL_MAX` (see `examples/poly_crossover.rs`).
+/// Falls back to divide-and-conquer if the FFT domain would be too large.
+pub fn roots_to_coeffs_auto(roots: &[FrVesta]) -> Vec<FrVesta> {
+    if roots.len() < AUTO_NAIVE_MAX {
+        roots_to_coeffs(roots)
+    } else if roots.len() <= AUTO_PARALLEL_MAX {
+        roots_to_coeffs_parallel(roots)
+    } else {
+        roots_to_coeffs_fft(roots).unwrap_or_else(|_| roots_to_coeffs_parallel(roots))
+    }
 }
 
 /// Batch FFT coefficient generation.
-pub fn batch_roots_to_coeffs_fft(batches: &[Vec<FrVesta>]) -> Vec<Vec<FrVesta>> {
+pub fn batch_roots_to_coeffs_fft(batches: &[Vec<FrVesta>]) -> Result<Vec<Vec<FrVesta>>, FftSizeError> {
     batches.par_iter().map(|r| roots_to_coeffs_fft(r)).collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::{RngCore, SeedableRng};
+
+    #[test]
+    fn roots_to_coeffs_auto_matches_naive() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x9e37_79b9_7f4a_7c15);
+        // AUTO_PARALLEL_MAX + 1 takes the FFT path.
+        let mut sizes = vec![0, 1, AUTO_NAIVE_MAX - 1, AUTO_NAIVE_MAX, 2048, AUTO_PARALLEL_MAX + 1];
+        sizes.extend((0..6).map(|_| (rng.next_u64() % 2049) as usize));
+        for n in sizes {
+            let roots: Vec<FrVesta> = (0..n).map(|_| FrVesta::random(&mut rng)).collect();
+            let naive = roots_to_coeffs(&roots);
+            assert_eq!(roots_to_coeffs_auto(&roots), naive, "auto, n = {}", n);
+            assert_eq!(roots_to_coeffs_parallel(&roots), naive, "parallel, n = {}", n);
+            if n <= 300 { assert_eq!(roots_to_coeffs_fft(&roots).unwrap(), naive, "fft, n = {}", n); }
+        }
+    }
 
+    #[test]
+    fn ct_eq_coeffs_agrees_with_eq() {
+        use ff::Field;
+        use pasta_curves::vesta::Scalar as Fr;
+        let a: Vec<Fr> = (1u64..=4).map(Fr::from).collect();
+        let mut b = a.clone();
+        assert!(ct_eq_coeffs(&a, &b) && a == b);
+        b[2] += Fr::ONE;
+        assert!(!ct_eq_coeffs(&a, &b) && a != b);
+        assert!(!ct_eq_coeffs(&a, &a[..3]) && !ct_eq_coeffs(&a[..3], &a));
+        // Zero padding: differing lengths compare equal only up to trailing zeros.
+        let mut padded = a.clone();
+        padded.resize(7, Fr::ZERO);
+        assert!(ct_eq_coeffs(&a, &padded) && ct_eq_coeffs(&padded, &a));
+        assert!(ct_eq_coeffs(&[], &[]) && ct_eq_coeffs(&[], &[Fr::ZERO]));
+    }
+
+    #[test]
+    fn parallel_cutoff_matches_naive() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0x2545_f491_4f6c_dd1d);
+        // Either side of the cutoff, and sizes that split into uneven halves.
+        let mut sizes = vec![PARALLEL_SEQ_CUTOFF - 1, PARALLEL_SEQ_CUTOFF, 2 * PARALLEL_SEQ_CUTOFF + 1];
+        sizes.extend((0..6).map(|_| (rng.next_u64() % 1200) as usize));
+        for n in sizes {
+            let roots: Vec<FrVesta> = (0..n).map(|_| FrVesta::random(&mut rng)).collect();
+            assert_eq!(roots_to_coeffs_parallel(&roots), roots_to_coeffs(&roots), "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn batch_parallel_matches_naive_across_cutoff() {
+        // 128-root batches stay sequential inside; 512-root batches split.
+        let batches: Vec<Vec<FrVesta>> = [128u64, 512, 128, 512]
+            .iter()
+            .enumerate()
+            .map(|(b, &n)| (0..n).map(|i| FrVesta::from(b as u64 * 1000 + i + 1)).collect())
+            .collect();
+        let naive: Vec<Vec<FrVesta>> = batches.iter().map(|r| roots_to_coeffs(r)).collect();
+        assert_eq!(batch_roots_to_coeffs_parallel(&batches), naive);
+    }
+
+    #[test]
+    fn oversized_fft_domain_errors() {
+        use ff::Field;
+        assert!(omega_for_size(1 << 32).is_ok());
+        assert_eq!(omega_for_size(1 << 33).unwrap_err(), FftSizeError { lg_n: 33, max: 32 });
+        let (omega, omega_inv) = omega_for_size(8).unwrap();
+        assert_eq!(omega.pow_vartime([8u64]), FrVesta::ONE);
+        assert_eq!(omega * omega_inv, FrVesta::ONE);
+    }
+
+    #[test]
+    fn merge_sorted_roots_equals_sorted_union() {
+        use ff::Field;
+        let sorted = |xs: &[u64]| {
+            let mut v: Vec<FrVesta> = xs.iter().map(|&x| FrVesta::from(x)).collect();
+            v.sort_by(canonical_root_cmp);
+            v.dedup();
+            v
+        };
+        // 256 and 300 differ in the low byte order, -1 is the largest value.
+        let mut a = sorted(&[1, 5, 256, 300, 9000]);
+        let mut b = sorted(&[0, 5, 7, 300, 1 << 40]);
+        a.push(-FrVesta::ONE);
+        b.push(-FrVesta::ONE);
+
+        let mut union: Vec<FrVesta> = a.iter().chain(&b).copied().collect();
+        union.sort_by(canonical_root_cmp);
+        union.dedup();
+        assert_eq!(merge_sorted_roots(&a, &b), union);
+        assert_eq!(merge_sorted_roots(&b, &a), union);
+        assert_eq!(merge_sorted_roots(&a, &[]), a);
+        assert!(merge_sorted_roots(&[], &[]).is_empty());
+    }
+}
@@ -5,12 +5,13 @@
 //! halo2_gadgets Poseidon chips.
 
 use blake2b_simd::Params as Blake2bParams;
+use crate::prf;
 use pasta_curves::vesta::Scalar as FrVesta;
 use ff::FromUniformBytes;
 
-const DOM_A_H: &[u8] = b"tachyon:A/h";       // 12
-const DOM_S_H: &[u8] = b"tachyon:S/h";       // 12
-const DOM_BLOCK_R: &[u8] = b"tachyon:block:r"; // 16
+const DOM_A_H: &[u8; 16] = b"tachyon:A/h\0\0\0\0\0"; // 11 + 5 = 16
+const DOM_S_H: &[u8; 16] = b"tachyon:S/h\0\0\0\0\0"; // 11 + 5 = 16
+const DOM_BLOCK_R: &[u8; 16] = b"tachyon:block:r\0"; // 15 + 1 = 16
 
 /// Hash 64 bytes to a Pasta field element (Vesta scalar) and return its 32-byte LE repr.
 pub fn hash64_to32(input: &[u8; 64]) -> [u8; 32] {
@@ -33,15 +34,104 @@ pub fn compress_nodes(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
     hash64_to32(&input)
 }
 
+/// Field form of `compress_nodes`: the parent of two node elements.
+pub fn compress_field(left: &FrVesta, right: &FrVesta) -> FrVesta {
+    let repr = compress_nodes(&ff::PrimeField::to_repr(left), &ff::PrimeField::to_repr(right));
+    // `hash64_to32` always returns a canonical repr.
+    Option::from(<FrVesta as ff::PrimeField>::from_repr(repr)).expect("canonical repr")
+}
+
+/// Sponge over Vesta scalars with a configurable width/rate, for benchmarking
+/// parameter choices. Until the Poseidon2 permutation lands the permutation is
+/// the same Blake2b-512 placeholder `compress_nodes` uses: each rate block is
+/// hashed with the previous digest as the single capacity lane (empty for the
+/// first block). That placeholder has no room for more capacity, so widths
+/// other than `rate + 1` are refused until the real permutation lands.
+#[derive(Clone, Debug)]
+pub struct Sponge {
+    width: usize,
+    rate: usize,
+    digest: Option<FrVesta>,
+    pending: Vec<FrVesta>,
+    squeezed: bool,
+}
+
+impl Sponge {
+    /// Sponge with `width` state elements of which `rate` are absorbed per
+    /// permutation. Requires `0 < rate < width`, and for now `width == rate + 1`.
+    pub fn with_params(width: usize, rate: usize) -> anyhow::Result<Self> {
+        if rate == 0 || rate >= width {
+            anyhow::bail!("sponge rate {} must be in 1..{} (width {})", rate, width, width);
+        }
+        if width - rate != 1 {
+            anyhow::bail!("sponge capacity {} unsupported: the placeholder permutation has one capacity lane", width - rate);
+        }
+        Ok(Self { width, rate, digest: None, pending: Vec::with_capacity(rate), squeezed: false })
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn rate(&self) -> usize { self.rate }
+
+    /// Capacity lanes, `width - rate`.
+    pub fn capacity(&self) -> usize { self.width - self.rate }
+
+    pub fn absorb(&mut self, x: &FrVesta) {
+        self.pending.push(*x);
+        self.squeezed = false;
+        if self.pending.len() == self.rate {
+            self.permute();
+        }
+    }
+
+    /// Finish the pending block (zero-padded to the rate) and return the digest.
+    /// Further squeezes permute an empty block on top of the previous digest.
+    pub fn squeeze(&mut self) -> FrVesta {
+        if !self.pending.is_empty() || self.digest.is_none() || self.squeezed {
+            self.permute();
+        }
+        self.squeezed = true;
+        self.digest.expect("permuted")
+    }
+
+    fn permute(&mut self) {
+        self.pending.resize(self.rate, <FrVesta as ff::Field>::ZERO);
+        let mut input = Vec::with_capacity(32 * (self.rate + 1));
+        if let Some(prev) = self.digest {
+            input.extend_from_slice(&ff::PrimeField::to_repr(&prev));
+        }
+        for x in self.pending.drain(..) {
+            input.extend_from_slice(&ff::PrimeField::to_repr(&x));
+        }
+        let hash = Blake2bParams::new().hash_length(64).hash(&input);
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(hash.as_bytes());
+        self.digest = Some(<FrVesta as FromUniformBytes<64>>::from_uniform_bytes(&wide));
+    }
+}
+
+impl Default for Sponge {
+    /// The 2-to-1 configuration `compress_nodes` uses: rate `NODE_ARITY`, one
+    /// capacity lane.
+    fn default() -> Self {
+        let rate = crate::params::NODE_ARITY;
+        Self::with_params(rate + 1, rate).expect("valid default sponge params")
+    }
+}
+
 /// Domain-separated hash for accumulator update: h_i = H_A(A_i, P_i).
 pub fn hash_A_h(a_i: &[u8; 32], p_i: &[u8; 32]) -> [u8; 32] {
     let mut m = [0u8; 64];
     m[..32].copy_from_slice(a_i);
     m[32..].copy_from_slice(p_i);
-    let tag = Blake2bParams::new().hash_length(32).personal(DOM_A_H).hash(&m);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(tag.as_bytes());
-    out
+    prf::blake2b_32(DOM_A_H, &m)
+}
+
+/// The record-level h_i for stepping A_i with block commitment P_i, i.e.
+/// A_{i+1} = [h_i]A_i + P_i. Publishers and record validators both go through
+/// this so they cannot drift apart; it is `hash_A_h` today.
+pub fn compute_h_i(a_i: &[u8; 32], p_i: &[u8; 32]) -> [u8; 32] {
+    hash_A_h(a_i, p_i)
 }
 
 /// Domain-separated hash for secondary accumulator update: h_i' = H_S(S_i, P_i').
@@ -49,10 +139,7 @@ pub fn hash_S_h(s_i: &[u8; 32], p_i_prime: &[u8; 32]) -> [u8; 32] {
     let mut m = [0u8; 64];
     m[..32].copy_from_slice(s_i);
     m[32..].copy_from_slice(p_i_prime);
-    let tag = Blake2bParams::new().hash_length(32).personal(DOM_S_H).hash(&m);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(tag.as_bytes());
-    out
+    prf::blake2b_32(DOM_S_H, &m)
 }
 
 /// Derive evaluation challenge r from block commitment and accumulator state.
@@ -60,8 +147,37 @@ pub fn derive_block_r(p_i: &[u8; 32], a_i: &[u8; 32]) -> [u8; 32] {
     let mut m = [0u8; 64];
     m[..32].copy_from_slice(p_i);
     m[32..].copy_from_slice(a_i);
-    let tag = Blake2bParams::new().hash_length(32).personal(DOM_BLOCK_R).hash(&m);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(tag.as_bytes());
-    out
+    prf::blake2b_32(DOM_BLOCK_R, &m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params;
+
+    #[test]
+    fn poseidon_domain_hashes_golden() {
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        assert_eq!(hex::encode(hash_A_h(&a, &b)), "b1ecf5f4d5cfb6c3291f3dd79a86e403bd7e6761e8c33205b12e330013683a0f");
+        assert_eq!(hex::encode(hash_S_h(&a, &b)), "55d36fae0d5aa6239fc37b21ecf58b7d2945481ac82a0a309172354c25dc240d");
+        assert_eq!(hex::encode(derive_block_r(&a, &b)), "1359843179ba7ac33815844a75f9ee4c5a5ec52c7581100874aa4cfd615e693f");
+    }
+
+    #[test]
+    fn default_sponge_matches_compress_field() {
+        let (a, b) = (FrVesta::from(7u64), FrVesta::from(11u64));
+        let mut sponge = Sponge::default();
+        assert_eq!((sponge.width(), sponge.rate(), sponge.capacity()), (3, params::NODE_ARITY, 1));
+        sponge.absorb(&a);
+        sponge.absorb(&b);
+        let out = sponge.squeeze();
+        assert_eq!(out, compress_field(&a, &b));
+        assert_ne!(sponge.squeeze(), out);
+
+        assert!(Sponge::with_params(3, 3).is_err());
+        assert!(Sponge::with_params(3, 0).is_err());
+        let wide = Sponge::with_params(5, 4).unwrap();
+        assert_eq!((wide.width(), wide.rate()), (5, 4));
+        assert!(Sponge::with_params(6, 4).is_err());
+    }
 }
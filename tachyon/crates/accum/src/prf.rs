@@ -0,0 +1,34 @@
+//! Domain-separated BLAKE2b PRF helpers shared by the Tachyon crates.
+//!
+//! Personalization tags are taken as `&[u8; 16]` so the BLAKE2b limit is
+//! checked by the type system rather than by hand-counted comments. Shorter
+//! tags are written zero-padded, which BLAKE2b treats identically.
+
+use blake2b_simd::Params as Blake2bParams;
+
+/// BLAKE2b-256 of `input` under personalization `personal`.
+pub fn blake2b_32(personal: &[u8; 16], input: &[u8]) -> [u8; 32] {
+    let hash = Blake2bParams::new().hash_length(32).personal(personal).hash(input);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
+
+/// BLAKE2b-256 of the concatenated `parts` under `personal`, streamed into the
+/// hash state part by part so large inputs are never joined into one buffer.
+/// Equals `blake2b_32` over the concatenation.
+pub fn blake2b_32_parts<'a>(personal: &[u8; 16], parts: impl IntoIterator<Item = &'a [u8]>) -> [u8; 32] {
+    let mut state = Blake2bParams::new().hash_length(32).personal(personal).to_state();
+    for part in parts { state.update(part); }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(state.finalize().as_bytes());
+    out
+}
+
+/// BLAKE2b-512 of `input` under personalization `personal`.
+pub fn blake2b_64(personal: &[u8; 16], input: &[u8]) -> [u8; 64] {
+    let hash = Blake2bParams::new().hash_length(64).personal(personal).hash(input);
+    let mut out = [0u8; 64];
+    out.copy_from_slice(hash.as_bytes());
+    out
+}
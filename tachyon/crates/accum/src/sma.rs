@@ -0,0 +1,213 @@
+//! Merkle-backed sparse accumulator implementing `SparseMerkleAccumulator`.
+//!
+//! Keys map to leaf positions by the top `height` bits of the key hash and a
+//! present key is stored as its own hash; empty leaves are all-zero. Interior
+//! nodes use `poseidon::compress_nodes`, with precomputed empty-subtree hashes
+//! so only populated paths are hashed. Two keys sharing a position cannot both
+//! be present (the second insert fails with `SmaError::PositionCollision`), so
+//! `height` must leave collisions negligible for the key set.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+
+use crate::poseidon::compress_nodes;
+use crate::{
+    BatchUpdate, MembershipProof, NonMembershipProof, Path, PathElem, Root, SmaError, SparseMerkleAccumulator,
+};
+
+const EMPTY_LEAF: [u8; 32] = [0u8; 32];
+
+/// Leaf position of a key hash: its top `height` bits (big-endian).
+pub fn key_position(key_hash: &[u8; 32], height: usize) -> u64 {
+    let mut b = [0u8; 8];
+    b.copy_from_slice(&key_hash[..8]);
+    u64::from_be_bytes(b) >> (64 - height)
+}
+
+fn empty_hashes(height: usize) -> Vec<[u8; 32]> {
+    let mut out = Vec::with_capacity(height + 1);
+    out.push(EMPTY_LEAF);
+    for i in 0..height {
+        out.push(compress_nodes(&out[i], &out[i]));
+    }
+    out
+}
+
+/// Fold a leaf value up a path; `None` if the path shape disagrees with `position`.
+fn root_from_path(position: u64, leaf: [u8; 32], path: &Path) -> Option<[u8; 32]> {
+    let mut node = leaf;
+    for (level, elem) in path.0.iter().enumerate() {
+        let is_right_child = (position >> level) & 1 == 1;
+        // `is_right` marks a sibling on the right, i.e. the current node is a left child.
+        if elem.is_right == is_right_child { return None; }
+        node = if is_right_child {
+            compress_nodes(&elem.sibling, &node)
+        } else {
+            compress_nodes(&node, &elem.sibling)
+        };
+    }
+    Some(node)
+}
+
+/// Check a membership proof against `root` for a tree of `height`. A path of
+/// any other length is rejected before hashing.
+pub fn verify_membership(root: &Root, height: usize, proof: &MembershipProof) -> bool {
+    if !(1..=63).contains(&height) || proof.path.0.len() != height { return false; }
+    let pos = key_position(&proof.key_hash, height);
+    root_from_path(pos, proof.key_hash, &proof.path) == Some(root.0)
+}
+
+/// Check a non-membership proof (empty leaf at the key's position) against
+/// `root` for a tree of `height`; wrong-length paths are rejected.
+pub fn verify_non_membership(root: &Root, height: usize, proof: &NonMembershipProof) -> bool {
+    if !(1..=63).contains(&height) || proof.path.0.len() != height { return false; }
+    let pos = key_position(&proof.key_hash, height);
+    root_from_path(pos, EMPTY_LEAF, &proof.path) == Some(root.0)
+}
+
+/// In-memory sparse Merkle tree over `2^height` leaves.
+#[derive(Clone, Debug)]
+pub struct MerkleSMA {
+    height: usize,
+    leaves: BTreeMap<u64, [u8; 32]>,
+    empty: Vec<[u8; 32]>,
+}
+
+impl MerkleSMA {
+    /// Empty tree; `height` must be in `1..=63`.
+    pub fn new(height: usize) -> Self {
+        assert!((1..=63).contains(&height), "unsupported SMA height {}", height);
+        Self { height, leaves: BTreeMap::new(), empty: empty_hashes(height) }
+    }
+
+    /// Non-empty leaves as `(position, value)` in ascending position order.
+    pub fn leaves(&self) -> impl Iterator<Item = (u64, [u8; 32])> + '_ {
+        self.leaves.iter().map(|(p, v)| (*p, *v))
+    }
+
+    /// Rebuild a tree from dumped leaves, failing unless its root equals `expected_root`.
+    pub fn from_leaves(height: usize, leaves: impl IntoIterator<Item = (u64, [u8; 32])>, expected_root: &Root) -> Result<Self> {
+        let mut sma = Self::new(height);
+        for (pos, value) in leaves {
+            if pos >> height != 0 { return Err(anyhow!("leaf position {} out of range for height {}", pos, height)); }
+            if value == EMPTY_LEAF { return Err(anyhow!("empty leaf value at position {}", pos)); }
+            if sma.leaves.insert(pos, value).is_some() { return Err(anyhow!("duplicate leaf position {}", pos)); }
+        }
+        if sma.root() != *expected_root { return Err(anyhow!("rebuilt root does not match expected root")); }
+        Ok(sma)
+    }
+
+    /// Hash of the subtree at `level` (0 = leaves) with the given index.
+    fn node(&self, level: usize, index: u64) -> [u8; 32] {
+        let start = index << level;
+        let end = (index + 1) << level;
+        if self.leaves.range(start..end).next().is_none() { return self.empty[level]; }
+        if level == 0 { return self.leaves[&index]; }
+        compress_nodes(&self.node(level - 1, 2 * index), &self.node(level - 1, 2 * index + 1))
+    }
+
+    fn path(&self, position: u64) -> Path {
+        let elems = (0..self.height)
+            .map(|level| {
+                let index = position >> level;
+                PathElem { sibling: self.node(level, index ^ 1), is_right: index & 1 == 0 }
+            })
+            .collect();
+        Path(elems)
+    }
+}
+
+impl SparseMerkleAccumulator for MerkleSMA {
+    fn height(&self) -> usize { self.height }
+
+    fn root(&self) -> Root { Root(self.node(self.height, 0)) }
+
+    fn prove_membership(&self, key_hash: [u8; 32]) -> MembershipProof {
+        MembershipProof { key_hash, path: self.path(key_position(&key_hash, self.height)) }
+    }
+
+    fn prove_non_membership(&self, key_hash: [u8; 32]) -> NonMembershipProof {
+        NonMembershipProof { key_hash, path: self.path(key_position(&key_hash, self.height)) }
+    }
+
+    fn apply_batch(&mut self, batch: &BatchUpdate) -> Result<Root, SmaError> {
+        batch.validate()?;
+        // Prior leaf values, to undo the batch if a later item collides.
+        let mut undo: Vec<(u64, Option<[u8; 32]>)> = Vec::with_capacity(batch.0.len());
+        for it in &batch.0 {
+            let pos = key_position(&it.key_hash, self.height);
+            let prior = self.leaves.get(&pos).copied();
+            if it.present {
+                if prior.is_some_and(|v| v != it.key_hash) {
+                    for (p, v) in undo.into_iter().rev() {
+                        match v { Some(v) => { self.leaves.insert(p, v); } None => { self.leaves.remove(&p); } }
+                    }
+                    return Err(SmaError::PositionCollision { position: pos });
+                }
+                self.leaves.insert(pos, it.key_hash);
+            } else if prior == Some(it.key_hash) {
+                self.leaves.remove(&pos);
+            }
+            undo.push((pos, prior));
+        }
+        Ok(self.root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{params, BatchItem};
+
+    #[test]
+    fn merkle_sma_dump_and_reload() {
+        let mut sma = MerkleSMA::new(params::ACCUM_HEIGHT);
+        let mut items: Vec<BatchItem> = (0u8..10)
+            .map(|i| BatchItem { key_hash: crate::poseidon::compress_nodes(&[i; 32], &[0u8; 32]), present: true })
+            .collect();
+        items.sort_by_key(|it| it.key_hash);
+        let root = sma.apply_batch(&BatchUpdate(items.clone())).unwrap();
+
+        let dump: Vec<(u64, [u8; 32])> = sma.leaves().collect();
+        assert_eq!(dump.len(), 10);
+        assert!(dump.windows(2).all(|w| w[0].0 < w[1].0));
+        let reloaded = MerkleSMA::from_leaves(params::ACCUM_HEIGHT, dump.clone(), &root).unwrap();
+        assert_eq!(reloaded.root(), root);
+        for it in &items {
+            let proof = reloaded.prove_membership(it.key_hash);
+            assert_eq!(proof, sma.prove_membership(it.key_hash));
+            assert!(verify_membership(&root, params::ACCUM_HEIGHT, &proof));
+        }
+        let absent = reloaded.prove_non_membership([0xffu8; 32]);
+        assert!(verify_non_membership(&root, params::ACCUM_HEIGHT, &absent));
+
+        assert!(MerkleSMA::from_leaves(params::ACCUM_HEIGHT, dump[1..].to_vec(), &root).is_err());
+    }
+
+    #[test]
+    fn sma_rejects_wrong_path_length() {
+        let h = 8;
+        let mut sma = MerkleSMA::new(h);
+        let key = [0x42u8; 32];
+        let root = sma.apply_batch(&BatchUpdate(vec![BatchItem { key_hash: key, present: true }])).unwrap();
+        let proof = sma.prove_membership(key);
+        assert!(verify_membership(&root, h, &proof));
+
+        let mut short = proof.clone();
+        short.path.0.pop();
+        assert!(!verify_membership(&root, h, &short));
+        let mut long = proof.clone();
+        long.path.0.push(PathElem { sibling: [0u8; 32], is_right: true });
+        assert!(!verify_membership(&root, h, &long));
+
+        let absent = sma.prove_non_membership([0x01u8; 32]);
+        assert!(verify_non_membership(&root, h, &absent));
+        let mut short = absent.clone();
+        short.path.0.pop();
+        assert!(!verify_non_membership(&root, h, &short));
+        let mut long = absent;
+        long.path.0.push(PathElem { sibling: [0u8; 32], is_right: true });
+        assert!(!verify_non_membership(&root, h, &long));
+    }
+}
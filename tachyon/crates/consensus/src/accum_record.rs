@@ -1,49 +1,302 @@
 //! Consensus record for per-block accumulator publication.
 
 use serde::{Deserialize, Serialize};
-use accum::{ipa, poseidon};
+use accum::{ipa, poly, poseidon};
+use accum::ipa::circuit::{IpaProof, IpaVerifierParams};
+use blake2b_simd::Params as Blake2bParams;
+use ff::{Field, FromUniformBytes};
 use group::prime::PrimeCurveAffine;
 use group::Curve;
+use pasta_curves::{pallas, vesta::Scalar as FrVesta};
+use pcd::block_circuit::{coeffs_to_pallas, commit_block_coeffs};
+use primitives::endian::put_u64_be;
 
-#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
-pub struct PallasPointBytes(pub [u8; 32]);
+const DS_RECORD_CHALLENGE_V1: &[u8; 16] = b"tachyon:rec:chal"; // 16
+const DS_RECORD_FINGERPRINT_V1: &[u8; 16] = b"tachyon:rec:fp\0\0"; // 14 + 2 = 16
 
+/// Longest IPA a block opening may use: a full block's coefficients, padded.
+const MAX_BLOCK_IPA_LEN: usize = ipa::NUM_COEFFICIENTS.next_power_of_two();
+
+/// Open P_i at `point` without revealing the coefficients. Returns
+/// `p_i(point)` as the IPA computes it: coefficients and `point` embedded in
+/// Fr(Pallas) and evaluated there (see `block_opening_value`).
+pub fn prove_block_commitment(coeffs: &[FrVesta], point: FrVesta, bases: &ipa::IpaBases) -> anyhow::Result<(pallas::Scalar, IpaProof)> {
+    let params = IpaVerifierParams::with_bases(bases, coeffs.len().next_power_of_two())?;
+    ipa::circuit::prove_ipa_opening(&params, &coeffs_to_pallas(coeffs), &ipa::embed_vesta_scalar(&point))
+}
+
+/// The value an opening of a block's P_i at `point` must carry, from the
+/// block's roots: the coefficients of prod(X - root), expanded in Fr(Vesta)
+/// as the publisher does, then embedded with `point` into Fr(Pallas) and
+/// evaluated there. The IPA only does Fr(Pallas) arithmetic, so this is in
+/// general not the embedding of `poly::eval_from_roots(roots, point)`, which
+/// reduces mod the Vesta scalar modulus instead.
+pub fn block_opening_value(roots: &[FrVesta], point: FrVesta) -> pallas::Scalar {
+    let x = ipa::embed_vesta_scalar(&point);
+    poly::roots_to_coeffs_auto(roots)
+        .iter()
+        .rev()
+        .fold(pallas::Scalar::ZERO, |acc, c| acc * x + ipa::embed_vesta_scalar(c))
+}
+
+/// Verify an IPA opening of `record.p_i` at `point` to `value`. P_i commits
+/// the block coefficients under `ipa::embed_vesta_scalar`, so `value` is the
+/// block polynomial evaluated at `point` in Fr(Pallas). The proof's round
+/// count fixes the IPA size, up to a padded full block.
+pub fn verify_block_commitment(record: &BlockAccumRecord, opening: &IpaProof, point: FrVesta, value: pallas::Scalar, bases: &ipa::IpaBases) -> bool {
+    let p_i = match ipa::decode_point(&record.p_i.0) { Some(p) => p, None => return false };
+    let n = match 1usize.checked_shl(opening.l_vec.len() as u32) { Some(n) if n <= MAX_BLOCK_IPA_LEN => n, _ => return false };
+    let params = match IpaVerifierParams::with_bases(bases, n) { Ok(params) => params, Err(_) => return false };
+    ipa::circuit::verify_ipa_opening(&params, &p_i, &ipa::embed_vesta_scalar(&point), &value, opening)
+}
+
+/// `verify_block_commitment` against the value implied by the block's roots
+/// (`block_opening_value`): the opening shows `record` commits to exactly the
+/// block with these roots.
+pub fn verify_block_commitment_roots(record: &BlockAccumRecord, opening: &IpaProof, point: FrVesta, roots: &[FrVesta], bases: &ipa::IpaBases) -> bool {
+    verify_block_commitment(record, opening, point, block_opening_value(roots, point), bases)
+}
+
+pub use accum::PallasPointBytes;
+
+/// Compute (h_i, A_{i+1}) with A_{i+1} = [h_i]A_i + P_i.
+///
+/// The identity is rejected for both inputs: an identity A_i drops all prior
+/// history from A_{i+1}, and an identity P_i means the block contributed
+/// nothing. The one exception is `genesis`, where A_0 may be the identity and
+/// A_1 = P_0; P_i must never be the identity.
+fn accum_step(a_i: &PallasPointBytes, p_i: &PallasPointBytes, genesis: bool) -> anyhow::Result<([u8; 32], PallasPointBytes)> {
+    let a_i_aff = ipa::decode_point(&a_i.0).ok_or_else(|| anyhow::anyhow!("invalid accumulator point A_i"))?;
+    let p_i_aff = ipa::decode_point(&p_i.0).ok_or_else(|| anyhow::anyhow!("invalid block commitment P_i"))?;
+    if bool::from(a_i_aff.is_identity()) && !genesis { anyhow::bail!("accumulator A_i is the identity"); }
+    if bool::from(p_i_aff.is_identity()) { anyhow::bail!("block commitment P_i is the identity"); }
+    let h_i = poseidon::compute_h_i(&a_i.0, &p_i.0);
+    let h_scalar = ipa::map_vesta_scalar_to_pallas(&h_i);
+    let a_next = (a_i_aff.to_curve() * h_scalar + p_i_aff.to_curve()).to_affine();
+    Ok((h_i, PallasPointBytes(ipa::encode_point(&a_next))))
+}
+
+/// Per-block accumulator record.
+///
+/// JSON layout (`to_json`), stable for indexers:
+/// `{"p_i": hex, "h_i": hex, "a_next": hex, "proof": base64}`, with 64-char
+/// lowercase hex for the 32-byte fields and padded standard base64 for the proof.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct BlockAccumRecord {
     /// Polynomial commitment for block i (Pallas G1 compressed bytes).
     pub p_i: PallasPointBytes,
     /// Domain-separated hash h_i = H_A(A_i, P_i).
+    #[serde(with = "hex32_serde")]
     pub h_i: [u8; 32],
     /// Accumulator state A_{i+1} (Pallas G1 compressed bytes).
     pub a_next: PallasPointBytes,
     /// Halo2 proof bytes attesting block polynomial identity and accumulator step.
+    #[serde(with = "base64_serde")]
     pub proof: Vec<u8>,
 }
 
+/// `[u8; 32]` as a hex string in human-readable formats.
+mod hex32_serde {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8; 32], s: S) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() { s.serialize_str(&hex::encode(v)) } else { serde::Serialize::serialize(v, s) }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 32], D::Error> {
+        if d.is_human_readable() {
+            accum::hexfmt::parse_hex32(&String::deserialize(d)?).map_err(D::Error::custom)
+        } else {
+            <[u8; 32]>::deserialize(d)
+        }
+    }
+}
+
+/// Byte vector as standard base64 in human-readable formats.
+mod base64_serde {
+    use base64ct::{Base64, Encoding};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        if s.is_human_readable() { s.serialize_str(&Base64::encode_string(v)) } else { s.serialize_bytes(v) }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        if d.is_human_readable() {
+            Base64::decode_vec(&String::deserialize(d)?).map_err(|e| D::Error::custom(format!("invalid base64 proof: {e}")))
+        } else {
+            Vec::<u8>::deserialize(d)
+        }
+    }
+}
+
 impl BlockAccumRecord {
+    /// Canonical JSON (see the type docs for the layout).
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Parse `to_json` output; errors on malformed hex or base64 and on
+    /// `p_i`/`a_next` that are not valid compressed Pallas points.
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let rec: Self = serde_json::from_str(json)?;
+        for (name, p) in [("p_i", &rec.p_i), ("a_next", &rec.a_next)] {
+            if ipa::decode_point(&p.0).is_none() { anyhow::bail!("{} is not a valid Pallas point", name); }
+        }
+        Ok(rec)
+    }
+
     /// Publisher helper: compute h_i and A_{i+1} from (A_i, P_i) and proof bytes.
-    pub fn from_ai_pi(a_i: &PallasPointBytes, p_i: &PallasPointBytes, proof: Vec<u8>) -> Self {
-        let h_i = poseidon::hash_A_h(&a_i.0, &p_i.0);
-        // Map h_i to Pallas scalar and compute A_{i+1} = [h_i]A_i + P_i
-        let a_i_aff = ipa::decode_point(&a_i.0).unwrap_or(ipa::g0());
-        let p_i_aff = ipa::decode_point(&p_i.0).unwrap_or(ipa::g0());
-        let h_scalar = ipa::map_vesta_scalar_to_pallas(&h_i);
-        let a_next_aff = (a_i_aff.to_curve() * h_scalar + p_i_aff.to_curve()).to_affine();
-        let a_next = PallasPointBytes(ipa::encode_point(&a_next_aff));
-        Self { p_i: *p_i, h_i, a_next, proof }
+    /// Errors if either point fails to decode or is the identity.
+    pub fn from_ai_pi(a_i: &PallasPointBytes, p_i: &PallasPointBytes, proof: Vec<u8>) -> anyhow::Result<Self> {
+        let (h_i, a_next) = accum_step(a_i, p_i, false)?;
+        Ok(Self { p_i: *p_i, h_i, a_next, proof })
+    }
+
+    /// Like `from_ai_pi` for the first record of a chain, where A_0 may be the identity.
+    pub fn from_genesis(a_0: &PallasPointBytes, p_0: &PallasPointBytes, proof: Vec<u8>) -> anyhow::Result<Self> {
+        let (h_i, a_next) = accum_step(a_0, p_0, true)?;
+        Ok(Self { p_i: *p_0, h_i, a_next, proof })
     }
 
     /// Verifier helper: check that (h_i, a_next) are consistent with (A_i, P_i).
+    /// An identity A_i or P_i is rejected; use `verify_genesis_step` for A_0.
     /// This does not verify the Halo2 proof; call the block-circuit verifier separately.
     pub fn verify_step(&self, a_i: &PallasPointBytes) -> bool {
-        let a_i_aff = match ipa::decode_point(&a_i.0) { Some(p) => p, None => return false };
-        let p_i_aff = match ipa::decode_point(&self.p_i.0) { Some(p) => p, None => return false };
-        let expected_h = poseidon::hash_A_h(&a_i.0, &self.p_i.0);
-        if expected_h != self.h_i { return false; }
-        let h_scalar = ipa::map_vesta_scalar_to_pallas(&self.h_i);
-        let a_next = (a_i_aff.to_curve() * h_scalar + p_i_aff.to_curve()).to_affine();
-        ipa::encode_point(&a_next) == self.a_next.0
+        self.check_step(a_i, false)
     }
+
+    /// `verify_step` for the first record of a chain: A_0 may be the identity.
+    pub fn verify_genesis_step(&self, a_0: &PallasPointBytes) -> bool {
+        self.check_step(a_0, true)
+    }
+
+    fn check_step(&self, a_i: &PallasPointBytes, genesis: bool) -> bool {
+        match accum_step(a_i, &self.p_i, genesis) {
+            Ok((h_i, a_next)) => h_i == self.h_i && a_next == self.a_next,
+            Err(_) => false,
+        }
+    }
+
+    /// Whether `next` extends this record: its step relation holds with
+    /// A_i = `self.a_next` (h_i recomputed from that A_i and `next.p_i`).
+    pub fn links_to(&self, next: &BlockAccumRecord) -> bool {
+        next.verify_step(&self.a_next)
+    }
+
+    /// Cheap gossip fingerprint BLAKE2b-256(p_i || a_next) for comparing
+    /// records at the same height across peers. Not a substitute for `verify_step`.
+    pub fn commitment_fingerprint(&self) -> [u8; 32] {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(&self.p_i.0);
+        buf[32..].copy_from_slice(&self.a_next.0);
+        accum::prf::blake2b_32(DS_RECORD_FINGERPRINT_V1, &buf)
+    }
+
+    /// Deterministic spot-check challenge bound to this record:
+    /// Blake2b-512(p_i || h_i || a_next) reduced into Fr(Vesta).
+    pub fn challenge_point(&self) -> FrVesta {
+        let hash = Blake2bParams::new()
+            .hash_length(64)
+            .personal(DS_RECORD_CHALLENGE_V1)
+            .to_state()
+            .update(&self.p_i.0)
+            .update(&self.h_i)
+            .update(&self.a_next.0)
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(hash.as_bytes());
+        <FrVesta as FromUniformBytes<64>>::from_uniform_bytes(&wide)
+    }
+
+    /// Spot-check prover-supplied coefficients: they must commit to `p_i` and
+    /// evaluate to `value` at `challenge_point()`.
+    pub fn check_opening(&self, coeffs: &[FrVesta], value: FrVesta) -> bool {
+        if ipa::encode_point(&commit_block_coeffs(coeffs)) != self.p_i.0 { return false; }
+        poly::eval_horner(coeffs, self.challenge_point()) == value
+    }
+}
+
+/// Whether two records for the same height commit to different blocks or
+/// accumulator states, i.e. the peers that sent them are on different forks.
+pub fn fingerprints_diverge(a: &BlockAccumRecord, b: &BlockAccumRecord) -> bool {
+    a.commitment_fingerprint() != b.commitment_fingerprint()
+}
+
+/// Validate a run of consecutive records starting from `genesis_a` = A_0
+/// (the first step follows `verify_genesis_step`, so A_0 may be the identity).
+/// Published chains start at `accum::genesis_accumulator()`.
+pub fn verify_chain(records: &[BlockAccumRecord], genesis_a: &PallasPointBytes) -> anyhow::Result<()> {
+    let first = match records.first() { Some(r) => r, None => return Ok(()) };
+    if !first.verify_genesis_step(genesis_a) { anyhow::bail!("record 0 does not extend the genesis accumulator"); }
+    for (i, pair) in records.windows(2).enumerate() {
+        if !pair[0].links_to(&pair[1]) { anyhow::bail!("record {} does not link to record {}", i + 1, i); }
+    }
+    Ok(())
+}
+
+/// Compact per-block summary for light clients: enough to follow the
+/// accumulator chain and the MMR leaves without the full record or proof.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct BlockHeader {
+    pub p_i: PallasPointBytes,
+    pub a_next: PallasPointBytes,
+    /// `BlockMMRLeaf::leaf_hash` of the block.
+    pub mmr_leaf_hash: [u8; 32],
+    pub height: u64,
 }
 
+impl BlockHeader {
+    /// Encoded length: p_i || a_next || mmr_leaf_hash || height (u64 BE).
+    pub const LEN: usize = 32 * 3 + 8;
+
+    pub fn new(record: &BlockAccumRecord, leaf: &crate::digest::BlockMMRLeaf, height: u64) -> Self {
+        Self { p_i: record.p_i, a_next: record.a_next, mmr_leaf_hash: leaf.leaf_hash(), height }
+    }
 
+    pub fn to_bytes(&self) -> [u8; Self::LEN] {
+        let mut out = Vec::with_capacity(Self::LEN);
+        out.extend_from_slice(&self.p_i.0);
+        out.extend_from_slice(&self.a_next.0);
+        out.extend_from_slice(&self.mmr_leaf_hash);
+        put_u64_be(&mut out, self.height);
+        out.try_into().expect("header encoding is LEN bytes")
+    }
+
+    /// Parse `to_bytes` output; errors on a wrong length or on `p_i`/`a_next`
+    /// that are not valid compressed Pallas points.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() != Self::LEN { anyhow::bail!("block header is {} bytes, expected {}", bytes.len(), Self::LEN); }
+        let word = |i: usize| -> [u8; 32] { bytes[32 * i..32 * (i + 1)].try_into().expect("32-byte slice") };
+        let header = Self {
+            p_i: PallasPointBytes(word(0)),
+            a_next: PallasPointBytes(word(1)),
+            mmr_leaf_hash: word(2),
+            height: u64::from_be_bytes(bytes[96..].try_into().expect("8-byte slice")),
+        };
+        for (name, p) in [("p_i", &header.p_i), ("a_next", &header.a_next)] {
+            if ipa::decode_point(&p.0).is_none() { anyhow::bail!("{} is not a valid Pallas point", name); }
+        }
+        Ok(header)
+    }
+}
+
+/// `verify_chain` for headers starting at `start_height`: header `i` must have
+/// height `start_height + i` and its `a_next` must be the step from the
+/// previous A (`a_start`, the accumulator after block `start_height - 1`, for
+/// header 0) by its `p_i`. A may be the identity only before height 0, so a
+/// light client can resume from a trusted checkpoint. Leaf hashes are carried,
+/// not checked; compare them against the MMR separately.
+pub fn verify_chain_headers(headers: &[BlockHeader], start_height: u64, a_start: &PallasPointBytes) -> anyhow::Result<()> {
+    let mut a_i = *a_start;
+    for (i, header) in headers.iter().enumerate() {
+        let height = start_height
+            .checked_add(i as u64)
+            .ok_or_else(|| anyhow::anyhow!("header {} height overflows u64", i))?;
+        if header.height != height { anyhow::bail!("header {} has height {}, expected {}", i, header.height, height); }
+        let (_, a_next) = accum_step(&a_i, &header.p_i, height == 0)?;
+        if a_next != header.a_next { anyhow::bail!("header {} does not extend the accumulator", i); }
+        a_i = header.a_next;
+    }
+    Ok(())
+}
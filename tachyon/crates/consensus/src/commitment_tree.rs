@@ -0,0 +1,66 @@
+//! Append-only note commitment tree used for the per-block Orchard root digest.
+
+use accum::poseidon::compress_nodes;
+
+/// Tree depth (Orchard uses 32).
+pub const COMMITMENT_TREE_DEPTH: usize = 32;
+
+/// Incremental Merkle tree: keeps only the left frontier, so `append` and
+/// `root` are O(depth). Empty leaves are all-zero and interior nodes use
+/// `poseidon::compress_nodes`.
+#[derive(Clone, Debug)]
+pub struct CommitmentTree {
+    size: u64,
+    /// `frontier[level]` is the pending left node at that level, if any.
+    frontier: Vec<Option<[u8; 32]>>,
+    empty: Vec<[u8; 32]>,
+}
+
+impl Default for CommitmentTree {
+    fn default() -> Self { Self::new() }
+}
+
+impl CommitmentTree {
+    pub fn new() -> Self {
+        let mut empty = Vec::with_capacity(COMMITMENT_TREE_DEPTH + 1);
+        empty.push([0u8; 32]);
+        for i in 0..COMMITMENT_TREE_DEPTH {
+            empty.push(compress_nodes(&empty[i], &empty[i]));
+        }
+        Self { size: 0, frontier: vec![None; COMMITMENT_TREE_DEPTH], empty }
+    }
+
+    /// Number of appended commitments.
+    pub fn size(&self) -> u64 { self.size }
+
+    /// Append a note commitment; errors once all `2^depth` leaves are used.
+    pub fn append(&mut self, cm: [u8; 32]) -> anyhow::Result<()> {
+        if self.size >> COMMITMENT_TREE_DEPTH != 0 { anyhow::bail!("commitment tree is full"); }
+        let mut node = cm;
+        for level in 0..COMMITMENT_TREE_DEPTH {
+            match self.frontier[level].take() {
+                Some(left) => node = compress_nodes(&left, &node),
+                None => {
+                    self.frontier[level] = Some(node);
+                    break;
+                }
+            }
+        }
+        self.size += 1;
+        Ok(())
+    }
+
+    /// Current root, treating all unfilled leaves as empty.
+    pub fn root(&self) -> [u8; 32] {
+        // Walk up from the next free leaf: `frontier[level]` is set exactly when
+        // that path node is a right child, so it supplies the left sibling.
+        let mut node = self.empty[0];
+        for level in 0..COMMITMENT_TREE_DEPTH {
+            node = match &self.frontier[level] {
+                Some(left) => compress_nodes(left, &node),
+                None => compress_nodes(&node, &self.empty[level]),
+            };
+        }
+        node
+    }
+}
@@ -1,6 +1,9 @@
 //! Per-block digests and MMR leaf (ZIP-221 extension).
 
-use blake2b_simd::Params as Blake2bParams;
+use accum::{merkle, prf::{blake2b_32, blake2b_32_parts}};
+use blake2b_simd::{Params as Blake2bParams, State as Blake2bState};
+use primitives::endian::{put_u32_be, put_u64_be};
+use primitives::TachyonBundle;
 use serde::{Deserialize, Serialize};
 
 /// BLAKE2b-256 digest of the latest Orchard commitment tree root.
@@ -23,77 +26,190 @@ pub struct BlockMMRLeaf {
     pub commitment_delta_digest: CommitmentDeltaDigest,
 }
 
+accum::impl_hex32!(OrchardRootDigest, NullifierBlockDigest, CommitmentDeltaDigest);
+
 const DS_ORCH_ROOT_V1: &[u8; 16] = b"orch.root.v1\0\0\0\0"; // 12 + 4 = 16
 const DS_NF_BLOCK_V1: &[u8; 16] = b"nf.block.v1\0\0\0\0\0"; // 11 + 5 = 16
 const DS_CM_DELTA_V1: &[u8; 16] = b"cm.delta.v1\0\0\0\0\0"; // 11 + 5 = 16
 const DS_MMR_LEAF_V1: &[u8; 16] = b"mmr.leaf.v1\0\0\0\0\0"; // 11 + 5 = 16
 const DS_TG_UNIFIED_BLOCK_V1: &[u8; 16] = b"tg.unified.blk\0\0"; // 14 + 2 = 16
 
+/// Hash functions behind the per-block digests and the MMR leaf hash, so
+/// alternatives (e.g. a recursion-friendly Poseidon) can be tried without
+/// touching callers. Consensus uses `Blake2bBackend`.
+pub trait DigestBackend {
+    /// Digest of the latest Orchard commitment tree root.
+    fn orchard_root(&self, root: &[u8; 32]) -> OrchardRootDigest;
+    /// Digest of the ordered per-block nullifier vector.
+    fn nullifier_block(&self, nullifiers: &[[u8; 32]]) -> NullifierBlockDigest;
+    /// Digest of the ordered per-block commitment additions.
+    fn commitment_delta(&self, commitments: &[[u8; 32]]) -> CommitmentDeltaDigest;
+    /// Hash of the three leaf digests into one MMR leaf value.
+    fn mmr_leaf(&self, leaf: &BlockMMRLeaf) -> [u8; 32];
+}
+
+/// Default backend: domain-separated BLAKE2b-256.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Blake2bBackend;
+
+impl DigestBackend for Blake2bBackend {
+    fn orchard_root(&self, root: &[u8; 32]) -> OrchardRootDigest {
+        OrchardRootDigest(blake2b_32(DS_ORCH_ROOT_V1, root))
+    }
+
+    fn nullifier_block(&self, nullifiers: &[[u8; 32]]) -> NullifierBlockDigest {
+        NullifierBlockDigest(blake2b_32_parts(DS_NF_BLOCK_V1, nullifiers.iter().map(|x| &x[..])))
+    }
+
+    fn commitment_delta(&self, commitments: &[[u8; 32]]) -> CommitmentDeltaDigest {
+        CommitmentDeltaDigest(blake2b_32_parts(DS_CM_DELTA_V1, commitments.iter().map(|x| &x[..])))
+    }
+
+    fn mmr_leaf(&self, leaf: &BlockMMRLeaf) -> [u8; 32] {
+        let mut buf = [0u8; 96];
+        buf[..32].copy_from_slice(&leaf.orchard_root_digest.0);
+        buf[32..64].copy_from_slice(&leaf.nullifier_block_digest.0);
+        buf[64..].copy_from_slice(&leaf.commitment_delta_digest.0);
+        blake2b_32(DS_MMR_LEAF_V1, &buf)
+    }
+}
+
 /// Compute BLAKE2b-256 digest of the current Orchard root (domain-separated).
 pub fn compute_orchard_root_digest(root: &[u8; 32]) -> OrchardRootDigest {
-    let hash = Blake2bParams::new().hash_length(32).personal(DS_ORCH_ROOT_V1).hash(root);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    OrchardRootDigest(out)
+    Blake2bBackend.orchard_root(root)
 }
 
 /// Compute BLAKE2b-256 digest of the ordered per-block nullifier vector.
 pub fn compute_nullifier_block_digest(nullifiers: &[[u8; 32]]) -> NullifierBlockDigest {
-    let mut buf = Vec::with_capacity(nullifiers.len() * 32);
-    for nf in nullifiers { buf.extend_from_slice(nf); }
-    let hash = Blake2bParams::new().hash_length(32).personal(DS_NF_BLOCK_V1).hash(&buf);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    NullifierBlockDigest(out)
+    Blake2bBackend.nullifier_block(nullifiers)
 }
 
 /// Compute BLAKE2b-256 digest of the ordered per-block commitment additions.
 pub fn compute_commitment_delta_digest(commitments: &[[u8; 32]]) -> CommitmentDeltaDigest {
-    let mut buf = Vec::with_capacity(commitments.len() * 32);
-    for cm in commitments { buf.extend_from_slice(cm); }
-    let hash = Blake2bParams::new().hash_length(32).personal(DS_CM_DELTA_V1).hash(&buf);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    CommitmentDeltaDigest(out)
+    Blake2bBackend.commitment_delta(commitments)
+}
+
+/// Count-bound `accum::merkle` root over the ordered per-block commitment
+/// additions, with Poseidon interior nodes so the delta can later be proven
+/// in-circuit. Consensus still commits `compute_commitment_delta_digest`.
+pub fn compute_commitment_delta_root(commitments: &[[u8; 32]]) -> [u8; 32] {
+    merkle::root(commitments)
+}
+
+/// Inclusion proof for one commitment under `compute_commitment_delta_root`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct CommitmentDeltaProof {
+    pub index: u64,
+    pub leaf_count: u64,
+    /// Siblings from the leaf up to the tree root.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl CommitmentDeltaProof {
+    /// Check that `cm` is commitment `index` of the block with delta root `root`.
+    pub fn verify(&self, root: &[u8; 32], cm: &[u8; 32]) -> bool {
+        merkle::verify(root, cm, self.index, self.leaf_count, &self.siblings)
+    }
+}
+
+/// Inclusion proof for `commitments[index]`, or `None` if out of range.
+pub fn commitment_delta_inclusion_proof(commitments: &[[u8; 32]], index: usize) -> Option<CommitmentDeltaProof> {
+    if index >= commitments.len() { return None; }
+    let siblings = merkle::siblings(&merkle::levels(commitments), index);
+    Some(CommitmentDeltaProof { index: index as u64, leaf_count: commitments.len() as u64, siblings })
 }
 
 impl BlockMMRLeaf {
     /// Hash the leaf tuple into a single 32-byte value (domain-separated).
     pub fn leaf_hash(&self) -> [u8; 32] {
-        let mut buf = [0u8; 96];
-        buf[..32].copy_from_slice(&self.orchard_root_digest.0);
-        buf[32..64].copy_from_slice(&self.nullifier_block_digest.0);
-        buf[64..].copy_from_slice(&self.commitment_delta_digest.0);
-        let hash = Blake2bParams::new().hash_length(32).personal(DS_MMR_LEAF_V1).hash(&buf);
-        let mut out = [0u8; 32];
-        out.copy_from_slice(hash.as_bytes());
-        out
+        self.leaf_hash_with(&Blake2bBackend)
+    }
+
+    /// `leaf_hash` under an alternative digest backend.
+    pub fn leaf_hash_with<B: DigestBackend>(&self, backend: &B) -> [u8; 32] {
+        backend.mmr_leaf(self)
+    }
+}
+
+/// `BlockMMRLeaf` lives here, downstream of pcd, so the leaf check on
+/// `pcd::AggPCDPublic` is provided as an extension trait.
+pub trait AggMmrLeafCheck {
+    /// Whether the bound `block_mmr_leaf_hash` equals `leaf.leaf_hash()`.
+    fn check_mmr_leaf(&self, leaf: &BlockMMRLeaf) -> bool;
+}
+
+impl AggMmrLeafCheck for pcd::AggPCDPublic {
+    fn check_mmr_leaf(&self, leaf: &BlockMMRLeaf) -> bool {
+        self.block_mmr_leaf_hash == leaf.leaf_hash()
+    }
+}
+
+/// The block's full MMR leaf in one pass over `bundles` (block order): each
+/// bundle's nullifiers and commitments feed both digests as it is visited.
+/// Equals the leaf built from `compute_orchard_root_digest`,
+/// `compute_nullifier_block_digest` and `compute_commitment_delta_digest`
+/// over the concatenated sets.
+pub fn compute_block_digests(bundles: &[TachyonBundle], orchard_root: &[u8; 32]) -> BlockMMRLeaf {
+    let mut acc = BlockDigestAccumulator::new();
+    acc.set_orchard_root(*orchard_root);
+    for bundle in bundles { acc.add_bundle(bundle); }
+    acc.finalize()
+}
+
+/// Streaming builder for a block's MMR leaf: feed bundles in block order and
+/// the digests equal the batch functions over the concatenated sets.
+#[derive(Clone, Debug)]
+pub struct BlockDigestAccumulator {
+    nf_state: Blake2bState,
+    cm_state: Blake2bState,
+    orchard_root: [u8; 32],
+}
+
+impl Default for BlockDigestAccumulator {
+    fn default() -> Self { Self::new() }
+}
+
+impl BlockDigestAccumulator {
+    pub fn new() -> Self {
+        Self {
+            nf_state: Blake2bParams::new().hash_length(32).personal(DS_NF_BLOCK_V1).to_state(),
+            cm_state: Blake2bParams::new().hash_length(32).personal(DS_CM_DELTA_V1).to_state(),
+            orchard_root: [0u8; 32],
+        }
+    }
+
+    /// Orchard root to digest at finalize, normally `CommitmentTree::root()` after
+    /// the block's commitments are appended. Zeros if never set.
+    pub fn set_orchard_root(&mut self, root: [u8; 32]) { self.orchard_root = root; }
+
+    pub fn add_bundle(&mut self, bundle: &TachyonBundle) {
+        for nf in &bundle.nullifiers { self.nf_state.update(nf); }
+        for cm in &bundle.commitments { self.cm_state.update(cm); }
+    }
+
+    pub fn finalize(&self) -> BlockMMRLeaf {
+        let mut nf = [0u8; 32];
+        nf.copy_from_slice(self.nf_state.finalize().as_bytes());
+        let mut cm = [0u8; 32];
+        cm.copy_from_slice(self.cm_state.finalize().as_bytes());
+        BlockMMRLeaf {
+            orchard_root_digest: compute_orchard_root_digest(&self.orchard_root),
+            nullifier_block_digest: NullifierBlockDigest(nf),
+            commitment_delta_digest: CommitmentDeltaDigest(cm),
+        }
     }
 }
 
 /// Experimental block-level unified tachygram digest over on-chain primitives.
 pub fn compute_unified_tachygram_block(nullifiers: &[[u8; 32]], commitments: &[[u8; 32]], value_commitments: &[[u8; 32]], fees: &[u64]) -> [u8; 32] {
     let mut buf = Vec::new();
-    // preserve counts and order deterministically
-    let mut tmp = Vec::with_capacity(4);
-    // nullifiers
-    tmp.clear(); encode_u32(nullifiers.len() as u32, &mut tmp); buf.extend_from_slice(&tmp);
-    for nf in nullifiers { buf.extend_from_slice(nf); }
-    // commitments
-    tmp.clear(); encode_u32(commitments.len() as u32, &mut tmp); buf.extend_from_slice(&tmp);
-    for cm in commitments { buf.extend_from_slice(cm); }
-    // value commitments
-    tmp.clear(); encode_u32(value_commitments.len() as u32, &mut tmp); buf.extend_from_slice(&tmp);
-    for vc in value_commitments { buf.extend_from_slice(vc); }
-    // fees
-    tmp.clear(); encode_u32(fees.len() as u32, &mut tmp); buf.extend_from_slice(&tmp);
-    for f in fees { buf.extend_from_slice(&f.to_be_bytes()); }
-    let hash = Blake2bParams::new().hash_length(32).personal(DS_TG_UNIFIED_BLOCK_V1).hash(&buf);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    out
-}
-
-#[inline]
-fn encode_u32(v: u32, out: &mut Vec<u8>) { out.extend_from_slice(&v.to_be_bytes()); }
+    // preserve counts (big-endian u32) and order deterministically
+    for list in [nullifiers, commitments, value_commitments] {
+        put_u32_be(&mut buf, list.len() as u32);
+        for item in list { buf.extend_from_slice(item); }
+    }
+    put_u32_be(&mut buf, fees.len() as u32);
+    for f in fees { put_u64_be(&mut buf, *f); }
+    blake2b_32(DS_TG_UNIFIED_BLOCK_V1, &buf)
+}
 
@@ -0,0 +1,42 @@
+//! Fee statistics over the bundles covered by an aggregate.
+
+use anyhow::{anyhow, Result};
+use primitives::TachyonBundle;
+
+/// Per-aggregate fee and size summary for explorers.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct FeeSummary {
+    pub bundle_count: usize,
+    pub total_fee: u64,
+    pub min_fee: u64,
+    pub max_fee: u64,
+    /// Lower median for an even count, so it is always one of the fees.
+    pub median_fee: u64,
+    pub nullifier_count: u64,
+    pub commitment_count: u64,
+}
+
+/// Summarize the bundles covered by one `AggregateProof`. All fee fields are
+/// 0 for no bundles; errors if the total fee or a count overflows `u64`.
+pub fn aggregate_fee_summary(bundles: &[TachyonBundle]) -> Result<FeeSummary> {
+    let mut fees: Vec<u64> = bundles.iter().map(|b| b.fee).collect();
+    fees.sort_unstable();
+    let mut summary = FeeSummary { bundle_count: bundles.len(), ..FeeSummary::default() };
+    for b in bundles {
+        summary.total_fee = summary.total_fee.checked_add(b.fee).ok_or_else(|| anyhow!("total fee overflow"))?;
+        summary.nullifier_count = summary
+            .nullifier_count
+            .checked_add(b.nullifiers.len() as u64)
+            .ok_or_else(|| anyhow!("nullifier count overflow"))?;
+        summary.commitment_count = summary
+            .commitment_count
+            .checked_add(b.commitments.len() as u64)
+            .ok_or_else(|| anyhow!("commitment count overflow"))?;
+    }
+    if let (Some(&min), Some(&max)) = (fees.first(), fees.last()) {
+        summary.min_fee = min;
+        summary.max_fee = max;
+        summary.median_fee = fees[(fees.len() - 1) / 2];
+    }
+    Ok(summary)
+}
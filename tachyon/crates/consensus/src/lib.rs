@@ -4,9 +4,16 @@ pub mod digest;
 pub mod mempool;
 pub mod accum_record;
 pub mod publisher;
+pub mod commitment_tree;
+pub mod fees;
 
 // Re-export all public items from modules for convenience
 pub use digest::*;
 pub use mempool::*;
 pub use accum_record::*;
 pub use publisher::*;
+pub use commitment_tree::*;
+pub use fees::*;
+
+// The chain-history MMR lives in accum so the nullifier window can use it.
+pub use accum::{Mmr, MmrProof};
@@ -1,14 +1,20 @@
 //! Mempool admission and block verification.
 
+use std::collections::BTreeSet;
+
 use anyhow::{anyhow, Result};
-use accum::NullifierSMAWindow;
-use primitives::TachyonBundle;
+use accum::{params::DEGREE_N, BatchUpdate, NullifierSMAWindow};
+use pcd::{api::{self, AggPCD}, DomainSep, VerifyingKey};
+use primitives::{Tachygram, TachyonBundle};
 
+use crate::commitment_tree::CommitmentTree;
 use crate::digest::*;
 
-/// Admit a transaction into mempool: check nullifier freshness in the window.
+/// Admit a transaction into mempool: check action/primitive consistency and
+/// nullifier freshness in the window.
 /// Additional verification (PCD, signatures) is deferred to aggregate verification.
 pub fn admit_tx(bundle: &TachyonBundle, window: &mut NullifierSMAWindow) -> Result<()> {
+    bundle.check_consistency()?;
     for nf in &bundle.nullifiers {
         if !window.is_fresh(nf) {
             return Err(anyhow!("duplicate nullifier"));
@@ -17,29 +23,150 @@ pub fn admit_tx(bundle: &TachyonBundle, window: &mut NullifierSMAWindow) -> Resu
     Ok(())
 }
 
-/// Verify block aggregates and update nullifier window with tx nullifiers.
-/// This stub does not verify aggregate proofs yet.
-pub fn verify_block(bundles: &[TachyonBundle], window: &mut NullifierSMAWindow) -> Result<()> {
+/// Reasons `Mempool::admit` turns a bundle away.
+#[derive(Debug, thiserror::Error)]
+pub enum MempoolError {
+    /// The bundle failed `admit_tx` (inconsistent or reuses a nullifier).
+    #[error(transparent)]
+    Invalid(#[from] anyhow::Error),
+    /// Admitting the bundle would exceed the block's `DEGREE_N` unique tachygrams.
+    #[error("block would carry {count} unique tachygrams, max {max}")]
+    OverBudget { count: usize, max: usize },
+    /// A pending bundle already spends this nullifier.
+    #[error("nullifier {0} is already spent by a pending bundle")]
+    ConflictingSpend(Tachygram),
+}
+
+/// Bundles pending for the next block. Tracks the block's unique tachygrams
+/// (nullifiers and commitments), which must fit the block polynomial degree,
+/// and the nullifiers they spend, so no two pending bundles conflict.
+#[derive(Clone, Debug, Default)]
+pub struct Mempool {
+    bundles: Vec<TachyonBundle>,
+    tachygrams: BTreeSet<[u8; 32]>,
+    nullifiers: BTreeSet<[u8; 32]>,
+}
+
+impl Mempool {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn bundles(&self) -> &[TachyonBundle] { &self.bundles }
+
+    /// Unique tachygrams the admitted bundles contribute to the block.
+    pub fn tachygram_count(&self) -> usize { self.tachygrams.len() }
+
+    /// Admit `bundle` if it passes `admit_tx`, spends no nullifier a pending
+    /// bundle already spends, and keeps the block within `DEGREE_N`.
+    pub fn admit(&mut self, bundle: TachyonBundle, window: &mut NullifierSMAWindow) -> Result<(), MempoolError> {
+        admit_tx(&bundle, window)?;
+        if let Some(nf) = bundle.nullifiers.iter().find(|nf| self.nullifiers.contains(*nf)) {
+            return Err(MempoolError::ConflictingSpend(Tachygram(*nf)));
+        }
+        let new: BTreeSet<&[u8; 32]> = bundle.nullifiers.iter()
+            .chain(&bundle.commitments)
+            .filter(|g| !self.tachygrams.contains(*g))
+            .collect();
+        let count = self.tachygrams.len() + new.len();
+        if count > DEGREE_N { return Err(MempoolError::OverBudget { count, max: DEGREE_N }); }
+        self.tachygrams.extend(new.into_iter().copied());
+        self.nullifiers.extend(bundle.nullifiers.iter().copied());
+        self.bundles.push(bundle);
+        Ok(())
+    }
+
+    /// Evict pending bundles spending any of `confirmed_nullifiers` (spent by a
+    /// newly applied block) and return them; the tachygram budget and pending
+    /// nullifiers are recounted from the bundles that remain.
+    pub fn prune_confirmed(&mut self, confirmed_nullifiers: &[[u8; 32]]) -> Vec<TachyonBundle> {
+        let confirmed: BTreeSet<&[u8; 32]> = confirmed_nullifiers.iter().collect();
+        let (evicted, kept): (Vec<_>, Vec<_>) = std::mem::take(&mut self.bundles)
+            .into_iter()
+            .partition(|b| b.nullifiers.iter().any(|nf| confirmed.contains(nf)));
+        self.tachygrams = kept.iter().flat_map(|b| b.nullifiers.iter().chain(&b.commitments)).copied().collect();
+        self.nullifiers = kept.iter().flat_map(|b| b.nullifiers.iter()).copied().collect();
+        self.bundles = kept;
+        evicted
+    }
+
+    /// Hand the pending bundles to the block builder and reset the budget.
+    pub fn take_bundles(&mut self) -> Vec<TachyonBundle> {
+        self.tachygrams.clear();
+        self.nullifiers.clear();
+        std::mem::take(&mut self.bundles)
+    }
+}
+
+/// Reject a block that adds the same note commitment twice, within one
+/// bundle or across bundles.
+pub fn check_unique_commitments(bundles: &[TachyonBundle]) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    for cm in bundles.iter().flat_map(|b| b.commitments.iter()) {
+        if !seen.insert(cm) {
+            return Err(anyhow!("duplicate commitment {} in block", Tachygram(*cm)));
+        }
+    }
+    Ok(())
+}
+
+/// Reject a block that spends a nullifier twice, within one bundle or across
+/// bundles, or that spends one already in `window`.
+pub fn check_unique_nullifiers(bundles: &[TachyonBundle], window: &NullifierSMAWindow) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    for nf in bundles.iter().flat_map(|b| b.nullifiers.iter()) {
+        if !seen.insert(nf) {
+            return Err(anyhow!("duplicate nullifier {} in block", Tachygram(*nf)));
+        }
+        if !window.is_fresh(nf) {
+            return Err(anyhow!("nullifier {} already spent", Tachygram(*nf)));
+        }
+    }
+    Ok(())
+}
+
+/// Verify block aggregates, append the block's commitments to `tree` and
+/// return the block's MMR leaf. This stub does not verify aggregate proofs yet.
+pub fn verify_block(bundles: &[TachyonBundle], window: &mut NullifierSMAWindow, tree: &mut CommitmentTree) -> Result<BlockMMRLeaf> {
     // Deterministic batch update placeholder; SMA backend will be wired later.
     let _ = window.window_len();
-    // Compute per-block digests for PCD binding and MMR leaf construction.
-    let mut all_nullifiers: Vec<[u8; 32]> = Vec::new();
-    let mut all_commitments: Vec<[u8; 32]> = Vec::new();
+    // Compute per-block digests for PCD binding and MMR leaf construction; the
+    // Orchard root digest covers the tree after this block's commitments.
+    let mut digests = BlockDigestAccumulator::new();
     for bundle in bundles {
-        all_nullifiers.extend_from_slice(&bundle.nullifiers);
-        all_commitments.extend_from_slice(&bundle.commitments);
-    }
-    let _nf_digest = compute_nullifier_block_digest(&all_nullifiers);
-    let _cm_delta_digest = compute_commitment_delta_digest(&all_commitments);
-    // Orchard root digest will be provided by the commitment tree state machine
-    // at block finalize; placeholder zeros here.
-    let _orch_digest = compute_orchard_root_digest(&[0u8; 32]);
-    let _leaf = BlockMMRLeaf {
-        orchard_root_digest: _orch_digest,
-        nullifier_block_digest: _nf_digest,
-        commitment_delta_digest: _cm_delta_digest,
-    };
-    let _leaf_hash = _leaf.leaf_hash();
+        digests.add_bundle(bundle);
+        for cm in &bundle.commitments {
+            tree.append(*cm)?;
+        }
+    }
+    digests.set_orchard_root(tree.root());
+    Ok(digests.finalize())
+}
+
+/// Full block validation: the aggregate must have been built against the
+/// window's current root (rejecting stale or forked windows), its PCD must
+/// verify in the block domain, no commitment may repeat, no nullifier may
+/// repeat or already be in the window, the aggregate must bind the block's MMR
+/// leaf, and the block's nullifiers are then inserted into the window. The
+/// domain comes from this context, never from the aggregate's own `domain`. All checks run before
+/// anything changes, so an invalid block leaves `window` and `tree` untouched.
+pub fn verify_full_block(vk: &VerifyingKey, agg: &AggPCD, bundles: &[TachyonBundle], window: &mut NullifierSMAWindow, tree: &mut CommitmentTree) -> Result<()> {
+    if agg.public.window_root != window.current_root.0 {
+        return Err(anyhow!("aggregate window root does not match current window"));
+    }
+    if !api::verify_agg(vk, agg, &DomainSep { is_block: true })? {
+        return Err(anyhow!("aggregate PCD verification failed"));
+    }
+    check_unique_commitments(bundles)?;
+    check_unique_nullifiers(bundles, window)?;
+    let nullifiers: Vec<[u8; 32]> = bundles.iter().flat_map(|b| b.nullifiers.iter().copied()).collect();
+    let batch = BatchUpdate::inserts(&nullifiers);
+    batch.validate()?;
+    let mut next_tree = tree.clone();
+    let leaf = verify_block(bundles, window, &mut next_tree)?;
+    if !agg.public.check_mmr_leaf(&leaf) {
+        return Err(anyhow!("aggregate block MMR leaf hash does not match the block"));
+    }
+    window.apply_batch(&batch)?;
+    *tree = next_tree;
     Ok(())
 }
 
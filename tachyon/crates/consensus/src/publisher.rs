@@ -1,44 +1,118 @@
 //! Block publisher helpers: build per-block polynomial commitment and proof.
 
-use accum::{ipa, poly, poseidon};
-use primitives::digest::tachygram_to_fr as tg_to_fr;
+use std::collections::BTreeMap;
+
+use accum::{ipa, params::DEGREE_N, poly, poseidon};
+use primitives::{digest::tachygrams_to_fr_batch, Tachygram};
 use crate::accum_record::{BlockAccumRecord, PallasPointBytes};
-use pcd::{block_circuit::BlockPolyWitness, api2};
-use pasta_curves::pallas;
+use pcd::{block_circuit::{commit_block_coeffs, BlockPolyWitness}, api2, ProvingKey};
+use pasta_curves::vesta::Scalar as FrVesta;
 use ff::PrimeField;
 
+/// Circuit size parameter used when publishing block records: the smallest
+/// `k` that fits a full `DEGREE_N` block.
+pub const PUBLISH_K: u32 = api2::required_k(DEGREE_N);
+
+/// Sort roots into the canonical consensus order (`poly::canonical_root_cmp`).
+/// All nodes must use this order so the block polynomial's coefficient
+/// commitment never depends on in-memory field layout.
+pub fn canonical_sort_roots(roots: &mut [FrVesta]) {
+    roots.sort_by(poly::canonical_root_cmp);
+}
+
+/// How the publisher treats distinct grams that map to the same root.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GramCollisionMode {
+    /// Merge them into one root, as for duplicate grams.
+    #[default]
+    Merge,
+    /// Fail: two distinct grams sharing a root means a `tg_to_fr` collision.
+    Strict,
+}
+
+/// Check that `roots[i]` (the root of `grams[i]`) is shared only by equal
+/// grams. Repeated grams are fine; a distinct gram on an existing root errors.
+pub fn check_root_collisions(grams: &[[u8; 32]], roots: &[FrVesta]) -> anyhow::Result<()> {
+    if grams.len() != roots.len() {
+        anyhow::bail!("{} roots for {} grams", roots.len(), grams.len());
+    }
+    let mut seen: BTreeMap<[u8; 32], &[u8; 32]> = BTreeMap::new();
+    for (gram, root) in grams.iter().zip(roots) {
+        let prev = *seen.entry(root.to_repr()).or_insert(gram);
+        if prev != gram {
+            anyhow::bail!("grams {} and {} map to the same root", Tachygram(*prev), Tachygram(*gram));
+        }
+    }
+    Ok(())
+}
+
+/// Partition a block's grams into sub-blocks of at most `DEGREE_N` unique
+/// grams each. Grams are sorted by byte value and deduped first, so every node
+/// splits the same set identically. An empty input yields one empty chunk.
+pub fn split_grams_for_blocks(grams: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+    let mut unique = grams.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.is_empty() { return vec![vec![]]; }
+    unique.chunks(DEGREE_N).map(|c| c.to_vec()).collect()
+}
+
 /// Build a BlockAccumRecord from prior accumulator A_i and block tachygrams.
-/// Uses FFT for large batches and falls back to simple method otherwise.
-pub fn build_block_record(a_i: &PallasPointBytes, grams: &[[u8; 32]]) -> anyhow::Result<BlockAccumRecord> {
-    // Map grams → Fr(Vesta), sort and dedup
-    let mut roots: Vec<_> = grams.iter().map(tg_to_fr).collect();
-    roots.sort();
+/// Coefficients come from `poly::roots_to_coeffs_auto`.
+/// Proves at the `k` the key was generated for (`PUBLISH_K` in production).
+pub fn build_block_record(pk: &ProvingKey, a_i: &PallasPointBytes, grams: &[[u8; 32]]) -> anyhow::Result<BlockAccumRecord> {
+    build_block_record_with_mode(pk, a_i, grams, GramCollisionMode::Merge)
+}
+
+/// `build_block_record` with an explicit policy for grams sharing a root.
+pub fn build_block_record_with_mode(
+    pk: &ProvingKey,
+    a_i: &PallasPointBytes,
+    grams: &[[u8; 32]],
+    mode: GramCollisionMode,
+) -> anyhow::Result<BlockAccumRecord> {
+    // Map grams → Fr(Vesta), sort canonically and dedup
+    let mut roots = tachygrams_to_fr_batch(grams);
+    if mode == GramCollisionMode::Strict { check_root_collisions(grams, &roots)?; }
+    canonical_sort_roots(&mut roots);
     roots.dedup();
+    let k = pk.info()?.k;
+    if api2::required_k(roots.len()) > k {
+        anyhow::bail!("{} roots need k >= {}, proving key is for k={}", roots.len(), api2::required_k(roots.len()), k);
+    }
 
-    // Compute coefficients via FFT when large, else divide-and-conquer
-    let coeffs = if roots.len() >= 64 {
-        poly::roots_to_coeffs_fft(&roots)
-    } else {
-        poly::roots_to_coeffs_parallel(&roots)
-    };
+    // Method picked by size. An empty block has the constant polynomial 1:
+    // P_i = commit([1]) and A_{i+1} = [h_i]A_i + P_i, so the chain still advances.
+    let coeffs = poly::roots_to_coeffs_auto(&roots);
 
     // Map coeffs (FrVesta) → Pallas scalars and commit
-    let scalars: Vec<pallas::Scalar> = coeffs.iter().map(|x| {
-        let xb = ff::PrimeField::to_repr(x);
-        let mut b32 = [0u8; 32];
-        b32.copy_from_slice(xb.as_ref());
-        ipa::map_vesta_scalar_to_pallas(&b32)
-    }).collect();
-    let p_i_aff = ipa::commit_coeffs(&scalars);
-    let p_i_bytes = PallasPointBytes(ipa::encode_point(&p_i_aff));
+    let p_i_aff = commit_block_coeffs(&coeffs);
 
     // Build circuit witness and produce proof (mock for now)
-    let a_i_aff = ipa::decode_point(&a_i.0).unwrap_or(ipa::g0());
+    let a_i_aff = ipa::decode_point(&a_i.0).ok_or_else(|| anyhow::anyhow!("invalid accumulator point A_i"))?;
     let wit = BlockPolyWitness { roots, coeffs, p_i: p_i_aff, a_i: a_i_aff };
-    let (public, proof) = api2::prove_block(&api2::Params { k: 18 }, &wit)?;
+    let (public, proof) = api2::prove_block(&api2::Params { k }, pk, &wit)?;
     // Public includes p_i,a_i,a_next bytes; recompute h_i for record
-    let h_i = poseidon::hash_A_h(&public.a_i_bytes, &public.p_i_bytes);
-    Ok(BlockAccumRecord { p_i: p_i_bytes, h_i, a_next: PallasPointBytes(public.a_next_bytes), proof })
+    let h_i = poseidon::compute_h_i(&public.a_i_bytes, &public.p_i_bytes);
+    Ok(BlockAccumRecord { p_i: PallasPointBytes(public.p_i_bytes), h_i, a_next: PallasPointBytes(public.a_next_bytes), proof })
+}
+
+/// Build one record per `split_grams_for_blocks` chunk, each stepping from the
+/// previous record's `a_next` (the first from `a_i`).
+pub fn build_block_records(pk: &ProvingKey, a_i: &PallasPointBytes, grams: &[[u8; 32]]) -> anyhow::Result<Vec<BlockAccumRecord>> {
+    let mut a = *a_i;
+    let mut records = Vec::new();
+    for chunk in split_grams_for_blocks(grams) {
+        let record = build_block_record(pk, &a, &chunk)?;
+        a = record.a_next;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Build the first record of a chain, stepping from `accum::genesis_accumulator()`.
+pub fn build_genesis_record(pk: &ProvingKey, grams: &[[u8; 32]]) -> anyhow::Result<BlockAccumRecord> {
+    build_block_record(pk, &accum::genesis_accumulator(), grams)
 }
 
 
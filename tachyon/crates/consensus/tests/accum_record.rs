@@ -0,0 +1,205 @@
+use consensus::{build_block_record, build_genesis_record, fingerprints_diverge, verify_chain, verify_chain_headers, BlockAccumRecord, BlockHeader, BlockMMRLeaf, PallasPointBytes};
+use ff::Field;
+use pasta_curves::{pallas, vesta::Scalar as FrVesta};
+
+mod common;
+
+#[test]
+fn challenge_point_distinct_and_stable() {
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let r1 = build_block_record(&pk, &a_i, &[[1u8; 32], [2u8; 32]]).unwrap();
+    let r2 = build_block_record(&pk, &a_i, &[[3u8; 32]]).unwrap();
+    assert_ne!(r1.challenge_point(), r2.challenge_point());
+
+    let json = serde_json::to_string(&r1).unwrap();
+    let back: BlockAccumRecord = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.challenge_point(), r1.challenge_point());
+
+    // Opening with the publisher's coefficients passes; a wrong value fails.
+    let mut roots = vec![
+        primitives::digest::tachygram_to_fr(&[1u8; 32]),
+        primitives::digest::tachygram_to_fr(&[2u8; 32]),
+    ];
+    consensus::canonical_sort_roots(&mut roots);
+    let coeffs = accum::poly::roots_to_coeffs_parallel(&roots);
+    let z = r1.challenge_point();
+    let value = roots.iter().fold(FrVesta::ONE, |acc, a| acc * (z - a));
+    assert!(r1.check_opening(&coeffs, value));
+    assert!(!r1.check_opening(&coeffs, value + FrVesta::ONE));
+    assert!(!r2.check_opening(&coeffs, value));
+}
+
+#[test]
+fn block_records_chain_and_reject_broken_link() {
+    let pk = common::block_pk();
+    let genesis = accum::genesis_accumulator();
+    let mut records = Vec::new();
+    let mut a_i = genesis;
+    for grams in [vec![[1u8; 32]], vec![[2u8; 32], [3u8; 32]], vec![]] {
+        let rec = build_block_record(&pk, &a_i, &grams).unwrap();
+        a_i = rec.a_next;
+        records.push(rec);
+    }
+    assert!(records[0].links_to(&records[1]) && records[1].links_to(&records[2]));
+    verify_chain(&records, &genesis).unwrap();
+
+    // Skipping a block breaks the link.
+    assert!(!records[0].links_to(&records[2]));
+    let skipped = [records[0].clone(), records[2].clone()];
+    assert!(verify_chain(&skipped, &genesis).is_err());
+    assert!(verify_chain(&records[1..], &genesis).is_err());
+}
+
+#[test]
+fn block_headers_chain_and_reject_wrong_a_next() {
+    let pk = common::block_pk();
+    let genesis = accum::genesis_accumulator();
+    let mut headers = Vec::new();
+    let mut a_i = genesis;
+    for (height, grams) in [vec![[1u8; 32]], vec![[2u8; 32], [3u8; 32]], vec![[4u8; 32]]].into_iter().enumerate() {
+        let rec = build_block_record(&pk, &a_i, &grams).unwrap();
+        let leaf = BlockMMRLeaf::default();
+        let header = BlockHeader::new(&rec, &leaf, height as u64);
+        assert_eq!(header.mmr_leaf_hash, leaf.leaf_hash());
+        assert_eq!(BlockHeader::from_bytes(&header.to_bytes()).unwrap(), header);
+        a_i = rec.a_next;
+        headers.push(header);
+    }
+    verify_chain_headers(&headers, 0, &genesis).unwrap();
+    // Resume from the checkpoint after block 0.
+    verify_chain_headers(&headers[1..], 1, &headers[0].a_next).unwrap();
+    assert!(verify_chain_headers(&headers[1..], 2, &headers[0].a_next).is_err());
+    assert!(verify_chain_headers(&headers[1..], 1, &genesis).is_err());
+
+    let mut forged = headers.clone();
+    forged[1].a_next = headers[2].a_next;
+    assert!(verify_chain_headers(&forged, 0, &genesis).is_err());
+    let mut renumbered = headers.clone();
+    renumbered[2].height = 5;
+    assert!(verify_chain_headers(&renumbered, 0, &genesis).is_err());
+    assert!(BlockHeader::from_bytes(&headers[0].to_bytes()[1..]).is_err());
+}
+
+#[test]
+fn identity_accumulator_only_allowed_at_genesis() {
+    let identity = PallasPointBytes([0u8; 32]);
+    let g = accum::ipa::g0();
+    assert_eq!(identity.0, accum::ipa::encode_point(&pasta_curves::pallas::Affine::default()));
+    let p_i = PallasPointBytes(accum::ipa::encode_point(&g));
+
+    assert!(BlockAccumRecord::from_ai_pi(&identity, &p_i, vec![]).is_err());
+    assert!(BlockAccumRecord::from_ai_pi(&p_i, &identity, vec![]).is_err());
+    assert!(BlockAccumRecord::from_genesis(&p_i, &identity, vec![]).is_err());
+
+    // From an identity A_0 the first step gives A_1 = P_0.
+    let rec = BlockAccumRecord::from_genesis(&identity, &p_i, vec![]).unwrap();
+    assert_eq!(rec.a_next, p_i);
+    assert!(!rec.verify_step(&identity));
+    assert!(rec.verify_genesis_step(&identity));
+    verify_chain(std::slice::from_ref(&rec), &identity).unwrap();
+
+    let next = BlockAccumRecord::from_ai_pi(&rec.a_next, &p_i, vec![]).unwrap();
+    assert!(rec.links_to(&next));
+    verify_chain(&[rec, next], &identity).unwrap();
+}
+
+#[test]
+fn genesis_accumulator_is_stable_and_starts_chain() {
+    let genesis = accum::genesis_accumulator();
+    assert_eq!(hex::encode(genesis.0), "8ab45042fe87587073048a12e9e8bc010f253a77102857453b561c2dbbe4e48f");
+    assert_eq!(genesis, accum::genesis_accumulator());
+    assert_ne!(genesis.0, accum::ipa::encode_point(&accum::ipa::g0()));
+
+    let pk = common::block_pk();
+    let first = build_genesis_record(&pk, &[[1u8; 32]]).unwrap();
+    let second = build_block_record(&pk, &first.a_next, &[[2u8; 32]]).unwrap();
+    assert!(first.verify_step(&genesis));
+    assert!(first.links_to(&second));
+    verify_chain(&[first, second], &genesis).unwrap();
+
+    assert!(build_block_record(&pk, &PallasPointBytes([0xff; 32]), &[[1u8; 32]]).is_err());
+}
+
+#[test]
+fn commitment_fingerprints_detect_forks() {
+    let pk = common::block_pk();
+    let genesis = accum::genesis_accumulator();
+    let ours = build_genesis_record(&pk, &[[1u8; 32]]).unwrap();
+    let same = build_block_record(&pk, &genesis, &[[1u8; 32]]).unwrap();
+    let fork = build_block_record(&pk, &genesis, &[[2u8; 32]]).unwrap();
+    assert_ne!(ours.p_i, fork.p_i);
+
+    assert_eq!(ours.commitment_fingerprint(), same.commitment_fingerprint());
+    assert!(!fingerprints_diverge(&ours, &same));
+    assert!(fingerprints_diverge(&ours, &fork));
+
+    // Proof bytes do not enter the fingerprint.
+    let reproved = BlockAccumRecord { proof: vec![1, 2, 3], ..ours.clone() };
+    assert!(!fingerprints_diverge(&ours, &reproved));
+}
+
+#[test]
+fn record_json_roundtrip_and_malformed_point() {
+    let pk = common::block_pk();
+    let mut rec = build_genesis_record(&pk, &[[5u8; 32]]).unwrap();
+    rec.proof = vec![0xde, 0xad, 0xbe, 0xef, 0x01];
+    let json = rec.to_json().unwrap();
+    let v: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(v["p_i"], rec.p_i.hex());
+    assert_eq!(v["a_next"], rec.a_next.hex());
+    assert_eq!(v["h_i"], hex::encode(rec.h_i));
+    assert_eq!(v["proof"], "3q2+7wE=");
+    assert_eq!(BlockAccumRecord::from_json(&json).unwrap(), rec);
+
+    // Non-hex characters, wrong length and an off-curve point are all rejected.
+    let p_i = rec.p_i.hex();
+    for bad in [format!("zz{}", &p_i[2..]), p_i[2..].to_string(), "ff".repeat(32)] {
+        let json = json.replace(&p_i, &bad);
+        assert!(BlockAccumRecord::from_json(&json).is_err(), "{bad}");
+    }
+    assert!(BlockAccumRecord::from_json(&json.replace("3q2+7wE=", "3q2+7wE")).is_err());
+}
+
+#[test]
+fn block_commitment_opening_verifies() {
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let grams = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let record = build_block_record(&pk, &a_i, &grams).unwrap();
+    let other = build_block_record(&pk, &a_i, &grams[..2]).unwrap();
+
+    let mut roots: Vec<FrVesta> = grams.iter().map(primitives::digest::tachygram_to_fr).collect();
+    consensus::canonical_sort_roots(&mut roots);
+    let coeffs = accum::poly::roots_to_coeffs(&roots);
+    let bases = accum::ipa::IpaBases::global();
+    let point = FrVesta::from(7u64);
+    let (value, proof) = consensus::prove_block_commitment(&coeffs, point, bases).unwrap();
+
+    assert!(consensus::verify_block_commitment(&record, &proof, point, value, bases));
+    assert!(!consensus::verify_block_commitment(&record, &proof, point, value + pallas::Scalar::ONE, bases));
+    assert!(!consensus::verify_block_commitment(&record, &proof, point + FrVesta::ONE, value, bases));
+    assert!(!consensus::verify_block_commitment(&other, &proof, point, value, bases));
+
+    // The opened value is the block polynomial at `point`, so the roots alone
+    // pin it. When the coefficients and every partial sum stay below both
+    // moduli (roots -2, -3 give X^2 + 5X + 6) it is also the embedding of
+    // `eval_from_roots`.
+    assert_eq!(value, consensus::block_opening_value(&roots, point));
+    assert!(consensus::verify_block_commitment_roots(&record, &proof, point, &roots, bases));
+    assert!(!consensus::verify_block_commitment_roots(&record, &proof, point, &roots[..2], bases));
+    let small: Vec<FrVesta> = [2u64, 3].map(|r| -FrVesta::from(r)).to_vec();
+    let (small_value, _) = consensus::prove_block_commitment(&accum::poly::roots_to_coeffs(&small), point, bases).unwrap();
+    assert_eq!(small_value, accum::ipa::embed_vesta_scalar(&accum::poly::eval_from_roots(&small, point)));
+}
+
+#[test]
+fn publisher_and_from_ai_pi_agree_on_h_i() {
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let published = build_block_record(&pk, &a_i, &[[4u8; 32], [5u8; 32]]).unwrap();
+    let rebuilt = BlockAccumRecord::from_ai_pi(&a_i, &published.p_i, vec![]).unwrap();
+    assert_eq!(published.h_i, rebuilt.h_i);
+    assert_eq!(published.h_i, accum::poseidon::compute_h_i(&a_i.0, &published.p_i.0));
+    assert_eq!(published.a_next, rebuilt.a_next);
+}
@@ -0,0 +1,8 @@
+//! Fixtures shared by the consensus integration tests.
+
+use pcd::{api2, ProvingKey};
+
+/// Block-circuit proving key at the small size these tests prove at.
+pub fn block_pk() -> ProvingKey {
+    api2::setup(&api2::Params { k: 8 }).unwrap().0
+}
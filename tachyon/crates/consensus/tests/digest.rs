@@ -0,0 +1,153 @@
+use accum::poseidon::compress_nodes;
+use consensus::{
+    commitment_delta_inclusion_proof, compute_block_digests, compute_commitment_delta_digest, compute_commitment_delta_root, compute_nullifier_block_digest, compute_orchard_root_digest,
+    compute_unified_tachygram_block, Blake2bBackend, BlockDigestAccumulator, BlockMMRLeaf, CommitmentDeltaDigest,
+    DigestBackend, NullifierBlockDigest, OrchardRootDigest,
+};
+use primitives::TachyonBundle;
+
+fn bundle(nfs: &[u8], cms: &[u8]) -> TachyonBundle {
+    let mut b = TachyonBundle::new();
+    b.nullifiers = nfs.iter().map(|&x| [x; 32]).collect();
+    b.commitments = cms.iter().map(|&x| [x; 32]).collect();
+    b
+}
+
+#[test]
+fn incremental_digests_match_batch() {
+    let bundles = [bundle(&[1, 2], &[10]), bundle(&[], &[11, 12]), bundle(&[3], &[])];
+    let mut acc = BlockDigestAccumulator::new();
+    acc.set_orchard_root([7u8; 32]);
+    for b in &bundles {
+        acc.add_bundle(b);
+    }
+    let leaf = acc.finalize();
+
+    let nfs: Vec<[u8; 32]> = bundles.iter().flat_map(|b| b.nullifiers.clone()).collect();
+    let cms: Vec<[u8; 32]> = bundles.iter().flat_map(|b| b.commitments.clone()).collect();
+    assert_eq!(leaf.nullifier_block_digest, compute_nullifier_block_digest(&nfs));
+    assert_eq!(leaf.commitment_delta_digest, compute_commitment_delta_digest(&cms));
+    assert_eq!(leaf.orchard_root_digest, compute_orchard_root_digest(&[7u8; 32]));
+
+    let empty = BlockDigestAccumulator::new().finalize();
+    assert_eq!(empty.nullifier_block_digest, compute_nullifier_block_digest(&[]));
+}
+
+#[test]
+fn block_digests_golden() {
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    assert_eq!(hex::encode(compute_orchard_root_digest(&a).0), "49bfd03e950e547ab63327c396bbe40631bc0b165e89a411112d25f6876ba4e3");
+    assert_eq!(hex::encode(compute_nullifier_block_digest(&[a, b]).0), "13c146baea8fd17727043e7222709ad77b2d97ea4cebea9a2659d54e8dc07bdd");
+    assert_eq!(hex::encode(compute_commitment_delta_digest(&[a, b]).0), "3a034e2e2a7ae0e5b5b59e62d9e4703897a3405b4b6d639fb61d365c13552528");
+    let leaf = BlockMMRLeaf {
+        orchard_root_digest: OrchardRootDigest(a),
+        nullifier_block_digest: NullifierBlockDigest(b),
+        commitment_delta_digest: CommitmentDeltaDigest(a),
+    };
+    assert_eq!(hex::encode(leaf.leaf_hash()), "807064fa1cdf6c5f809b51f1c478ad8adbb2a9c8c38162c663cfcde97609a602");
+    assert_eq!(hex::encode(compute_unified_tachygram_block(&[a], &[b], &[a], &[5])), "b6185b090460fc9f12ac80416e1afea441a2ad7413b45530a689d770faef9070");
+}
+
+
+#[test]
+fn digest_newtypes_hex_roundtrip() {
+    let b = [0x3cu8; 32];
+    assert_eq!(OrchardRootDigest::from_hex(&OrchardRootDigest(b).hex()).unwrap(), OrchardRootDigest(b));
+    assert_eq!(NullifierBlockDigest::from_hex(&NullifierBlockDigest(b).hex()).unwrap(), NullifierBlockDigest(b));
+    assert_eq!(CommitmentDeltaDigest::from_hex(&CommitmentDeltaDigest(b).hex()).unwrap(), CommitmentDeltaDigest(b));
+    assert!(OrchardRootDigest::from_hex("3c3c").is_err());
+}
+
+/// Stand-in for a Poseidon digest backend: folds items with `compress_nodes`.
+struct PoseidonStub;
+
+impl PoseidonStub {
+    fn fold(tag: u8, items: &[[u8; 32]]) -> [u8; 32] {
+        items.iter().fold([tag; 32], |acc, x| compress_nodes(&acc, x))
+    }
+}
+
+impl DigestBackend for PoseidonStub {
+    fn orchard_root(&self, root: &[u8; 32]) -> OrchardRootDigest { OrchardRootDigest(Self::fold(1, &[*root])) }
+    fn nullifier_block(&self, nfs: &[[u8; 32]]) -> NullifierBlockDigest { NullifierBlockDigest(Self::fold(2, nfs)) }
+    fn commitment_delta(&self, cms: &[[u8; 32]]) -> CommitmentDeltaDigest { CommitmentDeltaDigest(Self::fold(3, cms)) }
+    fn mmr_leaf(&self, leaf: &BlockMMRLeaf) -> [u8; 32] {
+        Self::fold(4, &[leaf.orchard_root_digest.0, leaf.nullifier_block_digest.0, leaf.commitment_delta_digest.0])
+    }
+}
+
+#[test]
+fn digest_backends_default_and_alternative() {
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    let leaf = BlockMMRLeaf {
+        orchard_root_digest: OrchardRootDigest(a),
+        nullifier_block_digest: NullifierBlockDigest(b),
+        commitment_delta_digest: CommitmentDeltaDigest(a),
+    };
+    assert_eq!(Blake2bBackend.orchard_root(&a), compute_orchard_root_digest(&a));
+    assert_eq!(Blake2bBackend.nullifier_block(&[a, b]), compute_nullifier_block_digest(&[a, b]));
+    assert_eq!(Blake2bBackend.commitment_delta(&[a, b]), compute_commitment_delta_digest(&[a, b]));
+    assert_eq!(leaf.leaf_hash_with(&Blake2bBackend), leaf.leaf_hash());
+
+    assert_ne!(PoseidonStub.orchard_root(&a), compute_orchard_root_digest(&a));
+    assert_ne!(PoseidonStub.nullifier_block(&[a, b]), compute_nullifier_block_digest(&[a, b]));
+    assert_ne!(PoseidonStub.commitment_delta(&[a, b]), compute_commitment_delta_digest(&[a, b]));
+    assert_ne!(leaf.leaf_hash_with(&PoseidonStub), leaf.leaf_hash());
+}
+
+#[test]
+fn commitment_delta_root_and_inclusion_proofs() {
+    let cms: Vec<[u8; 32]> = (1u8..=5).map(|x| [x; 32]).collect();
+    let root = compute_commitment_delta_root(&cms);
+    assert_eq!(root, compute_commitment_delta_root(&cms));
+    let mut swapped = cms.clone();
+    swapped.swap(0, 1);
+    assert_ne!(root, compute_commitment_delta_root(&swapped));
+    // A trailing zero commitment is not the same as padding.
+    let mut padded = cms.clone();
+    padded.push([0u8; 32]);
+    assert_ne!(root, compute_commitment_delta_root(&padded));
+
+    for (i, cm) in cms.iter().enumerate() {
+        let proof = commitment_delta_inclusion_proof(&cms, i).unwrap();
+        assert!(proof.verify(&root, cm), "index {i}");
+        assert!(!proof.verify(&root, &[9u8; 32]));
+    }
+    let mut wrong_index = commitment_delta_inclusion_proof(&cms, 2).unwrap();
+    wrong_index.index = 3;
+    assert!(!wrong_index.verify(&root, &cms[2]));
+    assert!(commitment_delta_inclusion_proof(&cms, 5).is_none());
+}
+
+#[test]
+fn one_pass_block_digests_match_separate() {
+    let bundles = [bundle(&[1, 2], &[10]), bundle(&[], &[11, 12]), bundle(&[3], &[]), bundle(&[4, 5, 6], &[13, 14])];
+    let root = [9u8; 32];
+    let nfs: Vec<[u8; 32]> = bundles.iter().flat_map(|b| b.nullifiers.clone()).collect();
+    let cms: Vec<[u8; 32]> = bundles.iter().flat_map(|b| b.commitments.clone()).collect();
+    let separate = BlockMMRLeaf {
+        orchard_root_digest: compute_orchard_root_digest(&root),
+        nullifier_block_digest: compute_nullifier_block_digest(&nfs),
+        commitment_delta_digest: compute_commitment_delta_digest(&cms),
+    };
+    assert_eq!(compute_block_digests(&bundles, &root), separate);
+    assert_eq!(
+        compute_block_digests(&[], &root),
+        BlockMMRLeaf { nullifier_block_digest: compute_nullifier_block_digest(&[]), commitment_delta_digest: compute_commitment_delta_digest(&[]), ..separate }
+    );
+}
+
+#[test]
+fn streamed_set_digests_match_buffered() {
+    let items: Vec<[u8; 32]> = (0u32..10_000)
+        .map(|i| {
+            let mut x = [0x5a; 32];
+            x[..4].copy_from_slice(&i.to_le_bytes());
+            x
+        })
+        .collect();
+    let buf: Vec<u8> = items.iter().flatten().copied().collect();
+    assert_eq!(compute_commitment_delta_digest(&items).0, accum::prf::blake2b_32(b"cm.delta.v1\0\0\0\0\0", &buf));
+    assert_eq!(compute_nullifier_block_digest(&items).0, accum::prf::blake2b_32(b"nf.block.v1\0\0\0\0\0", &buf));
+    assert_eq!(compute_commitment_delta_digest(&[]).0, accum::prf::blake2b_32(b"cm.delta.v1\0\0\0\0\0", &[]));
+}
@@ -0,0 +1,31 @@
+use consensus::{aggregate_fee_summary, FeeSummary};
+use primitives::TachyonBundle;
+
+fn bundle(fee: u64, nullifiers: usize, commitments: usize) -> TachyonBundle {
+    let mut b = TachyonBundle::new();
+    b.fee = fee;
+    b.nullifiers = vec![[1u8; 32]; nullifiers];
+    b.commitments = vec![[2u8; 32]; commitments];
+    b
+}
+
+#[test]
+fn fee_summary_totals_and_median() {
+    let bundles = [bundle(10, 1, 2), bundle(1, 2, 0), bundle(5, 1, 1)];
+    let s = aggregate_fee_summary(&bundles).unwrap();
+    assert_eq!(
+        s,
+        FeeSummary {
+            bundle_count: 3,
+            total_fee: 16,
+            min_fee: 1,
+            max_fee: 10,
+            median_fee: 5,
+            nullifier_count: 4,
+            commitment_count: 3,
+        }
+    );
+
+    assert_eq!(aggregate_fee_summary(&[]).unwrap(), FeeSummary::default());
+    assert!(aggregate_fee_summary(&[bundle(u64::MAX, 0, 0), bundle(1, 0, 0)]).is_err());
+}
@@ -0,0 +1,245 @@
+use accum::{NullifierSMAWindow, Root};
+use consensus::{check_unique_commitments, compute_orchard_root_digest, AggMmrLeafCheck, verify_block, verify_full_block, BlockMMRLeaf, CommitmentTree, Mempool, MempoolError};
+use pcd::{api::{self, AggPCD}, AggPCDPublic, DomainSep, ProvingKey, VerifyingKey};
+use primitives::{Tachyaction, TachyonBundle};
+
+/// Block-domain aggregate against `window_root` binding `leaf`.
+fn agg_for(window_root: [u8; 32], leaf: &BlockMMRLeaf) -> AggPCD {
+    let public = AggPCDPublic {
+        total_count: 1,
+        included_txids_digest: [0u8; 32],
+        window_root,
+        block_mmr_leaf_hash: leaf.leaf_hash(),
+        domain: DomainSep { is_block: true },
+        vk_fingerprint: VerifyingKey(vec![]).fingerprint(),
+    };
+    api::prove_agg(&ProvingKey(vec![]), public, &[]).unwrap()
+}
+
+/// The MMR leaf `bundles` produce on top of `window` and `tree`, without changing them.
+fn leaf_for(bundles: &[TachyonBundle], window: &NullifierSMAWindow, tree: &CommitmentTree) -> BlockMMRLeaf {
+    verify_block(bundles, &mut window.clone(), &mut tree.clone()).unwrap()
+}
+
+#[test]
+fn aggregate_window_root_must_match() {
+    let vk = VerifyingKey(vec![]);
+    let mut window = NullifierSMAWindow::new(accum::params::ACCUM_HEIGHT, Root([3u8; 32]));
+    let mut tree = CommitmentTree::new();
+    let mut bundle = TachyonBundle::new();
+    bundle.nullifiers.push([8u8; 32]);
+
+    let leaf = leaf_for(std::slice::from_ref(&bundle), &window, &tree);
+    assert!(verify_full_block(&vk, &agg_for([4u8; 32], &leaf), &[bundle.clone()], &mut window, &mut tree).is_err());
+    assert_eq!(window.current_root, Root([3u8; 32]));
+
+    verify_full_block(&vk, &agg_for([3u8; 32], &leaf), &[bundle.clone()], &mut window, &mut tree).unwrap();
+    assert!(!window.is_fresh(&[8u8; 32]));
+
+    // The window advanced, so an aggregate against the old root is now stale.
+    let leaf = leaf_for(std::slice::from_ref(&bundle), &window, &tree);
+    assert!(verify_full_block(&vk, &agg_for([3u8; 32], &leaf), &[bundle], &mut window, &mut tree).is_err());
+}
+
+#[test]
+fn full_block_rejects_double_spends() {
+    let vk = VerifyingKey(vec![]);
+    let mut window = NullifierSMAWindow::default();
+    let mut tree = CommitmentTree::new();
+    let (a, b) = (spend_bundle(1, 0..1), spend_bundle(1, 1..2));
+
+    // The same nullifier twice in one block.
+    let agg = agg_for(window.current_root.0, &leaf_for(&[a.clone(), b.clone()], &window, &tree));
+    assert!(verify_full_block(&vk, &agg, &[a.clone(), b.clone()], &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 0);
+    assert_eq!(window.window_len(), 1);
+
+    // A nullifier spent by an earlier block.
+    let agg = agg_for(window.current_root.0, &leaf_for(std::slice::from_ref(&a), &window, &tree));
+    verify_full_block(&vk, &agg, &[a], &mut window, &mut tree).unwrap();
+    let agg = agg_for(window.current_root.0, &leaf_for(std::slice::from_ref(&b), &window, &tree));
+    assert!(verify_full_block(&vk, &agg, &[b], &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 1);
+    assert_eq!(window.window_len(), 2);
+}
+
+#[test]
+fn full_block_over_batch_limit_leaves_state_untouched() {
+    let vk = VerifyingKey(vec![]);
+    let mut window = NullifierSMAWindow::default();
+    let mut tree = CommitmentTree::new();
+    let bundles: Vec<TachyonBundle> = (0..accum::params::MAX_BATCH_ITEMS as u32 + 1).map(|i| spend_bundle(i, i..i + 1)).collect();
+    let root = window.current_root;
+    let agg = agg_for(root.0, &leaf_for(&bundles, &window, &tree));
+    assert!(verify_full_block(&vk, &agg, &bundles, &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 0);
+    assert_eq!((window.current_root, window.window_len()), (root, 1));
+}
+
+#[test]
+fn block_aggregate_must_bind_mmr_leaf_and_block_domain() {
+    let vk = VerifyingKey(vec![]);
+    let mut bundle = TachyonBundle::new();
+    bundle.nullifiers.push([8u8; 32]);
+    bundle.commitments.push([9u8; 32]);
+    let mut window = NullifierSMAWindow::new(accum::params::ACCUM_HEIGHT, Root([3u8; 32]));
+    let mut tree = CommitmentTree::new();
+    let leaf = leaf_for(std::slice::from_ref(&bundle), &window, &tree);
+
+    let wrong_leaf = BlockMMRLeaf { commitment_delta_digest: consensus::CommitmentDeltaDigest([0xee; 32]), ..leaf };
+    let agg = agg_for([3u8; 32], &wrong_leaf);
+    assert!(!agg.public.check_mmr_leaf(&leaf));
+    assert!(verify_full_block(&vk, &agg, &[bundle.clone()], &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 0);
+    assert!(window.is_fresh(&[8u8; 32]));
+
+    // A tx-domain aggregate, or one relabeled as a block aggregate, is refused:
+    // the domain comes from the block context, not from the aggregate.
+    let mut tx_agg = agg_for([3u8; 32], &leaf);
+    tx_agg.public.domain = DomainSep { is_block: false };
+    tx_agg = api::prove_agg(&ProvingKey(vec![]), tx_agg.public, &[]).unwrap();
+    assert!(verify_full_block(&vk, &tx_agg, &[bundle.clone()], &mut window, &mut tree).is_err());
+    let mut relabeled = tx_agg;
+    relabeled.public.domain = DomainSep { is_block: true };
+    assert!(verify_full_block(&vk, &relabeled, &[bundle.clone()], &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 0);
+
+    let agg = agg_for([3u8; 32], &leaf);
+    assert!(agg.public.check_mmr_leaf(&leaf));
+    verify_full_block(&vk, &agg, &[bundle], &mut window, &mut tree).unwrap();
+    assert_eq!(tree.size(), 1);
+    assert!(!window.is_fresh(&[8u8; 32]));
+}
+
+#[test]
+fn genesis_tx_public_anchors_the_empty_commitment_tree() {
+    let genesis = pcd::TxPCDPublic::genesis();
+    assert_eq!(genesis.range_root_min, CommitmentTree::new().root());
+}
+
+#[test]
+fn block_orchard_digest_tracks_commitment_tree() {
+    let mut window = NullifierSMAWindow::default();
+    let mut bundle = TachyonBundle::new();
+    bundle.commitments = vec![[1u8; 32], [2u8; 32]];
+
+    let mut tree = CommitmentTree::new();
+    let empty_root = tree.root();
+    let leaf1 = verify_block(&[bundle.clone()], &mut window, &mut tree).unwrap();
+    assert_eq!(tree.size(), 2);
+    assert_ne!(tree.root(), empty_root);
+    assert_eq!(leaf1.orchard_root_digest, compute_orchard_root_digest(&tree.root()));
+
+    // Same commitments from the same start state give the same digest.
+    let mut replay = CommitmentTree::new();
+    assert_eq!(verify_block(&[bundle.clone()], &mut window, &mut replay).unwrap(), leaf1);
+
+    // A further block changes the digest; manual appends agree with the verifier.
+    let leaf2 = verify_block(&[bundle], &mut window, &mut tree).unwrap();
+    assert_ne!(leaf2.orchard_root_digest, leaf1.orchard_root_digest);
+    let mut manual = CommitmentTree::new();
+    for cm in [[1u8; 32], [2u8; 32], [1u8; 32], [2u8; 32]] {
+        manual.append(cm).unwrap();
+    }
+    assert_eq!(manual.root(), tree.root());
+}
+
+/// Zero-value bundle spending `nf` and creating outputs `cms` (two tachygram sources).
+fn spend_bundle(nf: u32, cms: std::ops::Range<u32>) -> TachyonBundle {
+    let key = |tag: u8, i: u32| {
+        let mut k = [tag; 32];
+        k[28..].copy_from_slice(&i.to_be_bytes());
+        k
+    };
+    let mut b = TachyonBundle::new();
+    b.actions.push(Tachyaction::Spend { nf: key(0xaa, nf), value: 0 });
+    b.nullifiers.push(key(0xaa, nf));
+    for i in cms {
+        b.actions.push(Tachyaction::Output { cm: key(0xcc, i), value: 0 });
+        b.commitments.push(key(0xcc, i));
+    }
+    b
+}
+
+#[test]
+fn mempool_rejects_bundle_over_degree_budget() {
+    let max = accum::params::DEGREE_N;
+    let mut window = NullifierSMAWindow::default();
+    let mut pool = Mempool::new();
+    // 4 bundles of 1 nullifier + 1023 commitments fill the block exactly.
+    for i in 0..4u32 {
+        pool.admit(spend_bundle(i, i * 1023..(i + 1) * 1023), &mut window).unwrap();
+    }
+    assert_eq!(pool.tachygram_count(), max);
+
+    // Commitments already counted do not use budget; a new nullifier does.
+    match pool.admit(spend_bundle(4, 0..3), &mut window) {
+        Err(MempoolError::OverBudget { count, max: m }) => assert_eq!((count, m), (max + 1, max)),
+        other => panic!("expected OverBudget, got {:?}", other),
+    }
+    assert_eq!((pool.bundles().len(), pool.tachygram_count()), (4, max));
+
+    assert_eq!(pool.take_bundles().len(), 4);
+    pool.admit(spend_bundle(4, 0..3), &mut window).unwrap();
+    assert_eq!(pool.tachygram_count(), 4);
+}
+
+#[test]
+fn mempool_rejects_conflicting_pending_spend() {
+    let mut window = NullifierSMAWindow::default();
+    let mut pool = Mempool::new();
+    let first = spend_bundle(0, 0..2);
+    pool.admit(first.clone(), &mut window).unwrap();
+
+    match pool.admit(spend_bundle(0, 2..3), &mut window) {
+        Err(MempoolError::ConflictingSpend(nf)) => assert_eq!(nf.0, first.nullifiers[0]),
+        other => panic!("expected ConflictingSpend, got {:?}", other),
+    }
+    assert_eq!((pool.bundles().len(), pool.tachygram_count()), (1, 3));
+
+    // Once the first spend is confirmed the nullifier is free in the pool again.
+    pool.prune_confirmed(&first.nullifiers);
+    pool.admit(spend_bundle(0, 2..3), &mut window).unwrap();
+}
+
+#[test]
+fn prune_confirmed_evicts_spent_bundles() {
+    let mut window = NullifierSMAWindow::default();
+    let mut pool = Mempool::new();
+    let spent = spend_bundle(0, 0..2);
+    let pending = spend_bundle(1, 2..3);
+    pool.admit(spent.clone(), &mut window).unwrap();
+    pool.admit(pending.clone(), &mut window).unwrap();
+    assert_eq!(pool.tachygram_count(), 5);
+
+    let evicted = pool.prune_confirmed(&[spent.nullifiers[0], [0x11u8; 32]]);
+    assert_eq!(evicted, vec![spent]);
+    assert_eq!(pool.bundles(), &[pending][..]);
+    assert_eq!(pool.tachygram_count(), 2);
+    assert!(pool.prune_confirmed(&[]).is_empty());
+}
+
+#[test]
+fn duplicate_commitments_rejected() {
+    let with_cms = |cms: &[u8]| {
+        let mut b = TachyonBundle::new();
+        b.commitments = cms.iter().map(|&x| [x; 32]).collect();
+        b
+    };
+    let clean = [with_cms(&[1, 2]), with_cms(&[3])];
+    check_unique_commitments(&clean).unwrap();
+
+    let shared = [with_cms(&[1, 2]), with_cms(&[3, 2])];
+    let err = check_unique_commitments(&shared).unwrap_err();
+    assert!(err.to_string().contains(&"02".repeat(32)), "{err}");
+
+    let vk = VerifyingKey(vec![]);
+    let mut window = NullifierSMAWindow::new(accum::params::ACCUM_HEIGHT, Root([3u8; 32]));
+    let mut tree = CommitmentTree::new();
+    let agg = agg_for([3u8; 32], &leaf_for(&shared, &window, &tree));
+    assert!(verify_full_block(&vk, &agg, &shared, &mut window, &mut tree).is_err());
+    assert_eq!(tree.size(), 0);
+    let agg = agg_for([3u8; 32], &leaf_for(&clean, &window, &tree));
+    verify_full_block(&vk, &agg, &clean, &mut window, &mut tree).unwrap();
+    assert_eq!(tree.size(), 3);
+}
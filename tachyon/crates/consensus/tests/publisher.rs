@@ -0,0 +1,79 @@
+use consensus::{
+    build_block_record, build_block_record_with_mode, canonical_sort_roots, check_root_collisions,
+    split_grams_for_blocks, GramCollisionMode,
+};
+use ff::Field;
+use pasta_curves::vesta::Scalar as FrVesta;
+
+mod common;
+
+fn grams(tags: &[u8]) -> Vec<[u8; 32]> {
+    tags.iter().map(|&t| [t; 32]).collect()
+}
+
+#[test]
+fn root_permutations_commit_identically() {
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let r1 = build_block_record(&pk, &a_i, &grams(&[1, 2, 3, 4, 5])).unwrap();
+    let r2 = build_block_record(&pk, &a_i, &grams(&[4, 2, 5, 1, 3, 2])).unwrap();
+    assert_eq!(r1.p_i, r2.p_i);
+    assert_eq!(r1.a_next, r2.a_next);
+
+    let mut xs = vec![FrVesta::from(300u64), FrVesta::from(7u64), FrVesta::from(256u64)];
+    canonical_sort_roots(&mut xs);
+    assert_eq!(xs, vec![FrVesta::from(7u64), FrVesta::from(256u64), FrVesta::from(300u64)]);
+}
+
+#[test]
+fn empty_block_advances_accumulator() {
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let rec = build_block_record(&pk, &a_i, &[]).unwrap();
+    assert_eq!(rec.p_i.0, accum::ipa::encode_point(&pcd::block_circuit::commit_block_coeffs(&[FrVesta::ONE])));
+    assert!(rec.verify_step(&a_i));
+    assert_ne!(rec.a_next, a_i);
+
+    // The next block chains from the empty block's A_{i+1}.
+    let next = build_block_record(&pk, &rec.a_next, &grams(&[9])).unwrap();
+    assert!(next.verify_step(&rec.a_next));
+}
+
+#[test]
+fn strict_mode_reports_gram_root_collisions() {
+    let gs = grams(&[1, 2, 1]);
+    // Repeated grams sharing a root are fine; a distinct gram on that root is not.
+    let r = |x: u64| FrVesta::from(x);
+    check_root_collisions(&gs, &[r(10), r(20), r(10)]).unwrap();
+    let err = check_root_collisions(&gs, &[r(10), r(10), r(10)]).unwrap_err();
+    assert!(err.to_string().contains("same root"));
+    assert!(check_root_collisions(&gs, &[r(10)]).is_err());
+
+    // Real grams do not collide, so strict mode agrees with the default. The
+    // proofs carry fresh prover blinds, so compare everything else.
+    let pk = common::block_pk();
+    let a_i = accum::genesis_accumulator();
+    let strict = build_block_record_with_mode(&pk, &a_i, &gs, GramCollisionMode::Strict).unwrap();
+    let default = build_block_record(&pk, &a_i, &gs).unwrap();
+    assert_eq!((strict.p_i, strict.h_i, strict.a_next), (default.p_i, default.h_i, default.a_next));
+}
+
+#[test]
+fn oversized_block_splits_at_degree_bound() {
+    let n = accum::params::DEGREE_N;
+    let mut grams: Vec<[u8; 32]> = (0..n as u32 + 10)
+        .map(|i| {
+            let mut g = [0u8; 32];
+            g[..4].copy_from_slice(&i.to_be_bytes());
+            g
+        })
+        .collect();
+    let expected = grams.clone();
+    grams.reverse();
+    grams.push(grams[0]);
+
+    let chunks = split_grams_for_blocks(&grams);
+    assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![n, 10]);
+    assert_eq!(chunks.concat(), expected);
+    assert_eq!(split_grams_for_blocks(&[]), vec![Vec::<[u8; 32]>::new()]);
+}
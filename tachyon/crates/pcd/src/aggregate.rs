@@ -1,10 +1,29 @@
 //! Aggregator for Tachyon: builds AggregateProofs from txids.
 
-use anyhow::Result;
+use accum::merkle;
+use anyhow::{anyhow, Result};
+use blake2b_simd::Params as Blake2bParams;
+use primitives::endian::put_u32_be;
+use serde::{Deserialize, Serialize};
 use crate::VerifyingKey;
 
 pub const TXID_LEN: usize = 32;
 
+const DS_AGG_TXIDS_V1: &[u8; 16] = b"tachyon.agg.v1\0\0"; // 14 + 2 = 16
+
+/// Fiat–Shamir binding over the ordered txid list: BLAKE2b-256 transcript of
+/// count || txid_0 || ... || txid_{n-1}, absorbed incrementally.
+pub fn txid_binding(txids: &[[u8; TXID_LEN]]) -> [u8; 32] {
+    let mut st = Blake2bParams::new().hash_length(32).personal(DS_AGG_TXIDS_V1).to_state();
+    let mut count = Vec::with_capacity(4);
+    put_u32_be(&mut count, txids.len() as u32);
+    st.update(&count);
+    for id in txids { st.update(id); }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(st.finalize().as_bytes());
+    out
+}
+
 /// Aggregate proof structure containing txids and proof bytes.
 #[derive(Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Debug)]
 pub struct AggregateProof {
@@ -14,6 +33,95 @@ pub struct AggregateProof {
     pub proof: Vec<u8>,
 }
 
+impl AggregateProof {
+    /// Recompute the txid binding and compare it to `proof`. Not a SNARK check;
+    /// it only detects reordered, altered, added or dropped txids.
+    pub fn verify_binding(&self) -> bool {
+        self.proof == txid_binding(&self.txids)
+    }
+
+    /// Copy with the proof tampered by `primitives::corrupt_proof_bytes`.
+    #[cfg(feature = "testing")]
+    pub fn with_corrupt_proof(&self) -> Self {
+        let mut out = self.clone();
+        primitives::corrupt_proof_bytes(&mut out.proof);
+        out
+    }
+}
+
+/// Sorted, deduped copy of `txids`: the leaf order of `sorted_txids_root`.
+fn sorted_txids(txids: &[[u8; TXID_LEN]]) -> Vec<[u8; TXID_LEN]> {
+    let mut sorted = txids.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted
+}
+
+/// A present txid at `index` of the sorted list, with its Merkle siblings.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct SortedTxidLeaf {
+    pub index: u64,
+    pub txid: [u8; TXID_LEN],
+    /// Siblings from the leaf up to the tree root.
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl SortedTxidLeaf {
+    fn verify(&self, root: &[u8; 32], leaf_count: u64) -> bool {
+        merkle::verify(root, &self.txid, self.index, leaf_count, &self.siblings)
+    }
+}
+
+/// Absence of a txid from an aggregate: the adjacent present txids around it
+/// in sorted order. `lower` is `None` when the txid sorts before every present
+/// one, `upper` when it sorts after; both are `None` only for an empty list.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub struct NonInclusionWitness {
+    pub leaf_count: u64,
+    pub lower: Option<SortedTxidLeaf>,
+    pub upper: Option<SortedTxidLeaf>,
+}
+
+/// Check `witness` shows `txid` is absent from the list committed by `root`
+/// (an `AggregateProof::sorted_txids_root`).
+pub fn verify_non_inclusion(root: &[u8; 32], txid: &[u8; TXID_LEN], witness: &NonInclusionWitness) -> bool {
+    let n = witness.leaf_count;
+    let leaf_ok = |leaf: &SortedTxidLeaf| leaf.verify(root, n);
+    match (&witness.lower, &witness.upper) {
+        (None, None) => n == 0 && merkle::root(&[]) == *root,
+        (None, Some(up)) => up.index == 0 && *txid < up.txid && leaf_ok(up),
+        (Some(lo), None) => lo.index + 1 == n && lo.txid < *txid && leaf_ok(lo),
+        (Some(lo), Some(up)) => {
+            lo.index + 1 == up.index && lo.txid < *txid && *txid < up.txid && leaf_ok(lo) && leaf_ok(up)
+        }
+    }
+}
+
+impl AggregateProof {
+    /// `accum::merkle` root over the sorted, deduped txids, binding the leaf
+    /// count. Commits the set for `verify_non_inclusion`; `proof` still binds
+    /// the original order.
+    pub fn sorted_txids_root(&self) -> [u8; 32] {
+        merkle::root(&sorted_txids(&self.txids))
+    }
+
+    /// Witness that `txid` is not covered by this aggregate; errors if it is.
+    pub fn prove_non_inclusion(&self, txid: &[u8; TXID_LEN]) -> Result<NonInclusionWitness> {
+        let sorted = sorted_txids(&self.txids);
+        let pos = match sorted.binary_search(txid) {
+            Ok(_) => return Err(anyhow!("txid {} is in the aggregate", hex::encode(txid))),
+            Err(pos) => pos,
+        };
+        let levels = merkle::levels(&sorted);
+        let leaf = |index: usize| SortedTxidLeaf { index: index as u64, txid: sorted[index], siblings: merkle::siblings(&levels, index) };
+        Ok(NonInclusionWitness {
+            leaf_count: sorted.len() as u64,
+            lower: pos.checked_sub(1).map(leaf),
+            upper: (pos < sorted.len()).then(|| leaf(pos)),
+        })
+    }
+}
+
 #[derive(Default)]
 pub struct Aggregator {
     txids: Vec<[u8; TXID_LEN]>,
@@ -25,8 +133,9 @@ impl Aggregator {
     pub fn add_txid(&mut self, txid: [u8; TXID_LEN]) { self.txids.push(txid); }
 
     pub fn build(self, _vk: &VerifyingKey) -> Result<AggregateProof> {
-        // Placeholder: construct aggregate with provided txids and empty proof bytes.
-        Ok(AggregateProof { txids: self.txids, proof: Vec::new() })
+        // Transcript binding over the txids until recursive proofs are wired.
+        let proof = txid_binding(&self.txids).to_vec();
+        Ok(AggregateProof { txids: self.txids, proof })
     }
 }
 
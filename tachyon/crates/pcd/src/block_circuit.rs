@@ -2,15 +2,16 @@
 
 use accum::{ipa, poseidon};
 use halo2_proofs::{
-    circuit::{Layouter, SimpleFloorPlanner, Value},
+    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
 };
 use pasta_curves::{pallas, vesta::Scalar as FrVesta};
 use accum::ipa::circuit::MsmConfig;
-use ff::Field;
+use ff::{Field, PrimeField};
 use group::Curve;
 use group::prime::PrimeCurveAffine;
 use halo2_proofs::poly::Rotation;
+use primitives::endian::put_u32_be;
 
 #[derive(Clone, Debug, Default)]
 pub struct BlockPolyWitness {
@@ -24,6 +25,83 @@ pub struct BlockPolyWitness {
     pub a_i: pallas::Affine,
 }
 
+const WITNESS_V1: u8 = 1;
+/// Field/curve identifier: roots and coeffs in Fr(Vesta), points on Pallas.
+const WITNESS_FIELD_VESTA_PALLAS: u8 = 1;
+
+/// Header errors from `BlockPolyWitness::from_bytes`, surfaced through
+/// `anyhow` so callers can downcast and tell a newer layout from corruption.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WitnessHeaderError {
+    #[error("unsupported block witness version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("unknown block witness field identifier: {0}")]
+    UnknownField(u8),
+}
+
+fn read_chunk<'a>(data: &mut &'a [u8], n: usize) -> anyhow::Result<&'a [u8]> {
+    if data.len() < n { anyhow::bail!("truncated block witness"); }
+    let (head, rest) = data.split_at(n);
+    *data = rest;
+    Ok(head)
+}
+
+fn read_fr_vec(data: &mut &[u8]) -> anyhow::Result<Vec<FrVesta>> {
+    let mut len = [0u8; 4];
+    len.copy_from_slice(read_chunk(data, 4)?);
+    let len = u32::from_be_bytes(len) as usize;
+    if data.len() / 32 < len { anyhow::bail!("truncated block witness"); }
+    (0..len)
+        .map(|_| {
+            let mut repr = [0u8; 32];
+            repr.copy_from_slice(read_chunk(data, 32)?);
+            Option::from(FrVesta::from_repr(repr)).ok_or_else(|| anyhow::anyhow!("non-canonical field element in block witness"))
+        })
+        .collect()
+}
+
+fn read_point(data: &mut &[u8]) -> anyhow::Result<pallas::Affine> {
+    let mut b = [0u8; 32];
+    b.copy_from_slice(read_chunk(data, 32)?);
+    ipa::decode_point(&b).ok_or_else(|| anyhow::anyhow!("invalid point in block witness"))
+}
+
+impl BlockPolyWitness {
+    /// Encoding for out-of-process provers: version || field id || u32 n ||
+    /// roots || u32 m || coeffs || p_i || a_i, with 32-byte field reprs,
+    /// compressed points and big-endian lengths.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 + 8 + 32 * (self.roots.len() + self.coeffs.len() + 2));
+        out.push(WITNESS_V1);
+        out.push(WITNESS_FIELD_VESTA_PALLAS);
+        for v in [&self.roots, &self.coeffs] {
+            put_u32_be(&mut out, v.len() as u32);
+            for x in v.iter() { out.extend_from_slice(x.to_repr().as_ref()); }
+        }
+        out.extend_from_slice(&ipa::encode_point(&self.p_i));
+        out.extend_from_slice(&ipa::encode_point(&self.a_i));
+        out
+    }
+
+    /// Decode `to_bytes` output; rejects an unknown header (as a
+    /// `WitnessHeaderError`), `coeffs.len() != roots.len() + 1`, non-canonical
+    /// scalars, invalid points and trailing bytes.
+    pub fn from_bytes(mut data: &[u8]) -> anyhow::Result<Self> {
+        let header = read_chunk(&mut data, 2)?;
+        if header[0] != WITNESS_V1 { return Err(WitnessHeaderError::UnsupportedVersion(header[0]).into()); }
+        if header[1] != WITNESS_FIELD_VESTA_PALLAS { return Err(WitnessHeaderError::UnknownField(header[1]).into()); }
+        let roots = read_fr_vec(&mut data)?;
+        let coeffs = read_fr_vec(&mut data)?;
+        if coeffs.len() != roots.len() + 1 {
+            anyhow::bail!("block witness has {} coeffs for {} roots", coeffs.len(), roots.len());
+        }
+        let p_i = read_point(&mut data)?;
+        let a_i = read_point(&mut data)?;
+        if !data.is_empty() { anyhow::bail!("trailing bytes in block witness"); }
+        Ok(Self { roots, coeffs, p_i, a_i })
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct BlockPolyPublic {
     pub p_i_bytes: [u8; 32],
@@ -31,6 +109,47 @@ pub struct BlockPolyPublic {
     pub a_next_bytes: [u8; 32],
 }
 
+impl BlockPolyPublic {
+    /// Off-circuit check of the accumulator step: points decode and
+    /// A_{i+1} = [H_A(A_i, P_i)]A_i + P_i.
+    pub fn is_consistent(&self) -> bool {
+        let (p_i, a_i) = match (ipa::decode_point(&self.p_i_bytes), ipa::decode_point(&self.a_i_bytes)) {
+            (Some(p), Some(a)) => (p, a),
+            _ => return false,
+        };
+        let h_i = ipa::map_vesta_scalar_to_pallas(&poseidon::hash_A_h(&self.a_i_bytes, &self.p_i_bytes));
+        let a_next = (a_i.to_curve() * h_i + p_i.to_curve()).to_affine();
+        ipa::encode_point(&a_next) == self.a_next_bytes
+    }
+}
+
+/// Pallas scalars `commit_block_coeffs` commits for `coeffs`: each
+/// coefficient under `ipa::embed_vesta_scalar`.
+pub fn coeffs_to_pallas(coeffs: &[FrVesta]) -> Vec<pallas::Scalar> {
+    coeffs.iter().map(ipa::embed_vesta_scalar).collect()
+}
+
+/// Commit Fr(Vesta) coefficients as P_i, embedding each into the Pallas scalar field.
+/// Variable-time; for public block coefficients.
+pub fn commit_block_coeffs(coeffs: &[FrVesta]) -> pallas::Affine {
+    ipa::commit_coeffs(&coeffs_to_pallas(coeffs))
+}
+
+/// `prove_block_poly` for untrusted witnesses: first recompute the monic
+/// expansion of `w.roots` and require `w.coeffs` to equal it coefficient by
+/// coefficient, instead of relying on the single-point identity check. Costs
+/// a full expansion (`roots_to_coeffs_auto`).
+pub fn prove_block_poly_strict(w: &BlockPolyWitness) -> anyhow::Result<(BlockPolyPublic, Vec<u8>)> {
+    let expected = accum::poly::roots_to_coeffs_auto(&w.roots);
+    if expected.len() != w.coeffs.len() {
+        anyhow::bail!("{} coefficients for {} roots", w.coeffs.len(), w.roots.len());
+    }
+    if let Some(i) = expected.iter().zip(&w.coeffs).position(|(a, b)| a != b) {
+        anyhow::bail!("coefficient {} is not the expansion of the roots", i);
+    }
+    prove_block_poly(w)
+}
+
 /// Off-circuit prover skeleton: computes public inputs and returns dummy proof bytes.
 pub fn prove_block_poly(w: &BlockPolyWitness) -> anyhow::Result<(BlockPolyPublic, Vec<u8>)> {
     // Encode public points.
@@ -38,13 +157,7 @@ pub fn prove_block_poly(w: &BlockPolyWitness) -> anyhow::Result<(BlockPolyPublic
     let a_i_bytes = ipa::encode_point(&w.a_i);
 
     // Off-circuit binding: check P_i equals Commit(coeffs) with chunked MSM mapping.
-    let coeffs_pallas: Vec<pallas::Scalar> = w.coeffs.iter().map(|x| {
-        let xb = ff::PrimeField::to_repr(x);
-        let mut b32 = [0u8; 32];
-        b32.copy_from_slice(xb.as_ref());
-        ipa::map_vesta_scalar_to_pallas(&b32)
-    }).collect();
-    let p_i_ref = ipa::commit_coeffs(&coeffs_pallas);
+    let p_i_ref = commit_block_coeffs(&w.coeffs);
     if ipa::encode_point(&p_i_ref) != p_i_bytes { anyhow::bail!("commitment mismatch for P_i"); }
 
     // Derive r and evaluate both sides off-circuit for a quick sanity check.
@@ -90,7 +203,99 @@ pub struct BlockPolyConfig {
     msm: MsmConfig,
 }
 
+/// Little-endian bits of the canonical repr of `x` (NUM_BITS long).
+pub fn fr_to_le_bits(x: &FrVesta) -> Vec<bool> {
+    let repr = x.to_repr();
+    let bytes = repr.as_ref();
+    (0..FrVesta::NUM_BITS as usize).map(|i| (bytes[i >> 3] >> (i & 7)) & 1 == 1).collect()
+}
+
+impl BlockPolyConfig {
+    /// Constrain `value` to equal Σ bits[i]·2^i (little-endian) with each bit boolean.
+    ///
+    /// Per bit this uses three rows: the mul gate for booleanity (b·b = b), the mul
+    /// gate against a fixed 2^i for the weighted bit, and the add gate for the
+    /// running sum. The final sum is copy-constrained to the witnessed `value`,
+    /// which is returned so callers can bind it to other regions.
+    ///
+    /// With `NUM_BITS` bits the sum can exceed the modulus, so `value + q` would
+    /// also decompose; the bits are then also constrained to be at most q - 1,
+    /// which makes the decomposition canonical (`fr_to_le_bits`). Fewer bits
+    /// stay below q and need no check; more are a synthesis error.
+    pub fn decompose_bits(
+        &self,
+        mut layouter: impl Layouter<FrVesta>,
+        value: FrVesta,
+        bits: &[bool],
+    ) -> Result<AssignedCell<FrVesta, FrVesta>, Error> {
+        if bits.len() > FrVesta::NUM_BITS as usize { return Err(Error::Synthesis); }
+        layouter.assign_region(
+            || "decompose_bits",
+            |mut region| {
+                let value_cell = region.assign_advice(|| "value", self.d, 0, || Value::known(value))?;
+                let mut acc_cell = region.assign_advice_from_constant(|| "acc0", self.c, 0, FrVesta::ZERO)?;
+                let mut acc = FrVesta::ZERO;
+                let mut pow = FrVesta::ONE;
+                let mut row = 1;
+                let mut bit_cells = Vec::with_capacity(bits.len());
+                for &bit in bits {
+                    let b = if bit { FrVesta::ONE } else { FrVesta::ZERO };
+                    // b * b = b
+                    self.s_mul.enable(&mut region, row)?;
+                    let b_cell = region.assign_advice(|| "bit", self.a, row, || Value::known(b))?;
+                    b_cell.copy_advice(|| "bit", &mut region, self.b, row)?;
+                    b_cell.copy_advice(|| "bit", &mut region, self.c, row)?;
+                    // t = b * 2^i
+                    self.s_mul.enable(&mut region, row + 1)?;
+                    b_cell.copy_advice(|| "bit", &mut region, self.a, row + 1)?;
+                    region.assign_advice_from_constant(|| "2^i", self.b, row + 1, pow)?;
+                    let t_cell = region.assign_advice(|| "t", self.c, row + 1, || Value::known(b * pow))?;
+                    // acc' = acc + t
+                    self.s_add.enable(&mut region, row + 2)?;
+                    acc_cell.copy_advice(|| "acc", &mut region, self.a, row + 2)?;
+                    t_cell.copy_advice(|| "t", &mut region, self.b, row + 2)?;
+                    acc += b * pow;
+                    acc_cell = region.assign_advice(|| "acc'", self.c, row + 2, || Value::known(acc))?;
+                    pow = pow.double();
+                    row += 3;
+                    bit_cells.push((b_cell, b));
+                }
+                region.constrain_equal(acc_cell.cell(), value_cell.cell())?;
+
+                if bits.len() == FrVesta::NUM_BITS as usize {
+                    // Compare against q - 1 from the top bit down. `eq` is 1 while
+                    // the bits so far match q - 1: at a 1 bit of q - 1, eq' = eq·b;
+                    // at a 0 bit, b may be set only once the prefix is smaller
+                    // (eq·b = 0), and eq is unchanged.
+                    let max_bits = fr_to_le_bits(&-FrVesta::ONE);
+                    let mut eq_cell = region.assign_advice_from_constant(|| "eq0", self.d, row, FrVesta::ONE)?;
+                    let mut eq = FrVesta::ONE;
+                    for ((b_cell, b), &max_bit) in bit_cells.iter().zip(&max_bits).rev() {
+                        self.s_mul.enable(&mut region, row)?;
+                        eq_cell.copy_advice(|| "eq", &mut region, self.a, row)?;
+                        b_cell.copy_advice(|| "bit", &mut region, self.b, row)?;
+                        if max_bit {
+                            eq *= b;
+                            eq_cell = region.assign_advice(|| "eq'", self.c, row, || Value::known(eq))?;
+                        } else {
+                            region.assign_advice_from_constant(|| "eq·b = 0", self.c, row, FrVesta::ZERO)?;
+                        }
+                        row += 1;
+                    }
+                }
+                Ok(value_cell)
+            },
+        )
+    }
+}
+
 impl BlockPolyCircuit {
+    /// The empty block: no roots and the constant polynomial p(X) = 1. This is
+    /// the keygen shape and the placeholder circuit `api2` verifies against.
+    pub fn empty() -> Self {
+        Self { roots: vec![], coeffs: vec![<FrVesta as ff::Field>::ONE], r: <FrVesta as ff::Field>::ONE }
+    }
+
     pub fn from_witness(w: &BlockPolyWitness) -> Self {
         let p_i_bytes = ipa::encode_point(&w.p_i);
         let a_i_bytes = ipa::encode_point(&w.a_i);
@@ -108,13 +313,18 @@ impl Circuit<FrVesta> for BlockPolyCircuit {
     type Config = BlockPolyConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
-    fn without_witnesses(&self) -> Self { Self { roots: vec![], coeffs: vec![], r: <FrVesta as ff::Field>::ONE } }
+    // Keygen shape: the empty block, whose polynomial is the constant 1.
+    fn without_witnesses(&self) -> Self { Self::empty() }
 
     fn configure(meta: &mut ConstraintSystem<FrVesta>) -> Self::Config {
         let a = meta.advice_column();
         let b = meta.advice_column();
         let c = meta.advice_column();
         let d = meta.advice_column();
+        // Copy constraints and a constants column back the bit-decomposition region.
+        for col in [a, b, c, d] { meta.enable_equality(col); }
+        let constants = meta.fixed_column();
+        meta.enable_constant(constants);
         let s_mul = meta.selector();
         let s_add = meta.selector();
         let s_eq = meta.selector();
@@ -147,69 +357,56 @@ impl Circuit<FrVesta> for BlockPolyCircuit {
     }
 
     fn synthesize(&self, cfg: Self::Config, mut layouter: impl Layouter<FrVesta>) -> Result<(), Error> {
-        // Compute lhs = ∏(r - a_j) and rhs = Horner(coeffs, r)
+        // Compute lhs = ∏(r - a_j) and rhs = Horner(coeffs, r). Both chains
+        // start from constant cells and link rows by copy constraints, so with
+        // zero roots lhs is the constant 1 and Horner over the empty block's
+        // polynomial [1] yields 1; the eq gate compares the chain outputs.
         let r = self.r;
-        let roots = self.roots.clone();
-        let coeffs = self.coeffs.clone();
+        // p(X) = ∏(X - a_j) has exactly one more coefficient than roots; any
+        // other shape makes the identity meaningless (e.g. lhs = 1 vs rhs = 0).
+        if self.coeffs.len() != self.roots.len() + 1 { return Err(Error::Synthesis); }
 
-        // Product chain
-        let lhs_val = roots.iter().fold(FrVesta::ONE, |acc, a| acc * (r - *a));
-        layouter.assign_region(
+        // Product chain: row k holds acc_k * (r - a_k) = acc_{k+1}.
+        let lhs = layouter.assign_region(
             || "product",
             |mut region| {
-                // initialize acc = 1
-                let row0 = 0;
-                let one = Value::known(<FrVesta as ff::Field>::ONE);
-                region.assign_advice(|| "acc0", cfg.c, row0, || one)?;
-                let mut cur_row = 1;
+                let mut acc_cell = region.assign_advice_from_constant(|| "acc0", cfg.c, 0, <FrVesta as ff::Field>::ONE)?;
                 let mut acc = <FrVesta as ff::Field>::ONE;
-                for a_root in roots.iter() {
-                    let a_val = Value::known(acc);
-                    let b_val = Value::known(r - *a_root);
-                    let c_val = Value::known(acc * (r - *a_root));
-                    cfg.s_mul.enable(&mut region, cur_row)?;
-                    region.assign_advice(|| "a", cfg.a, cur_row, || a_val)?;
-                    region.assign_advice(|| "b", cfg.b, cur_row, || b_val)?;
-                    region.assign_advice(|| "c", cfg.c, cur_row, || c_val)?;
-                    acc = acc * (r - *a_root);
-                    cur_row += 1;
+                for (k, a_root) in self.roots.iter().enumerate() {
+                    let row = k + 1;
+                    cfg.s_mul.enable(&mut region, row)?;
+                    acc_cell.copy_advice(|| "acc", &mut region, cfg.a, row)?;
+                    region.assign_advice(|| "r - a", cfg.b, row, || Value::known(r - *a_root))?;
+                    acc *= r - *a_root;
+                    acc_cell = region.assign_advice(|| "acc'", cfg.c, row, || Value::known(acc))?;
                 }
-                // store final lhs in c at cur_row
-                region.assign_advice(|| "lhs", cfg.c, cur_row, || Value::known(acc))?;
-                Ok(())
+                Ok(acc_cell)
             },
         )?;
 
-        // Horner evaluation
-        let rhs_val = {
-            let mut acc = <FrVesta as ff::Field>::ZERO;
-            for &c in coeffs.iter().rev() { acc = acc * r + c; }
-            acc
-        };
-        layouter.assign_region(
+        // Horner chain: acc' = acc * r + c over coefficients from the top.
+        let rhs = layouter.assign_region(
             || "horner",
             |mut region| {
-                // compute acc = acc * r + c across rows
+                let r_cell = region.assign_advice(|| "r", cfg.d, 0, || Value::known(r))?;
+                let mut acc_cell = region.assign_advice_from_constant(|| "acc0", cfg.c, 0, <FrVesta as ff::Field>::ZERO)?;
                 let mut acc = <FrVesta as ff::Field>::ZERO;
-                let mut row = 0;
-                for &coef in coeffs.iter().rev() {
+                let mut row = 1;
+                for &coef in self.coeffs.iter().rev() {
                     // t = acc * r
                     cfg.s_mul.enable(&mut region, row)?;
-                    region.assign_advice(|| "acc", cfg.a, row, || Value::known(acc))?;
-                    region.assign_advice(|| "r", cfg.b, row, || Value::known(r))?;
-                    region.assign_advice(|| "t", cfg.c, row, || Value::known(acc * r))?;
+                    acc_cell.copy_advice(|| "acc", &mut region, cfg.a, row)?;
+                    r_cell.copy_advice(|| "r", &mut region, cfg.b, row)?;
+                    let t_cell = region.assign_advice(|| "t", cfg.c, row, || Value::known(acc * r))?;
                     // acc' = t + coef
                     cfg.s_add.enable(&mut region, row + 1)?;
-                    region.assign_advice(|| "t", cfg.a, row + 1, || Value::known(acc * r))?;
+                    t_cell.copy_advice(|| "t", &mut region, cfg.a, row + 1)?;
                     region.assign_advice(|| "coef", cfg.b, row + 1, || Value::known(coef))?;
-                    let new_acc = acc * r + coef;
-                    region.assign_advice(|| "acc'", cfg.c, row + 1, || Value::known(new_acc))?;
-                    acc = new_acc;
+                    acc = acc * r + coef;
+                    acc_cell = region.assign_advice(|| "acc'", cfg.c, row + 1, || Value::known(acc))?;
                     row += 2;
                 }
-                // store rhs in d at row
-                region.assign_advice(|| "rhs", cfg.d, row, || Value::known(acc))?;
-                Ok(())
+                Ok(acc_cell)
             },
         )?;
 
@@ -218,8 +415,8 @@ impl Circuit<FrVesta> for BlockPolyCircuit {
             || "eq",
             |mut region| {
                 cfg.s_eq.enable(&mut region, 0)?;
-                region.assign_advice(|| "lhs", cfg.c, 0, || Value::known(lhs_val))?;
-                region.assign_advice(|| "rhs", cfg.d, 0, || Value::known(rhs_val))?;
+                lhs.copy_advice(|| "lhs", &mut region, cfg.c, 0)?;
+                rhs.copy_advice(|| "rhs", &mut region, cfg.d, 0)?;
                 Ok(())
             },
         )?;
@@ -8,42 +8,327 @@ pub mod wallet_step;
 
 /// High-level proving/verification entrypoints (placeholders binding Halo2 APIs).
 pub mod api2 {
-    use super::{block_circuit::{BlockPolyCircuit, BlockPolyWitness, BlockPolyPublic, prove_block_poly}, wallet_step::{WalletNonMemStepCircuit, WalletStepWitness, WalletStepPublic}};
-    use halo2_proofs::dev::MockProver;
-    use ff::Field;
-    use pasta_curves::vesta::Scalar as FrVesta;
+    use super::{block_circuit::{BlockPolyCircuit, BlockPolyWitness, BlockPolyPublic}, wallet_step::{WalletNonMemStepCircuit, WalletStepWitness, WalletStepPublic}, ProvingKey, VerifyingKey};
+    use blake2b_simd::Params as Blake2bParams;
+    use primitives::endian::put_u32_be;
+    use ff::PrimeField;
+    use halo2_proofs::{
+        dev::MockProver,
+        plonk::{keygen_vk, VerifyingKey as Halo2VerifyingKey},
+        poly::commitment::Params as CommitmentParams,
+        transcript::{Blake2bWrite, Challenge255, EncodedChallenge, Transcript},
+    };
+    use rayon::prelude::*;
+    use pasta_curves::vesta;
+    use rand_chacha::ChaCha20Rng;
+    use rand_core::{CryptoRng, OsRng, RngCore, SeedableRng};
+    use std::path::Path;
 
     pub struct Params { pub k: u32 }
 
-    pub fn prove_block(_params: &Params, wit: &BlockPolyWitness) -> anyhow::Result<(BlockPolyPublic, Vec<u8>)> {
-        // Compute public summary off-circuit; return stub proof bytes for now.
-        let (public, proof) = super::block_circuit::prove_block_poly(wit)?;
+    /// Rows halo2 reserves at the end of the domain: `blinding_factors() + 1`,
+    /// where the block circuit's single-rotation gates give 5 blinding factors.
+    const RESERVED_ROWS: usize = 6;
+
+    /// Smallest `k` whose usable rows fit `BlockPolyCircuit` over `num_roots`
+    /// roots. The product chain (n + 1 rows), Horner chain (2n + 3) and eq row
+    /// share columns and stack; the placeholder MSM (n + 1 rows) sits beside them.
+    pub const fn required_k(num_roots: usize) -> u32 {
+        let chains = 3 * num_roots + 5;
+        let msm = num_roots + 1;
+        let rows = if chains > msm { chains } else { msm };
+        let mut k = 1;
+        while (1usize << k) < rows + RESERVED_ROWS { k += 1; }
+        k
+    }
+
+    const KEY_V1: u8 = 1;
+    const KIND_PK: u8 = 0;
+    const KIND_VK: u8 = 1;
+    const DS_VK_FP_V1: &[u8; 16] = b"tachyon.vkfp.v1\0"; // 15 + 1 = 16
+    const DS_PROOF_SEED_V1: &[u8; 16] = b"tachyon.prvseed\0"; // 15 + 1 = 16
+    const DS_BLOCK_PROOF_V1: &[u8; 16] = b"tachyon.blkprf\0\0"; // 14 + 2 = 16
+
+    /// Decoded block-circuit key: circuit size and fingerprint of the pinned Halo2 VK.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub struct KeyInfo {
+        pub k: u32,
+        pub vk_fingerprint: [u8; 32],
+    }
+
+    fn encode_key(kind: u8, info: &KeyInfo) -> Vec<u8> {
+        let mut out = Vec::with_capacity(2 + 4 + 32);
+        out.push(KEY_V1);
+        out.push(kind);
+        put_u32_be(&mut out, info.k);
+        out.extend_from_slice(&info.vk_fingerprint);
+        out
+    }
+
+    fn decode_key(kind: u8, bytes: &[u8]) -> anyhow::Result<KeyInfo> {
+        if bytes.len() != 2 + 4 + 32 { anyhow::bail!("malformed key: {} bytes", bytes.len()); }
+        if bytes[0] != KEY_V1 { anyhow::bail!("unsupported key version: {}", bytes[0]); }
+        if bytes[1] != kind { anyhow::bail!("unexpected key kind: {}", bytes[1]); }
+        let mut k = [0u8; 4];
+        k.copy_from_slice(&bytes[2..6]);
+        let mut vk_fingerprint = [0u8; 32];
+        vk_fingerprint.copy_from_slice(&bytes[6..]);
+        Ok(KeyInfo { k: u32::from_be_bytes(k), vk_fingerprint })
+    }
+
+    impl ProvingKey {
+        pub fn info(&self) -> anyhow::Result<KeyInfo> { decode_key(KIND_PK, &self.0) }
+    }
+
+    impl VerifyingKey {
+        pub fn info(&self) -> anyhow::Result<KeyInfo> { decode_key(KIND_VK, &self.0) }
+    }
+
+    /// BLAKE2b-256 of the challenge a fresh Halo2 transcript squeezes after
+    /// absorbing `vk`. That is the VK's transcript representation, the value
+    /// every Halo2 proof is bound to, so the fingerprint changes exactly when
+    /// proofs under the key would and does not depend on `Debug` output.
+    fn vk_fingerprint(vk: &Halo2VerifyingKey<vesta::Affine>) -> anyhow::Result<[u8; 32]> {
+        let mut transcript = Blake2bWrite::<Vec<u8>, vesta::Affine, Challenge255<_>>::init(Vec::new());
+        vk.hash_into(&mut transcript)?;
+        let repr = transcript.squeeze_challenge().get_scalar().to_repr();
+        let hash = Blake2bParams::new().hash_length(32).personal(DS_VK_FP_V1).hash(repr.as_ref());
+        let mut out = [0u8; 32];
+        out.copy_from_slice(hash.as_bytes());
+        Ok(out)
+    }
+
+    /// Run Halo2 keygen for `BlockPolyCircuit` at `params.k` and return compact keys.
+    ///
+    /// Halo2 0.3 keys have no byte encoding, so the keys carry `k` and
+    /// `vk_fingerprint` of the Halo2 VK; proofs stay MockProver-checked until
+    /// the block circuit has a fixed shape.
+    pub fn setup(params: &Params) -> anyhow::Result<(ProvingKey, VerifyingKey)> {
+        // Keyed on the empty block: no roots, polynomial p(X) = 1.
+        let circuit = BlockPolyCircuit::empty();
+        let srs = CommitmentParams::<vesta::Affine>::new(params.k);
+        let vk = keygen_vk(&srs, &circuit)?;
+        let info = KeyInfo { k: params.k, vk_fingerprint: vk_fingerprint(&vk)? };
+        Ok((ProvingKey(encode_key(KIND_PK, &info)), VerifyingKey(encode_key(KIND_VK, &info))))
+    }
+
+    /// Persist keys as len(pk) || pk || len(vk) || vk (u32 big-endian lengths).
+    pub fn save_keys(path: impl AsRef<Path>, pk: &ProvingKey, vk: &VerifyingKey) -> anyhow::Result<()> {
+        let mut out = Vec::with_capacity(8 + pk.0.len() + vk.0.len());
+        for key in [&pk.0, &vk.0] {
+            put_u32_be(&mut out, key.len() as u32);
+            out.extend_from_slice(key);
+        }
+        std::fs::write(path, out)?;
+        Ok(())
+    }
+
+    /// Load keys written by `save_keys`; both must decode and describe the same circuit.
+    pub fn load_keys(path: impl AsRef<Path>) -> anyhow::Result<(ProvingKey, VerifyingKey)> {
+        let data = std::fs::read(path)?;
+        let mut rest = data.as_slice();
+        let mut keys = Vec::with_capacity(2);
+        for _ in 0..2 {
+            if rest.len() < 4 { anyhow::bail!("truncated key file"); }
+            let mut len = [0u8; 4];
+            len.copy_from_slice(&rest[..4]);
+            let len = u32::from_be_bytes(len) as usize;
+            if rest.len() < 4 + len { anyhow::bail!("truncated key file"); }
+            keys.push(rest[4..4 + len].to_vec());
+            rest = &rest[4 + len..];
+        }
+        if !rest.is_empty() { anyhow::bail!("trailing bytes in key file"); }
+        let vk = VerifyingKey(keys.pop().unwrap_or_default());
+        let pk = ProvingKey(keys.pop().unwrap_or_default());
+        if pk.info()? != vk.info()? { anyhow::bail!("proving and verifying keys do not match"); }
+        Ok((pk, vk))
+    }
+
+    /// Source of the prover's blinding randomness for `prove_block_with`.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub enum ProverRng {
+        /// Fresh OS randomness for every proof.
+        #[default]
+        Os,
+        /// ChaCha20 seeded with `proof_seed(public)`, so identical witnesses give
+        /// byte-identical proofs; meant for reproducing consensus divergences.
+        ///
+        /// Safe here because soundness never rests on the prover's randomness
+        /// and the block witness (the block's tachygrams) is public anyway. Do
+        /// not reuse this mode for circuits with secret witnesses, where the
+        /// blinding is what keeps them hidden.
+        Deterministic,
+    }
+
+    /// Seed for `ProverRng::Deterministic`: BLAKE2b-256 of the block's public
+    /// inputs (P_i, A_i, A_{i+1}).
+    pub fn proof_seed(public: &BlockPolyPublic) -> [u8; 32] {
+        let hash = Blake2bParams::new()
+            .hash_length(32)
+            .personal(DS_PROOF_SEED_V1)
+            .to_state()
+            .update(&public.p_i_bytes)
+            .update(&public.a_i_bytes)
+            .update(&public.a_next_bytes)
+            .finalize();
+        let mut seed = [0u8; 32];
+        seed.copy_from_slice(hash.as_bytes());
+        seed
+    }
+
+    /// `prove_block_with` under `ProverRng::Os`.
+    pub fn prove_block(params: &Params, pk: &ProvingKey, wit: &BlockPolyWitness) -> anyhow::Result<(BlockPolyPublic, Vec<u8>)> {
+        prove_block_with(params, pk, wit, ProverRng::Os)
+    }
+
+    pub fn prove_block_with(params: &Params, pk: &ProvingKey, wit: &BlockPolyWitness, rng: ProverRng) -> anyhow::Result<(BlockPolyPublic, Vec<u8>)> {
+        let info = pk.info()?;
+        if info.k != params.k { anyhow::bail!("proving key is for k={}, params use k={}", info.k, params.k); }
+        let needed = required_k(wit.roots.len());
+        if needed > params.k { anyhow::bail!("{} roots need k >= {}, params use k={}", wit.roots.len(), needed, params.k); }
+        // Compute public summary off-circuit; the proof is a placeholder for now.
+        let (public, _) = super::block_circuit::prove_block_poly(wit)?;
+        let proof = match rng {
+            ProverRng::Os => create_block_proof(&info, &public, &mut OsRng),
+            ProverRng::Deterministic => create_block_proof(&info, &public, &mut ChaCha20Rng::from_seed(proof_seed(&public))),
+        };
         Ok((public, proof))
     }
 
+    /// Placeholder block proof tag: BLAKE2b-256 of the key's VK fingerprint,
+    /// the public inputs and the prover's blind. It ties a proof to one key and
+    /// one (P_i, A_i, A_{i+1}), so missing, truncated or transplanted proofs are
+    /// rejected. Anyone can compute it: it proves nothing about P_i until
+    /// Halo2's `create_proof` replaces it.
+    fn block_proof_tag(info: &KeyInfo, public: &BlockPolyPublic, blind: &[u8; 32]) -> [u8; 32] {
+        let hash = Blake2bParams::new()
+            .hash_length(32)
+            .personal(DS_BLOCK_PROOF_V1)
+            .to_state()
+            .update(&info.vk_fingerprint)
+            .update(&public.p_i_bytes)
+            .update(&public.a_i_bytes)
+            .update(&public.a_next_bytes)
+            .update(blind)
+            .finalize();
+        let mut tag = [0u8; 32];
+        tag.copy_from_slice(hash.as_bytes());
+        tag
+    }
+
+    /// Stand-in for Halo2's `create_proof`: a 32-byte blind drawn from `rng`
+    /// followed by `block_proof_tag`. Like a real proof's blinding, the blind
+    /// makes `ProverRng::Os` proofs differ run to run while
+    /// `ProverRng::Deterministic` ones repeat.
+    fn create_block_proof<R: RngCore + CryptoRng>(info: &KeyInfo, public: &BlockPolyPublic, rng: &mut R) -> Vec<u8> {
+        let mut blind = [0u8; 32];
+        rng.fill_bytes(&mut blind);
+        let mut proof = Vec::with_capacity(64);
+        proof.extend_from_slice(&blind);
+        proof.extend_from_slice(&block_proof_tag(info, public, &blind));
+        proof
+    }
+
+    fn block_proof_matches(info: &KeyInfo, public: &BlockPolyPublic, proof: &[u8]) -> bool {
+        if proof.len() != 64 { return false; }
+        let mut blind = [0u8; 32];
+        blind.copy_from_slice(&proof[..32]);
+        proof[32..] == block_proof_tag(info, public, &blind)
+    }
+
     pub fn prove_wallet_step(_params: &Params, wit: &WalletStepWitness) -> anyhow::Result<(WalletStepPublic, Vec<u8>)> {
         super::wallet_step::prove_wallet_step(wit)
     }
 
-    pub fn verify_block(params: &Params, _public: &BlockPolyPublic, _proof: &[u8]) -> anyhow::Result<bool> {
-        // Use MockProver until real IPA PCS is wired.
-        let circuit = BlockPolyCircuit { roots: vec![], coeffs: vec![], r: FrVesta::ONE };
+    /// Returns `Ok(false)` for a key generated at a different `k`, or a proof
+    /// that was not produced under `vk` for `public`.
+    pub fn verify_block(params: &Params, vk: &VerifyingKey, public: &BlockPolyPublic, proof: &[u8]) -> anyhow::Result<bool> {
+        let info = vk.info()?;
+        if info.k != params.k { return Ok(false); }
+        if !public.is_consistent() { return Ok(false); }
+        if !block_proof_matches(&info, public, proof) { return Ok(false); }
+        // Use MockProver until real IPA PCS is wired; the placeholder circuit
+        // is the empty block (no roots, p(X) = 1).
+        let circuit = BlockPolyCircuit::empty();
         let prover = MockProver::run(params.k, &circuit, vec![])?;
         Ok(prover.verify().is_ok())
     }
 
-    pub fn verify_wallet_step(params: &Params, _public: &WalletStepPublic, _proof: &[u8]) -> anyhow::Result<bool> {
+    /// Partial check for sync from a trusted snapshot: recompute
+    /// A_{i+1} = [H_A(A_i, P_i)]A_i + P_i from `a_i_bytes`/`p_i_bytes` and
+    /// compare it to `a_next_bytes`, skipping the SNARK. It says nothing about
+    /// P_i committing to a valid block polynomial; that is the proof's job.
+    pub fn verify_block_ec_only(public: &BlockPolyPublic) -> bool {
+        public.is_consistent()
+    }
+
+    /// Verify many blocks; result `i` matches `verify_block(params, vk, &publics[i], proofs[i])`.
+    /// The placeholder circuit is the same for every block, so it is configured and
+    /// checked once; per-block public checks run in parallel. Missing proofs yield `false`.
+    pub fn verify_blocks_batch(params: &Params, vk: &VerifyingKey, publics: &[BlockPolyPublic], proofs: &[&[u8]]) -> Vec<bool> {
+        verify_blocks_detailed(params, vk, publics, proofs).iter().map(Result::is_ok).collect()
+    }
+
+    /// Why `verify_blocks_detailed` rejected a block.
+    #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+    pub enum BlockVerifyError {
+        /// A_{i+1} is not [H_A(A_i, P_i)]A_i + P_i, or a point does not decode.
+        #[error("accumulator step relation does not hold")]
+        EcRelation,
+        /// The proof is missing or does not verify under this key and `k`.
+        #[error("block SNARK did not verify")]
+        Snark,
+    }
+
+    /// `verify_blocks_batch` with the reason for each rejection. The EC
+    /// relation is checked first, so a block failing both reports `EcRelation`.
+    pub fn verify_blocks_detailed(params: &Params, vk: &VerifyingKey, publics: &[BlockPolyPublic], proofs: &[&[u8]]) -> Vec<Result<(), BlockVerifyError>> {
+        let info = vk.info().ok().filter(|info| info.k == params.k);
+        let circuit = BlockPolyCircuit::empty();
+        let circuit_ok = info.is_some() && MockProver::run(params.k, &circuit, vec![]).map(|p| p.verify().is_ok()).unwrap_or(false);
+        publics
+            .par_iter()
+            .enumerate()
+            .map(|(i, public)| {
+                if !public.is_consistent() { return Err(BlockVerifyError::EcRelation); }
+                let proof_ok = match (&info, proofs.get(i)) {
+                    (Some(info), Some(proof)) => block_proof_matches(info, public, proof),
+                    _ => false,
+                };
+                if !circuit_ok || !proof_ok { return Err(BlockVerifyError::Snark); }
+                Ok(())
+            })
+            .collect()
+    }
+
+    pub fn verify_wallet_step(params: &Params, public: &WalletStepPublic, _proof: &[u8]) -> anyhow::Result<bool> {
+        if !public.is_consistent() { return Ok(false); }
         // Keep wallet-step on MockProver for now.
         let circuit = WalletNonMemStepCircuit::default();
         let prover = halo2_proofs::dev::MockProver::run(params.k, &circuit, vec![])?;
         Ok(prover.verify().is_ok())
     }
+
+    /// Verify many wallet steps; result `i` matches `verify_wallet_step(params, &publics[i], proofs[i])`.
+    /// The step circuit is checked once and the A/S update relations per step run in parallel.
+    /// Missing proofs yield `false`.
+    pub fn verify_wallet_steps_batch(params: &Params, publics: &[WalletStepPublic], proofs: &[&[u8]]) -> Vec<bool> {
+        let circuit = WalletNonMemStepCircuit;
+        let circuit_ok = MockProver::run(params.k, &circuit, vec![]).map(|p| p.verify().is_ok()).unwrap_or(false);
+        publics
+            .par_iter()
+            .enumerate()
+            .map(|(i, public)| circuit_ok && i < proofs.len() && public.is_consistent())
+            .collect()
+    }
 }
 
 /// Authorizing digest (ZIP-244 authorizing-data hash) bound inside PCD.
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct AuthorizingDigest(pub [u8; 32]);
 
+accum::impl_hex32!(AuthorizingDigest);
+
 /// Tx-level PCD public inputs summary.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct TxPCDPublic {
@@ -62,6 +347,38 @@ pub struct TxPCDPublic {
     pub hash_commitment_delta: [u8; 32],
 }
 
+impl TxPCDPublic {
+    /// Public inputs that bootstrap a chain before its first block.
+    ///
+    /// Genesis spends and creates nothing: the nullifier and commitment sets
+    /// are empty, value and fee are zero, and the range anchor is position 0
+    /// with both roots equal to the empty commitment tree root (depth
+    /// `ACCUM_HEIGHT`, all-zero leaves under `compress_nodes`). There is no
+    /// earlier block to digest, so the chain digests are all zero. This is the
+    /// one transaction allowed to be empty; every later one must spend or
+    /// create at least one note.
+    pub fn genesis() -> Self {
+        let mut root = [0u8; 32];
+        for _ in 0..accum::params::ACCUM_HEIGHT {
+            root = accum::poseidon::compress_nodes(&root, &root);
+        }
+        Self {
+            range_anchor_min_pos: 0,
+            range_anchor_max_pos: 0,
+            range_root_min: root,
+            range_root_max: root,
+            authorizing_digest: AuthorizingDigest::default(),
+            nullifiers: vec![],
+            commitments: vec![],
+            value_commitment: [0u8; 32],
+            fee: 0,
+            hash_orchard_root: [0u8; 32],
+            hash_nullifier_block: [0u8; 32],
+            hash_commitment_delta: [0u8; 32],
+        }
+    }
+}
+
 /// Aggregate-level PCD public inputs summary.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct AggPCDPublic {
@@ -70,6 +387,14 @@ pub struct AggPCDPublic {
     pub window_root: [u8; 32],
     // Optionally bind block-level MMR leaf hash if aggregates are per-block.
     pub block_mmr_leaf_hash: [u8; 32],
+    /// Recursion domain the proof was made in; `is_block` aggregates must bind
+    /// `block_mmr_leaf_hash`. Verifiers pass the domain they expect to
+    /// `verify_agg` instead of trusting this field.
+    pub domain: DomainSep,
+    /// `VerifyingKey::fingerprint` of the key the proof is meant for.
+    /// `verify_agg` substitutes its own key's fingerprint, so the proof fails
+    /// under any other key whatever this field claims.
+    pub vk_fingerprint: [u8; 32],
 }
 
 /// High-level interfaces for proving and verifying tx and aggregate PCDs.
@@ -94,15 +419,105 @@ pub mod api {
 
     pub fn verify_tx(_vk: &VerifyingKey, _pcd: &TxPCD) -> anyhow::Result<bool> { Ok(true) }
 
+    /// `verify_tx`, and additionally require `pcd.public.authorizing_digest`
+    /// to equal `stamp.authorizing_digest32()`, so the proof is bound to this
+    /// stamp's authorizing data. Returns Ok(false) on a digest mismatch.
+    pub fn verify_tx_against_stamp(vk: &VerifyingKey, pcd: &TxPCD, stamp: &primitives::Tachystamp) -> anyhow::Result<bool> {
+        if !verify_tx(vk, pcd)? { return Ok(false); }
+        Ok(stamp.authorizing_digest32() == pcd.public.authorizing_digest.0)
+    }
+
+    /// Merge tx-level PCD stamps (`pcd::Tachystamp`). Wire-format
+    /// `primitives::Tachystamp`s convert via `From<&primitives::Tachystamp>`.
     pub fn merge(_vk: &VerifyingKey, stamps: &[Tachystamp]) -> anyhow::Result<super::Aggregate> {
         Ok(super::Aggregate { proof: ProofBytes(vec![]), domain: super::DomainSep { is_block: false }, count: stamps.len() as u32 })
     }
 
+    const DS_AGG_PROOF_V1: &[u8; 16] = b"tachyon.aggprf\0\0"; // 14 + 2 = 16
+
+    /// Placeholder aggregate proof until recursion is wired: BLAKE2b-256 of
+    /// the bincode-encoded public inputs, so it verifies only against them.
+    fn agg_proof_tag(public: &AggPCDPublic) -> anyhow::Result<[u8; 32]> {
+        Ok(accum::prf::blake2b_32(DS_AGG_PROOF_V1, &bincode::serialize(public)?))
+    }
+
     pub fn prove_agg(_pk: &ProvingKey, public: AggPCDPublic, _children: &[TxPCD]) -> anyhow::Result<AggPCD> {
-        Ok(AggPCD { proof: ProofBytes(vec![]), public })
+        let proof = ProofBytes(agg_proof_tag(&public)?.to_vec());
+        Ok(AggPCD { proof, public })
+    }
+
+    /// Verify `pcd` as an aggregate in `domain` under `vk`. The key fingerprint
+    /// and domain are the verifier's, not read from `pcd.public`: the proof
+    /// must have been made for public inputs carrying exactly these, so this
+    /// is Ok(false) for a proof bound to another key or domain.
+    pub fn verify_agg(vk: &VerifyingKey, pcd: &AggPCD, domain: &DomainSep) -> anyhow::Result<bool> {
+        let expected = AggPCDPublic { domain: domain.clone(), vk_fingerprint: vk.fingerprint(), ..pcd.public.clone() };
+        Ok(pcd.proof.0[..] == agg_proof_tag(&expected)?[..])
     }
 
-    pub fn verify_agg(_vk: &VerifyingKey, _pcd: &AggPCD) -> anyhow::Result<bool> { Ok(true) }
+    const DS_TXPCD_ID_V1: &[u8; 16] = b"tachyon.txpcd\0\0\0"; // 13 + 3 = 16
+
+    impl TxPCD {
+        /// Identifier of this tx PCD inside an aggregate: BLAKE2b-256 of the
+        /// bincode-encoded public inputs.
+        pub fn id(&self) -> anyhow::Result<[u8; 32]> {
+            Ok(accum::prf::blake2b_32(DS_TXPCD_ID_V1, &bincode::serialize(&self.public)?))
+        }
+    }
+
+    /// `AggPCDPublic::included_txids_digest` over `children`, in order.
+    pub fn included_txids_digest(children: &[TxPCD]) -> anyhow::Result<[u8; 32]> {
+        let ids = children.iter().map(TxPCD::id).collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(crate::aggregate::txid_binding(&ids))
+    }
+
+    const ARCHIVE_V1: u8 = 1;
+
+    /// An aggregate together with the tx PCDs it covers, for archival nodes.
+    #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+    pub struct ArchiveBundle {
+        pub agg: AggPCD,
+        pub children: Vec<TxPCD>,
+    }
+
+    impl ArchiveBundle {
+        /// version || u32 len || bincode(agg) || u32 count || (u32 len || bincode(child))*,
+        /// lengths big-endian as in the canonical encodings.
+        pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+            let mut out = vec![ARCHIVE_V1];
+            primitives::encode_bytes(&bincode::serialize(&self.agg)?, &mut out);
+            primitives::encode_u32(u32::try_from(self.children.len())?, &mut out);
+            for child in &self.children {
+                primitives::encode_bytes(&bincode::serialize(child)?, &mut out);
+            }
+            Ok(out)
+        }
+
+        pub fn from_bytes(mut data: &[u8]) -> anyhow::Result<Self> {
+            let ver = primitives::read_u8(&mut data)?;
+            if ver != ARCHIVE_V1 { anyhow::bail!("unsupported archive version: {}", ver); }
+            let agg = bincode::deserialize(&primitives::read_vec(&mut data)?)?;
+            let count = primitives::read_u32(&mut data)? as usize;
+            // Each child takes at least its 4-byte length prefix.
+            if count > data.len() / 4 { anyhow::bail!("archive claims {} children in {} bytes", count, data.len()); }
+            let mut children = Vec::with_capacity(count);
+            for _ in 0..count {
+                children.push(bincode::deserialize(&primitives::read_vec(&mut data)?)?);
+            }
+            if !data.is_empty() { anyhow::bail!("trailing bytes in ArchiveBundle"); }
+            Ok(Self { agg, children })
+        }
+
+        /// Verify the aggregate in `domain` and every child under `vk`, and that
+        /// the aggregate's count and `included_txids_digest` cover exactly `children`.
+        pub fn verify(&self, vk: &VerifyingKey, domain: &DomainSep) -> bool {
+            let digest_ok = matches!(included_txids_digest(&self.children), Ok(d) if d == self.agg.public.included_txids_digest);
+            digest_ok
+                && self.agg.public.total_count as usize == self.children.len()
+                && matches!(verify_agg(vk, &self.agg, domain), Ok(true))
+                && self.children.iter().all(|c| matches!(verify_tx(vk, c), Ok(true)))
+        }
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
@@ -111,18 +526,35 @@ pub struct ProofBytes(pub Vec<u8>);
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct VerifyingKey(pub Vec<u8>);
 
+const DS_VKEY_FP_V1: &[u8; 16] = b"tachyon.vkey.v1\0"; // 15 + 1 = 16
+
+impl VerifyingKey {
+    /// BLAKE2b-256 of the key bytes, bound into aggregate public inputs.
+    pub fn fingerprint(&self) -> [u8; 32] { accum::prf::blake2b_32(DS_VKEY_FP_V1, &self.0) }
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct ProvingKey(pub Vec<u8>);
 
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct DomainSep { pub is_block: bool }
 
+/// PCD view of a stamp: proof bytes plus recursion domain. Distinct from the
+/// on-chain `primitives::Tachystamp` (range anchor, grams, auth, PCD proof),
+/// which converts into this type for `merge`/`api::merge`.
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct Tachystamp {
     pub proof: ProofBytes,
     pub domain: DomainSep,
 }
 
+impl From<&primitives::Tachystamp> for Tachystamp {
+    /// Takes the embedded PCD proof; wire stamps are always tx-level.
+    fn from(stamp: &primitives::Tachystamp) -> Self {
+        Tachystamp { proof: ProofBytes(stamp.pcd_proof.0.clone()), domain: DomainSep { is_block: false } }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 pub struct Aggregate {
     pub proof: ProofBytes,
@@ -134,6 +566,7 @@ pub fn prove_tx(_pk: &ProvingKey, _witness: &[u8]) -> anyhow::Result<Tachystamp>
     Ok(Tachystamp { proof: ProofBytes(vec![]), domain: DomainSep { is_block: false } })
 }
 
+/// Merge PCD stamps (`pcd::Tachystamp`, not the on-chain `primitives::Tachystamp`).
 pub fn merge(_vk: &VerifyingKey, stamps: &[Tachystamp]) -> anyhow::Result<Aggregate> {
     Ok(Aggregate { proof: ProofBytes(vec![]), domain: DomainSep { is_block: false }, count: stamps.len() as u32 })
 }
@@ -1,34 +1,165 @@
 //! WalletNonMemStepCircuit skeleton: updates A/S and enforces alpha != 0.
-
-use accum::{ipa, poseidon};
+//!
+//! Non-membership semantics: the wallet queries key `v` against block i with
+//! polynomial p_i(X) = prod_j (X - a_ij) committed as P_i. The step binds
+//! alpha_i = p_i(v) through `EvaluationProof`, so alpha_i != 0 (shown by its
+//! inverse) holds exactly when `v` is not one of the block's tachygrams.
+//! The binding is checked off-circuit for now; the circuit will enforce it.
+
+use accum::{ipa, poly, poseidon};
+use crate::block_circuit::commit_block_coeffs;
 use halo2_proofs::{plonk::{Circuit, ConstraintSystem, Error}};
 use pasta_curves::{pallas, vesta::Scalar as FrVesta};
-use ff::{Field, PrimeField};
+use ff::Field;
 use group::prime::PrimeCurveAffine;
 use group::Curve;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
+/// Off-circuit opening of P_i at the queried key: the block polynomial itself.
 #[derive(Clone, Debug, Default)]
+pub struct EvaluationProof {
+    /// Coefficients of p_i in increasing degree order.
+    pub coeffs: Vec<FrVesta>,
+}
+
+impl EvaluationProof {
+    /// Whether the coefficients commit to `p_i` and evaluate to `alpha` at `v`.
+    ///
+    /// `v` and `alpha` are the wallet's secrets: the evaluation runs the same
+    /// field operations for every `v` (`poly::eval_horner`), both checks always
+    /// run and are combined as a `Choice`, and `alpha` is compared with
+    /// `ct_eq`, so timing depends only on the public coefficients.
+    pub fn verify(&self, p_i: &pallas::Affine, v: FrVesta, alpha: FrVesta) -> bool {
+        let commits = Choice::from((commit_block_coeffs(&self.coeffs) == *p_i) as u8);
+        let evaluates = poly::eval_horner(&self.coeffs, v).ct_eq(&alpha);
+        (commits & evaluates).into()
+    }
+}
+
+/// Wallet step witness. Secret: `v`, `alpha_i`, `alpha_inv` (which reveal the
+/// queried key) and `s_i`; these are wiped on drop. `p_i`, `a_i` and the block
+/// coefficients in `evaluation_proof` are public.
+#[derive(Clone, Default)]
 pub struct WalletStepWitness {
+    /// Queried key.
     pub v: FrVesta,
+    /// alpha_i = p_i(v), nonzero iff `v` is not a root of p_i.
     pub alpha_i: FrVesta,
     pub alpha_inv: FrVesta,
     pub p_i: pallas::Affine,
     pub s_i: pallas::Affine,
     pub a_i: pallas::Affine,
+    /// Binds `alpha_i` to the evaluation of P_i's polynomial at `v`.
+    pub evaluation_proof: EvaluationProof,
+}
+
+impl WalletStepWitness {
+    /// Witness for querying `v` against the block with coefficients `coeffs`.
+    /// Fails if `v` is a root of the block polynomial (the key is a member).
+    ///
+    /// Constant-time in `v`: the evaluation and the inversion (a fixed public
+    /// exponent) run the same operations for every key; only the membership
+    /// outcome, which the caller learns anyway, decides the branch.
+    pub fn new(v: FrVesta, coeffs: Vec<FrVesta>, s_i: pallas::Affine, a_i: pallas::Affine) -> anyhow::Result<Self> {
+        let alpha_i = poly::eval_horner(&coeffs, v);
+        let alpha_inv = Option::<FrVesta>::from(alpha_i.invert())
+            .ok_or_else(|| anyhow::anyhow!("queried key is a root of the block polynomial"))?;
+        Ok(Self {
+            v,
+            alpha_i,
+            alpha_inv,
+            p_i: commit_block_coeffs(&coeffs),
+            s_i,
+            a_i,
+            evaluation_proof: EvaluationProof { coeffs },
+        })
+    }
+}
+
+impl core::fmt::Debug for WalletStepWitness {
+    /// Prints the public fields only; the secrets show as `..`.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("WalletStepWitness")
+            .field("v", &"..")
+            .field("alpha_i", &"..")
+            .field("alpha_inv", &"..")
+            .field("p_i", &self.p_i)
+            .field("s_i", &"..")
+            .field("a_i", &self.a_i)
+            .field("evaluation_proof", &self.evaluation_proof)
+            .finish()
+    }
 }
 
+impl Zeroize for WalletStepWitness {
+    /// Overwrite the secret fields. Pasta types have no volatile wipe of their
+    /// own, so the stores are kept observable with `black_box`; best effort.
+    fn zeroize(&mut self) {
+        self.v = FrVesta::ZERO;
+        self.alpha_i = FrVesta::ZERO;
+        self.alpha_inv = FrVesta::ZERO;
+        self.s_i = pallas::Affine::identity();
+        std::hint::black_box(&mut *self);
+    }
+}
+
+impl Drop for WalletStepWitness {
+    fn drop(&mut self) { self.zeroize(); }
+}
+
+impl ZeroizeOnDrop for WalletStepWitness {}
+
 #[derive(Clone, Debug, Default)]
 pub struct WalletStepPublic {
     pub a_i_bytes: [u8; 32],
+    pub p_i_bytes: [u8; 32],
+    pub s_i_bytes: [u8; 32],
+    /// P_i' = P_i - [alpha_i] G_0 (alpha_i itself stays private).
+    pub p_prime_bytes: [u8; 32],
     pub a_next_bytes: [u8; 32],
     pub s_next_bytes: [u8; 32],
 }
 
+impl WalletStepPublic {
+    /// Off-circuit check of both state updates: points decode, A_i and P_i are
+    /// not the identity, A_{i+1} = [H_A(A_i, P_i)]A_i + P_i and
+    /// S_{i+1} = [H_S(S_i, P_i')]S_i + P_i'.
+    pub fn is_consistent(&self) -> bool {
+        let decoded = (
+            ipa::decode_point(&self.a_i_bytes),
+            ipa::decode_point(&self.p_i_bytes),
+            ipa::decode_point(&self.s_i_bytes),
+            ipa::decode_point(&self.p_prime_bytes),
+        );
+        let (a_i, p_i, s_i, p_prime) = match decoded {
+            (Some(a), Some(p), Some(s), Some(pp)) => (a, p, s, pp),
+            _ => return false,
+        };
+        if bool::from(a_i.is_identity() | p_i.is_identity()) { return false; }
+        let h_i = ipa::map_vesta_scalar_to_pallas(&poseidon::hash_A_h(&self.a_i_bytes, &self.p_i_bytes));
+        let h_i_prime = ipa::map_vesta_scalar_to_pallas(&poseidon::hash_S_h(&self.s_i_bytes, &self.p_prime_bytes));
+        let a_next = (a_i.to_curve() * h_i + p_i.to_curve()).to_affine();
+        let s_next = (s_i.to_curve() * h_i_prime + p_prime.to_curve()).to_affine();
+        ipa::encode_point(&a_next) == self.a_next_bytes && ipa::encode_point(&s_next) == self.s_next_bytes
+    }
+}
+
+/// Off-circuit wallet step. A_i and P_i must not be the identity: wallets
+/// only step from a published accumulator, so the genesis exception of
+/// `consensus::accum_record` (an identity A_0) never applies here.
 pub fn prove_wallet_step(w: &WalletStepWitness) -> anyhow::Result<(WalletStepPublic, Vec<u8>)> {
+    if bool::from(w.a_i.is_identity()) { anyhow::bail!("accumulator A_i is the identity"); }
+    if bool::from(w.p_i.is_identity()) { anyhow::bail!("block commitment P_i is the identity"); }
+
     // Check alpha * alpha_inv = 1 (off-circuit sanity; circuit will enforce).
-    if w.alpha_i * w.alpha_inv != FrVesta::ONE {
+    if !bool::from((w.alpha_i * w.alpha_inv).ct_eq(&FrVesta::ONE)) {
         anyhow::bail!("alpha inverse mismatch");
     }
+    // alpha_i must be p_i(v), otherwise alpha_i != 0 says nothing about v.
+    if !w.evaluation_proof.verify(&w.p_i, w.v, w.alpha_i) {
+        anyhow::bail!("alpha_i is not the evaluation of p_i at v");
+    }
 
     // Compute P_i' = P_i - [alpha_i] G_0.
     let g0 = ipa::g0();
@@ -60,6 +191,9 @@ pub fn prove_wallet_step(w: &WalletStepWitness) -> anyhow::Result<(WalletStepPub
     Ok((
         WalletStepPublic {
             a_i_bytes,
+            p_i_bytes,
+            s_i_bytes,
+            p_prime_bytes,
             a_next_bytes: ipa::encode_point(&a_next),
             s_next_bytes: ipa::encode_point(&s_next),
         },
@@ -0,0 +1,164 @@
+use pcd::{aggregate::{aggregate_txids, verify_non_inclusion}, api, AggPCDPublic, AuthorizingDigest, DomainSep, ProofBytes, ProvingKey, TxPCDPublic, VerifyingKey};
+
+#[test]
+fn aggregate_binding_detects_txid_tampering() {
+    let vk = VerifyingKey(vec![]);
+    let agg = aggregate_txids(&vk, vec![[1u8; 32], [2u8; 32], [3u8; 32]]).unwrap();
+    assert!(agg.verify_binding());
+
+    let mut reordered = agg.clone();
+    reordered.txids.swap(0, 2);
+    assert!(!reordered.verify_binding());
+
+    let mut altered = agg.clone();
+    altered.txids[1][0] ^= 1;
+    assert!(!altered.verify_binding());
+
+    let mut dropped = agg;
+    dropped.txids.pop();
+    assert!(!dropped.verify_binding());
+}
+
+#[cfg(feature = "testing")]
+#[test]
+fn verify_binding_rejects_corrupt_proof() {
+    let vk = VerifyingKey(vec![]);
+    let agg = aggregate_txids(&vk, vec![[4u8; 32], [5u8; 32]]).unwrap();
+    assert!(agg.verify_binding());
+    assert!(!agg.with_corrupt_proof().verify_binding());
+}
+
+#[test]
+fn authorizing_digest_hex_roundtrip() {
+    let d = AuthorizingDigest([0x5eu8; 32]);
+    assert_eq!(AuthorizingDigest::from_hex(&d.to_string()).unwrap(), d);
+    assert!(AuthorizingDigest::from_hex(&format!("{}x", &d.hex()[1..])).is_err());
+}
+
+#[test]
+fn verify_agg_rejects_mismatched_verifying_key() {
+    let vk = VerifyingKey(vec![1, 2, 3]);
+    let other = VerifyingKey(vec![1, 2, 4]);
+    assert_eq!(vk.fingerprint(), VerifyingKey(vec![1, 2, 3]).fingerprint());
+    assert_ne!(vk.fingerprint(), other.fingerprint());
+
+    let public = AggPCDPublic {
+        total_count: 2,
+        included_txids_digest: [0u8; 32],
+        window_root: [0u8; 32],
+        block_mmr_leaf_hash: [0u8; 32],
+        domain: DomainSep { is_block: false },
+        vk_fingerprint: vk.fingerprint(),
+    };
+    let tx = DomainSep { is_block: false };
+    let agg = api::prove_agg(&ProvingKey(vec![]), public, &[]).unwrap();
+    assert!(api::verify_agg(&vk, &agg, &tx).unwrap());
+    assert!(!api::verify_agg(&other, &agg, &tx).unwrap());
+
+    // Rewriting the self-reported key or domain does not move the proof.
+    let mut relabeled = agg.clone();
+    relabeled.public.vk_fingerprint = other.fingerprint();
+    assert!(!api::verify_agg(&other, &relabeled, &tx).unwrap());
+    assert!(!api::verify_agg(&vk, &agg, &DomainSep { is_block: true }).unwrap());
+    let mut promoted = agg.clone();
+    promoted.public.domain = DomainSep { is_block: true };
+    assert!(!api::verify_agg(&vk, &promoted, &DomainSep { is_block: true }).unwrap());
+    assert!(!api::verify_agg(&vk, &api::AggPCD { proof: ProofBytes(vec![]), ..agg }, &tx).unwrap());
+}
+
+fn tx_pcd(fee: u64) -> api::TxPCD {
+    let public = TxPCDPublic {
+        range_anchor_min_pos: 0,
+        range_anchor_max_pos: 10,
+        range_root_min: [1u8; 32],
+        range_root_max: [2u8; 32],
+        authorizing_digest: AuthorizingDigest([3u8; 32]),
+        nullifiers: vec![[fee as u8; 32]],
+        commitments: vec![[fee as u8 + 1; 32]],
+        value_commitment: [4u8; 32],
+        fee,
+        hash_orchard_root: [0u8; 32],
+        hash_nullifier_block: [0u8; 32],
+        hash_commitment_delta: [0u8; 32],
+    };
+    api::prove_tx(&ProvingKey(vec![]), &[], public).unwrap()
+}
+
+fn archive(vk: &VerifyingKey, children: Vec<api::TxPCD>, digest_over: &[api::TxPCD]) -> api::ArchiveBundle {
+    let public = AggPCDPublic {
+        total_count: children.len() as u32,
+        included_txids_digest: api::included_txids_digest(digest_over).unwrap(),
+        window_root: [0u8; 32],
+        block_mmr_leaf_hash: [0u8; 32],
+        domain: DomainSep { is_block: false },
+        vk_fingerprint: vk.fingerprint(),
+    };
+    let agg = api::prove_agg(&ProvingKey(vec![]), public, &children).unwrap();
+    api::ArchiveBundle { agg, children }
+}
+
+#[test]
+fn archive_bundle_roundtrips_and_verifies() {
+    let vk = VerifyingKey(vec![7]);
+    let children = vec![tx_pcd(10), tx_pcd(20), tx_pcd(30)];
+    let bundle = archive(&vk, children.clone(), &children);
+    let bytes = bundle.to_bytes().unwrap();
+    let decoded = api::ArchiveBundle::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded, bundle);
+    assert!(decoded.verify(&vk, &DomainSep { is_block: false }));
+    assert!(!decoded.verify(&VerifyingKey(vec![8]), &DomainSep { is_block: false }));
+    assert!(!decoded.verify(&vk, &DomainSep { is_block: true }));
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(api::ArchiveBundle::from_bytes(&trailing).is_err());
+    assert!(api::ArchiveBundle::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+}
+
+#[test]
+fn archive_bundle_detects_child_missing_from_digest() {
+    let vk = VerifyingKey(vec![7]);
+    let children = vec![tx_pcd(10), tx_pcd(20), tx_pcd(30)];
+    // The aggregate's digest leaves out the last child.
+    assert!(!archive(&vk, children.clone(), &children[..2]).verify(&vk, &DomainSep { is_block: false }));
+
+    // Dropping a child from the archive is caught as well.
+    let mut dropped = archive(&vk, children.clone(), &children);
+    dropped.children.pop();
+    assert!(!dropped.verify(&vk, &DomainSep { is_block: false }));
+
+    let mut reordered = archive(&vk, children.clone(), &children);
+    reordered.children.swap(0, 1);
+    assert!(!reordered.verify(&vk, &DomainSep { is_block: false }));
+}
+
+#[test]
+fn non_inclusion_witness_brackets_absent_txid() {
+    let vk = VerifyingKey(vec![]);
+    let agg = aggregate_txids(&vk, vec![[30u8; 32], [10u8; 32], [20u8; 32], [40u8; 32], [50u8; 32]]).unwrap();
+    let root = agg.sorted_txids_root();
+
+    let absent = [25u8; 32];
+    let witness = agg.prove_non_inclusion(&absent).unwrap();
+    assert_eq!(witness.lower.as_ref().unwrap().txid, [20u8; 32]);
+    assert_eq!(witness.upper.as_ref().unwrap().txid, [30u8; 32]);
+    assert!(verify_non_inclusion(&root, &absent, &witness));
+
+    // Below and above every present txid.
+    for edge in [[1u8; 32], [60u8; 32]] {
+        assert!(verify_non_inclusion(&root, &edge, &agg.prove_non_inclusion(&edge).unwrap()));
+    }
+
+    // A present txid has no witness, and a bracket cannot be reused for it.
+    assert!(agg.prove_non_inclusion(&[20u8; 32]).is_err());
+    assert!(!verify_non_inclusion(&root, &[20u8; 32], &witness));
+    assert!(!verify_non_inclusion(&root, &[30u8; 32], &witness));
+
+    // Skipping a present txid between the brackets is rejected.
+    let mut gap = witness.clone();
+    gap.upper = agg.prove_non_inclusion(&[45u8; 32]).unwrap().lower;
+    assert!(!verify_non_inclusion(&root, &[35u8; 32], &gap));
+    // So is a witness against another aggregate's root.
+    let other = aggregate_txids(&vk, vec![[20u8; 32], [30u8; 32]]).unwrap();
+    assert!(!verify_non_inclusion(&other.sorted_txids_root(), &absent, &witness));
+}
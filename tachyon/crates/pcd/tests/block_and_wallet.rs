@@ -1,7 +1,9 @@
 use accum::{ipa, poly};
-use pcd::{block_circuit::BlockPolyWitness, wallet_step::WalletStepWitness, api2};
+use pcd::{block_circuit::{self, fr_to_le_bits, BlockPolyCircuit, BlockPolyConfig, BlockPolyWitness, WitnessHeaderError}, wallet_step::WalletStepWitness, api2};
 use pasta_curves::{pallas, vesta::Scalar as FrVesta};
 use ff::Field;
+use group::prime::PrimeCurveAffine;
+use halo2_proofs::{circuit::{Layouter, SimpleFloorPlanner}, dev::MockProver, plonk::{Circuit, ConstraintSystem, Error}};
 
 #[test]
 fn block_poly_off_circuit_sanity() {
@@ -9,33 +11,384 @@ fn block_poly_off_circuit_sanity() {
     let roots = [3u64,5,7].map(FrVesta::from);
     let coeffs = poly::roots_to_coeffs(&roots);
     let p_i = {
-        // Map coeffs (FrVesta) to Pallas scalars via a deterministic hash-to-scalar.
-        let scalars: Vec<pallas::Scalar> = coeffs
-            .iter()
-            .map(|x| {
-                let xb = ff::PrimeField::to_repr(x);
-                let mut b32 = [0u8; 32];
-                b32.copy_from_slice(xb.as_ref());
-                ipa::map_vesta_scalar_to_pallas(&b32)
-            })
-            .collect();
+        // Embed coeffs (FrVesta) into the Pallas scalar field.
+        let scalars: Vec<pallas::Scalar> = coeffs.iter().map(ipa::embed_vesta_scalar).collect();
         ipa::commit_coeffs(&scalars)
     };
     let a_i = ipa::g0();
     let wit = BlockPolyWitness { roots: roots.to_vec(), coeffs, p_i, a_i };
-    let (_pub, _proof) = api2::prove_block(&api2::Params { k: 18 }, &wit).expect("off-circuit check");
+    let params = api2::Params { k: 8 };
+    let (pk, _vk) = api2::setup(&params).unwrap();
+    let (_pub, _proof) = api2::prove_block(&params, &pk, &wit).expect("off-circuit check");
 }
 
 #[test]
 fn wallet_step_off_circuit_sanity() {
-    // Use alpha != 0 and its inverse.
-    let alpha = FrVesta::from(9u64);
-    let alpha_inv = alpha.invert().unwrap();
-    let p_i = ipa::g0();
-    let a_i = ipa::g0();
-    let s_i = ipa::g0();
-    let wit = WalletStepWitness { v: FrVesta::from(1u64), alpha_i: alpha, alpha_inv, p_i, s_i, a_i };
+    // Query v = 1 against the block with roots {3, 5, 7}: alpha = p_i(1) != 0.
+    let coeffs = poly::roots_to_coeffs(&[3u64, 5, 7].map(FrVesta::from));
+    let wit = WalletStepWitness::new(FrVesta::from(1u64), coeffs, ipa::g0(), ipa::g0()).unwrap();
+    assert_eq!(wit.alpha_i, -FrVesta::from(48u64));
+    let shown = format!("{wit:?}");
+    assert!(!shown.contains(&format!("{:?}", wit.alpha_i)) && !shown.contains(&format!("{:?}", wit.alpha_inv)));
     let (_pub, _proof) = api2::prove_wallet_step(&api2::Params { k: 18 }, &wit).expect("off-circuit step");
 }
 
+#[test]
+fn wallet_step_rejects_identity_accumulator() {
+    let g = ipa::g0();
+    let identity = pallas::Affine::identity();
+    let coeffs = poly::roots_to_coeffs(&[FrVesta::from(2u64)]);
+    let wit = WalletStepWitness::new(FrVesta::ONE, coeffs, g, identity).unwrap();
+    assert!(api2::prove_wallet_step(&api2::Params { k: 8 }, &wit).is_err());
+    let mut fixed_a = wit.clone();
+    fixed_a.a_i = g;
+    let mut identity_p = fixed_a.clone();
+    identity_p.p_i = identity;
+    assert!(api2::prove_wallet_step(&api2::Params { k: 8 }, &identity_p).is_err());
+
+    let (mut public, _) = api2::prove_wallet_step(&api2::Params { k: 8 }, &fixed_a).unwrap();
+    assert!(public.is_consistent());
+    public.a_i_bytes = ipa::encode_point(&identity);
+    assert!(!public.is_consistent());
+}
+
+#[test]
+fn wallet_step_rejects_alpha_not_bound_to_evaluation() {
+    let coeffs = poly::roots_to_coeffs(&[3u64, 5, 7].map(FrVesta::from));
+    let g = ipa::g0();
+    let params = api2::Params { k: 8 };
+    let wit = WalletStepWitness::new(FrVesta::from(1u64), coeffs.clone(), g, g).unwrap();
+    assert!(wit.evaluation_proof.verify(&wit.p_i, wit.v, wit.alpha_i));
+    assert!(api2::prove_wallet_step(&params, &wit).is_ok());
+
+    // Nonzero alpha with a valid inverse, but not p_i(v).
+    let alpha = wit.alpha_i + FrVesta::ONE;
+    let mut forged = wit.clone();
+    forged.alpha_i = alpha;
+    forged.alpha_inv = alpha.invert().unwrap();
+    assert!(!wit.evaluation_proof.verify(&wit.p_i, wit.v, alpha));
+    assert!(api2::prove_wallet_step(&params, &forged).is_err());
+
+    // Opening coefficients that do not commit to P_i.
+    let mut other = wit.clone();
+    other.evaluation_proof.coeffs[0] += FrVesta::ONE;
+    assert!(!other.evaluation_proof.verify(&wit.p_i, wit.v, poly::eval_horner(&other.evaluation_proof.coeffs, wit.v)));
+    assert!(api2::prove_wallet_step(&params, &other).is_err());
+
+    // A member key has alpha = 0 and cannot produce a witness.
+    assert!(WalletStepWitness::new(FrVesta::from(5u64), coeffs, g, g).is_err());
+}
+
+#[derive(Clone)]
+struct BitDecompCircuit { value: FrVesta, bits: Vec<bool> }
+
+impl Circuit<FrVesta> for BitDecompCircuit {
+    type Config = BlockPolyConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self { Self { value: FrVesta::ZERO, bits: vec![false; self.bits.len()] } }
+
+    fn configure(meta: &mut ConstraintSystem<FrVesta>) -> Self::Config { BlockPolyCircuit::configure(meta) }
+
+    fn synthesize(&self, cfg: Self::Config, mut layouter: impl Layouter<FrVesta>) -> Result<(), Error> {
+        cfg.decompose_bits(layouter.namespace(|| "bits"), self.value, &self.bits)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn decompose_bits_binds_value() {
+    let value = FrVesta::from(0xdead_beef_u64);
+    let bits = fr_to_le_bits(&value);
+    let ok = MockProver::run(11, &BitDecompCircuit { value, bits: bits.clone() }, vec![]).unwrap();
+    assert!(ok.verify().is_ok());
 
+    // Flip one bit: the running sum no longer matches the witnessed value.
+    let mut bad_bits = bits;
+    bad_bits[3] = !bad_bits[3];
+    let bad = MockProver::run(11, &BitDecompCircuit { value, bits: bad_bits }, vec![]).unwrap();
+    assert!(bad.verify().is_err());
+
+    // value + q fits in NUM_BITS bits and sums to value in the field; the
+    // range check against q - 1 rejects it. Built as (q - 1) + (value + 1).
+    let (max, low) = (fr_to_le_bits(&-FrVesta::ONE), fr_to_le_bits(&(value + FrVesta::ONE)));
+    let mut carry = false;
+    let wrapped: Vec<bool> = max.iter().zip(&low).map(|(&x, &y)| {
+        let sum = x as u8 + y as u8 + carry as u8;
+        carry = sum > 1;
+        sum & 1 == 1
+    }).collect();
+    assert!(!carry);
+    let alias = MockProver::run(11, &BitDecompCircuit { value, bits: wrapped }, vec![]).unwrap();
+    assert!(alias.verify().is_err());
+    let max_ok = MockProver::run(11, &BitDecompCircuit { value: -FrVesta::ONE, bits: max }, vec![]).unwrap();
+    assert!(max_ok.verify().is_ok());
+}
+
+fn block_witness(roots: &[u64]) -> BlockPolyWitness {
+    let roots: Vec<FrVesta> = roots.iter().map(|&r| FrVesta::from(r)).collect();
+    let coeffs = poly::roots_to_coeffs(&roots);
+    let scalars: Vec<pallas::Scalar> = coeffs.iter().map(ipa::embed_vesta_scalar).collect();
+    BlockPolyWitness { roots, coeffs, p_i: ipa::commit_coeffs(&scalars), a_i: ipa::g0() }
+}
+
+#[test]
+fn block_circuit_satisfied_for_small_root_counts() {
+    for roots in [&[][..], &[9u64], &[9, 12]] {
+        let circuit = BlockPolyCircuit::from_witness(&block_witness(roots));
+        let prover = MockProver::run(11, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_ok(), "{} roots", roots.len());
+    }
+
+    // A coefficient vector of the wrong length is rejected at synthesis.
+    let mut bad = BlockPolyCircuit::from_witness(&block_witness(&[9]));
+    bad.coeffs.pop();
+    assert!(MockProver::run(11, &bad, vec![]).is_err());
+}
+
+#[test]
+fn no_witness_circuit_runs_at_production_k() {
+    // `api2::setup` and `verify_block` run the keygen shape; it must stay
+    // satisfiable at the k blocks are published with, `consensus::PUBLISH_K`
+    // = `api2::required_k(DEGREE_N)`.
+    let publish_k = api2::required_k(accum::params::DEGREE_N);
+    let circuit = BlockPolyCircuit::from_witness(&block_witness(&[3, 5, 7])).without_witnesses();
+    assert!(circuit.roots.is_empty());
+    for k in [8, publish_k] {
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_ok(), "k = {k}");
+    }
+    let params = api2::Params { k: publish_k };
+    let (pk, vk) = api2::setup(&params).unwrap();
+    let (public, proof) = api2::prove_block(&params, &pk, &block_witness(&[3, 5, 7])).unwrap();
+    assert!(api2::verify_block(&params, &vk, &public, &proof).unwrap());
+}
+
+#[test]
+fn verify_blocks_batch_matches_individual() {
+    let params = api2::Params { k: 8 };
+    let (pk, vk) = api2::setup(&params).unwrap();
+    let mut publics = Vec::new();
+    let mut proofs = Vec::new();
+    for roots in [&[1u64, 2][..], &[3, 4, 5], &[6]] {
+        let (public, proof) = api2::prove_block(&params, &pk, &block_witness(roots)).unwrap();
+        publics.push(public);
+        proofs.push(proof);
+    }
+    // Corrupt the accumulator output of the middle block.
+    publics[1].a_next_bytes = publics[0].a_next_bytes;
+
+    let proof_refs: Vec<&[u8]> = proofs.iter().map(|p| p.as_slice()).collect();
+    let batch = api2::verify_blocks_batch(&params, &vk, &publics, &proof_refs);
+    let individual: Vec<bool> = publics
+        .iter()
+        .zip(&proof_refs)
+        .map(|(p, proof)| api2::verify_block(&params, &vk, p, proof).unwrap())
+        .collect();
+    assert_eq!(batch, individual);
+    assert_eq!(batch, vec![true, false, true]);
+}
+
+#[test]
+fn detailed_block_verify_reports_failure_kind_per_index() {
+    use api2::BlockVerifyError;
+    let params = api2::Params { k: 8 };
+    let (pk, vk) = api2::setup(&params).unwrap();
+    let mut publics = Vec::new();
+    let mut proofs = Vec::new();
+    for roots in [&[1u64, 2][..], &[3, 4, 5], &[6]] {
+        let (public, proof) = api2::prove_block(&params, &pk, &block_witness(roots)).unwrap();
+        publics.push(public);
+        proofs.push(proof);
+    }
+    publics[0].a_next_bytes = publics[1].a_next_bytes;
+    // The last block has no proof.
+    let proof_refs: Vec<&[u8]> = proofs[..2].iter().map(|p| p.as_slice()).collect();
+
+    let detailed = api2::verify_blocks_detailed(&params, &vk, &publics, &proof_refs);
+    assert_eq!(detailed, vec![Err(BlockVerifyError::EcRelation), Ok(()), Err(BlockVerifyError::Snark)]);
+    let batch = api2::verify_blocks_batch(&params, &vk, &publics, &proof_refs);
+    assert_eq!(batch, detailed.iter().map(|r| r.is_ok()).collect::<Vec<_>>());
+
+    // Under a key for another `k` every consistent block is a SNARK failure.
+    let (_, vk9) = api2::setup(&api2::Params { k: 9 }).unwrap();
+    assert_eq!(api2::verify_blocks_detailed(&params, &vk9, &publics[1..2], &proof_refs[1..2]), vec![Err(BlockVerifyError::Snark)]);
+}
+
+#[test]
+fn setup_keys_prove_verify_and_reject_other_k() {
+    let params = api2::Params { k: 8 };
+    let (pk, vk) = api2::setup(&params).unwrap();
+    let (public, proof) = api2::prove_block(&params, &pk, &block_witness(&[2, 9])).unwrap();
+    assert!(api2::verify_block(&params, &vk, &public, &proof).unwrap());
+    // The proof is bound to the key and to these public inputs.
+    assert!(!api2::verify_block(&params, &vk, &public, &[]).unwrap());
+    assert!(!api2::verify_block(&params, &vk, &public, &proof[..proof.len() - 1]).unwrap());
+    let (other, other_proof) = api2::prove_block(&params, &pk, &block_witness(&[3])).unwrap();
+    assert!(api2::verify_block(&params, &vk, &other, &other_proof).unwrap());
+    assert!(!api2::verify_block(&params, &vk, &other, &proof).unwrap());
+
+    let path = std::env::temp_dir().join(format!("tachyon-block-keys-{}.bin", std::process::id()));
+    api2::save_keys(&path, &pk, &vk).unwrap();
+    let (pk2, vk2) = api2::load_keys(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!((&pk2, &vk2), (&pk, &vk));
+
+    let (pk9, vk9) = api2::setup(&api2::Params { k: 9 }).unwrap();
+    assert_ne!(vk9.info().unwrap().vk_fingerprint, vk.info().unwrap().vk_fingerprint);
+    assert!(!api2::verify_block(&params, &vk9, &public, &proof).unwrap());
+    assert!(api2::prove_block(&params, &pk9, &block_witness(&[2, 9])).is_err());
+}
+
+#[test]
+fn verify_wallet_steps_batch_flags_corrupted_s_next() {
+    let params = api2::Params { k: 8 };
+    let mut publics = Vec::new();
+    let mut proofs = Vec::new();
+    for (a, p) in [(3u64, 5u64), (7, 11), (13, 17)] {
+        let g = ipa::g0();
+        let coeffs = poly::roots_to_coeffs(&[FrVesta::from(a), FrVesta::from(p)]);
+        let wit = WalletStepWitness::new(FrVesta::ONE, coeffs, g, g).unwrap();
+        let (public, proof) = api2::prove_wallet_step(&params, &wit).unwrap();
+        publics.push(public);
+        proofs.push(proof);
+    }
+    publics[2].s_next_bytes = publics[1].s_next_bytes;
+    let proof_refs: Vec<&[u8]> = proofs.iter().map(|p| p.as_slice()).collect();
+    let batch = api2::verify_wallet_steps_batch(&params, &publics, &proof_refs);
+    let individual: Vec<bool> = publics
+        .iter()
+        .zip(&proof_refs)
+        .map(|(p, proof)| api2::verify_wallet_step(&params, p, proof).unwrap())
+        .collect();
+    assert_eq!(batch, vec![true, true, false]);
+    assert_eq!(batch, individual);
+}
+
+#[test]
+fn block_witness_bytes_roundtrip() {
+    let wit = block_witness(&[4, 8, 15]);
+    let bytes = wit.to_bytes();
+    let decoded = BlockPolyWitness::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.roots, wit.roots);
+    let (pub_a, _) = pcd::block_circuit::prove_block_poly(&wit).unwrap();
+    let (pub_b, _) = pcd::block_circuit::prove_block_poly(&decoded).unwrap();
+    assert_eq!((pub_a.p_i_bytes, pub_a.a_i_bytes, pub_a.a_next_bytes), (pub_b.p_i_bytes, pub_b.a_i_bytes, pub_b.a_next_bytes));
+
+    let mut short = wit.clone();
+    short.coeffs.pop();
+    assert!(BlockPolyWitness::from_bytes(&short.to_bytes()).is_err());
+    // First root repr replaced by a value above the modulus.
+    let mut non_canonical = bytes.clone();
+    non_canonical[6..38].copy_from_slice(&[0xFF; 32]);
+    assert!(BlockPolyWitness::from_bytes(&non_canonical).is_err());
+    let mut trailing = bytes;
+    trailing.push(0);
+    assert!(BlockPolyWitness::from_bytes(&trailing).is_err());
+}
+
+#[test]
+fn block_witness_rejects_unknown_header() {
+    let bytes = block_witness(&[4, 8]).to_bytes();
+    let header_err = |b: &[u8]| BlockPolyWitness::from_bytes(b).unwrap_err().downcast::<WitnessHeaderError>().unwrap();
+
+    let mut v2 = bytes.clone();
+    v2[0] = 2;
+    assert_eq!(header_err(&v2), WitnessHeaderError::UnsupportedVersion(2));
+    let mut other_field = bytes;
+    other_field[1] = 2;
+    assert_eq!(header_err(&other_field), WitnessHeaderError::UnknownField(2));
+}
+
+#[test]
+fn required_k_fits_block_circuit() {
+    let full = api2::required_k(accum::params::DEGREE_N);
+    assert!(api2::required_k(0) < api2::required_k(100));
+    assert!(api2::required_k(100) < full);
+    assert!(full <= 18);
+
+    // `required_k` is the smallest k that fits: one less runs out of rows.
+    for roots in [&[][..], &[4u64], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]] {
+        let k = api2::required_k(roots.len());
+        let circuit = BlockPolyCircuit::from_witness(&block_witness(roots));
+        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+        assert!(prover.verify().is_ok(), "{} roots at k = {k}", roots.len());
+        assert!(MockProver::run(k - 1, &circuit, vec![]).is_err(), "{} roots at k = {}", roots.len(), k - 1);
+    }
+
+    // A key too small for the block is refused before proving.
+    let small = api2::Params { k: api2::required_k(2) };
+    let (pk, _vk) = api2::setup(&small).unwrap();
+    assert!(api2::prove_block(&small, &pk, &block_witness(&[1, 2])).is_ok());
+    let many: Vec<u64> = (1..=20).collect();
+    assert!(api2::prove_block(&small, &pk, &block_witness(&many)).is_err());
+}
+
+#[test]
+fn wallet_step_witness_zeroizes_secrets() {
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+    fn assert_zeroize_on_drop<T: Zeroize + ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<WalletStepWitness>();
+
+    let coeffs = poly::roots_to_coeffs(&[FrVesta::from(2u64)]);
+    let mut wit = WalletStepWitness::new(FrVesta::from(9u64), coeffs.clone(), ipa::g0(), ipa::g0()).unwrap();
+    wit.zeroize();
+    assert_eq!((wit.v, wit.alpha_i, wit.alpha_inv), (FrVesta::ZERO, FrVesta::ZERO, FrVesta::ZERO));
+    assert_eq!(wit.s_i, pallas::Affine::identity());
+    // Public fields are left alone.
+    assert_eq!(wit.evaluation_proof.coeffs, coeffs);
+}
+
+#[test]
+fn deterministic_block_proofs_are_reproducible() {
+    let params = api2::Params { k: 8 };
+    let (pk, vk) = api2::setup(&params).unwrap();
+    let wit = block_witness(&[4, 6, 8]);
+    let (public_a, proof_a) = api2::prove_block_with(&params, &pk, &wit, api2::ProverRng::Deterministic).unwrap();
+    let (public_b, proof_b) = api2::prove_block_with(&params, &pk, &wit, api2::ProverRng::Deterministic).unwrap();
+    assert_eq!(proof_a, proof_b);
+    assert_eq!(api2::proof_seed(&public_a), api2::proof_seed(&public_b));
+    assert!(api2::verify_block(&params, &vk, &public_a, &proof_a).unwrap());
+
+    // OS randomness blinds each proof differently; both still verify.
+    let (_, os_a) = api2::prove_block_with(&params, &pk, &wit, api2::ProverRng::Os).unwrap();
+    let (_, os_b) = api2::prove_block_with(&params, &pk, &wit, api2::ProverRng::Os).unwrap();
+    assert_ne!(os_a, os_b);
+    assert_ne!(os_a, proof_a);
+    assert!(api2::verify_block(&params, &vk, &public_a, &os_a).unwrap());
+    assert!(api2::verify_block(&params, &vk, &public_a, &os_b).unwrap());
+
+    // The seed follows the public inputs.
+    let (other, _) = api2::prove_block_with(&params, &pk, &block_witness(&[4, 6]), api2::ProverRng::Deterministic).unwrap();
+    assert_ne!(api2::proof_seed(&other), api2::proof_seed(&public_a));
+}
+
+#[test]
+fn ec_only_verify_checks_accumulator_step() {
+    let (public, _) = block_circuit::prove_block_poly(&block_witness(&[3, 5])).unwrap();
+    assert!(api2::verify_block_ec_only(&public));
+
+    let mut tampered = public.clone();
+    tampered.a_next_bytes = public.a_i_bytes;
+    assert!(!api2::verify_block_ec_only(&tampered));
+    let mut undecodable = public;
+    undecodable.p_i_bytes = [0xff; 32];
+    assert!(!api2::verify_block_ec_only(&undecodable));
+}
+
+#[test]
+fn strict_prove_rejects_perturbed_coefficient() {
+    let wit = block_witness(&[3, 5, 7, 11]);
+    assert_eq!(block_circuit::prove_block_poly_strict(&wit).unwrap().0.a_next_bytes, block_circuit::prove_block_poly(&wit).unwrap().0.a_next_bytes);
+
+    for i in [0, 2, 4] {
+        let mut bad = wit.clone();
+        bad.coeffs[i] += FrVesta::ONE;
+        bad.p_i = block_circuit::commit_block_coeffs(&bad.coeffs);
+        let err = block_circuit::prove_block_poly_strict(&bad).unwrap_err();
+        assert!(err.to_string().contains(&format!("coefficient {}", i)), "{}", err);
+    }
+    let mut short = wit.clone();
+    short.coeffs.pop();
+    assert!(block_circuit::prove_block_poly_strict(&short).is_err());
+}
@@ -0,0 +1,71 @@
+use pcd::{api, AuthorizingDigest, DomainSep, ProofBytes, ProvingKey, Tachystamp, TxPCDPublic, VerifyingKey};
+use primitives::{PcdProof, RangeAnchor, Tachygram, TachystampBuilder, REDPALLAS_SIG_LEN};
+
+#[test]
+fn primitives_stamp_converts_and_merges() {
+    let anchor = RangeAnchor { min_pos: 1, max_pos: 4, root_min: [0u8; 32], root_max: [1u8; 32], frontier_attestation: vec![] };
+    let wire = TachystampBuilder::new()
+        .range_anchor(anchor)
+        .tachygram(Tachygram([5u8; 32]))
+        .auth(&[2u8; REDPALLAS_SIG_LEN])
+        .pcd_proof(PcdProof(vec![0xaa, 0xbb]))
+        .build()
+        .unwrap();
+
+    let stamp = Tachystamp::from(&wire);
+    assert_eq!(stamp.proof, ProofBytes(vec![0xaa, 0xbb]));
+    assert_eq!(stamp.domain, DomainSep { is_block: false });
+
+    let agg = api::merge(&VerifyingKey(vec![]), &[stamp.clone(), stamp]).unwrap();
+    assert_eq!(agg.count, 2);
+}
+
+#[test]
+fn verify_tx_against_stamp_checks_authorizing_digest() {
+    let anchor = RangeAnchor { min_pos: 1, max_pos: 4, root_min: [0u8; 32], root_max: [1u8; 32], frontier_attestation: vec![] };
+    let stamp = TachystampBuilder::new()
+        .range_anchor(anchor)
+        .tachygram(Tachygram([5u8; 32]))
+        .auth(&[2u8; REDPALLAS_SIG_LEN])
+        .pcd_proof(PcdProof(vec![0xaa]))
+        .build()
+        .unwrap();
+    let public = TxPCDPublic {
+        range_anchor_min_pos: 1,
+        range_anchor_max_pos: 4,
+        range_root_min: [0u8; 32],
+        range_root_max: [1u8; 32],
+        authorizing_digest: AuthorizingDigest(stamp.authorizing_digest32()),
+        nullifiers: vec![],
+        commitments: vec![],
+        value_commitment: [0u8; 32],
+        fee: 0,
+        hash_orchard_root: [0u8; 32],
+        hash_nullifier_block: [0u8; 32],
+        hash_commitment_delta: [0u8; 32],
+    };
+    let vk = VerifyingKey(vec![]);
+    let pcd = api::prove_tx(&ProvingKey(vec![]), &[], public).unwrap();
+    assert!(api::verify_tx_against_stamp(&vk, &pcd, &stamp).unwrap());
+
+    // Same stamp with an extra tachygram: canonical bytes differ, digest no longer matches.
+    let mut other = stamp.clone();
+    other.tachygrams.push(Tachygram([6u8; 32]));
+    assert_ne!(other.to_canonical_bytes(), stamp.to_canonical_bytes());
+    assert!(!api::verify_tx_against_stamp(&vk, &pcd, &other).unwrap());
+}
+
+#[test]
+fn genesis_tx_public_is_the_empty_bootstrap() {
+    // There is no TxPCDPublic validator yet; genesis is the one empty case it
+    // must accept, so pin the empty shape here and check it proves.
+    let genesis = TxPCDPublic::genesis();
+    assert!(genesis.nullifiers.is_empty() && genesis.commitments.is_empty());
+    assert_eq!((genesis.range_anchor_min_pos, genesis.range_anchor_max_pos, genesis.fee), (0, 0, 0));
+    assert_eq!(genesis.range_root_min, genesis.range_root_max);
+    assert_ne!(genesis.range_root_min, [0u8; 32]);
+    assert_eq!(genesis, TxPCDPublic::genesis());
+
+    let pcd = api::prove_tx(&ProvingKey(vec![]), &[], genesis).unwrap();
+    assert!(api::verify_tx(&VerifyingKey(vec![]), &pcd).unwrap());
+}
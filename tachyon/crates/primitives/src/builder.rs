@@ -0,0 +1,123 @@
+//! Validating builder for Tachystamps.
+
+use thiserror::Error;
+
+use crate::types::*;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TachystampBuildError {
+    #[error("missing range anchor")]
+    MissingRangeAnchor,
+    #[error("missing auth signature")]
+    MissingAuth,
+    #[error("missing pcd proof")]
+    MissingPcdProof,
+    #[error("invalid range anchor: min_pos {min_pos} > max_pos {max_pos}")]
+    InvalidRange { min_pos: u64, max_pos: u64 },
+    #[error("too many tachygrams: {0} > {MAX_TACHYGRAMS}")]
+    TooManyTachygrams(usize),
+    #[error("auth signature must be {REDPALLAS_SIG_LEN} bytes, got {0}")]
+    AuthLength(usize),
+}
+
+/// Fluent builder for `Tachystamp`; `build` checks the range anchor, the
+/// tachygram count and the auth signature length.
+#[derive(Clone, Debug, Default)]
+pub struct TachystampBuilder {
+    range_anchor: Option<RangeAnchor>,
+    tachygrams: Vec<Tachygram>,
+    auth: Option<Vec<u8>>,
+    pcd_proof: Option<PcdProof>,
+}
+
+impl TachystampBuilder {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn range_anchor(mut self, anchor: RangeAnchor) -> Self {
+        self.range_anchor = Some(anchor);
+        self
+    }
+
+    pub fn tachygram(mut self, tg: Tachygram) -> Self {
+        self.tachygrams.push(tg);
+        self
+    }
+
+    pub fn tachygrams(mut self, tgs: impl IntoIterator<Item = Tachygram>) -> Self {
+        self.tachygrams.extend(tgs);
+        self
+    }
+
+    /// Raw RedPallas signature bytes; the length is checked in `build`.
+    pub fn auth(mut self, sig: &[u8]) -> Self {
+        self.auth = Some(sig.to_vec());
+        self
+    }
+
+    pub fn pcd_proof(mut self, proof: PcdProof) -> Self {
+        self.pcd_proof = Some(proof);
+        self
+    }
+
+    pub fn build(self) -> Result<Tachystamp, TachystampBuildError> {
+        let range_anchor = self.range_anchor.ok_or(TachystampBuildError::MissingRangeAnchor)?;
+        if range_anchor.span().is_none() {
+            return Err(TachystampBuildError::InvalidRange { min_pos: range_anchor.min_pos, max_pos: range_anchor.max_pos });
+        }
+        if self.tachygrams.len() > MAX_TACHYGRAMS {
+            return Err(TachystampBuildError::TooManyTachygrams(self.tachygrams.len()));
+        }
+        let auth_bytes = self.auth.ok_or(TachystampBuildError::MissingAuth)?;
+        if auth_bytes.len() != REDPALLAS_SIG_LEN {
+            return Err(TachystampBuildError::AuthLength(auth_bytes.len()));
+        }
+        let mut auth = [0u8; REDPALLAS_SIG_LEN];
+        auth.copy_from_slice(&auth_bytes);
+        let pcd_proof = self.pcd_proof.ok_or(TachystampBuildError::MissingPcdProof)?;
+        Ok(Tachystamp { range_anchor, tachygrams: self.tachygrams, auth: RedPallasSig(auth), pcd_proof })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(min_pos: u64, max_pos: u64) -> RangeAnchor {
+        RangeAnchor { min_pos, max_pos, root_min: [1u8; 32], root_max: [2u8; 32], frontier_attestation: vec![] }
+    }
+
+    fn base() -> TachystampBuilder {
+        TachystampBuilder::new()
+            .range_anchor(anchor(10, 20))
+            .tachygram(Tachygram([7u8; 32]))
+            .auth(&[3u8; REDPALLAS_SIG_LEN])
+            .pcd_proof(PcdProof(vec![0xaa]))
+    }
+
+    #[test]
+    fn build_succeeds() {
+        let stamp = base().build().unwrap();
+        assert_eq!(stamp.tachygrams, vec![Tachygram([7u8; 32])]);
+        assert_eq!(stamp.range_anchor.min_pos, 10);
+        assert_eq!(stamp.auth.0, [3u8; REDPALLAS_SIG_LEN]);
+    }
+
+    #[test]
+    fn build_rejects_bad_range() {
+        let err = base().range_anchor(anchor(21, 20)).build().unwrap_err();
+        assert_eq!(err, TachystampBuildError::InvalidRange { min_pos: 21, max_pos: 20 });
+    }
+
+    #[test]
+    fn build_rejects_too_many_grams() {
+        let grams = (0..MAX_TACHYGRAMS).map(|_| Tachygram::default());
+        let err = base().tachygrams(grams).build().unwrap_err();
+        assert_eq!(err, TachystampBuildError::TooManyTachygrams(MAX_TACHYGRAMS + 1));
+    }
+
+    #[test]
+    fn build_rejects_bad_auth_length() {
+        let err = base().auth(&[0u8; 63]).build().unwrap_err();
+        assert_eq!(err, TachystampBuildError::AuthLength(63));
+    }
+}
@@ -1,6 +1,13 @@
 //! Digest derivation functions for nullifiers, sync tags, and tachygrams.
 
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
+#[cfg(feature = "std")]
+use accum::prf::{blake2b_32, blake2b_64};
 use blake2b_simd::Params as Blake2bParams;
+#[cfg(feature = "std")]
+use rayon::prelude::*;
 use pasta_curves::vesta::Scalar as FrVesta;
 use ff::FromUniformBytes;
 
@@ -14,6 +21,21 @@ const DS_SYNC_V1: &[u8; 16] = b"tachyon.sync.v1\0"; // 15 + 1 = 16
 const DS_TG_UNIFIED_TX_V1: &[u8; 16] = b"tg.unified.tx.v1"; // exactly 16
 const DS_TACHYGRAM_TO_FR_V1: &[u8; 16] = b"tg.to_fr.v1\0\0\0\0\0"; // exactly 16 bytes
 
+// accum is std-only; these match `accum::prf::{blake2b_32, blake2b_64}`.
+#[cfg(not(feature = "std"))]
+fn blake2b_32(personal: &[u8; 16], data: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(Blake2bParams::new().hash_length(32).personal(personal).hash(data).as_bytes());
+    out
+}
+
+#[cfg(not(feature = "std"))]
+fn blake2b_64(personal: &[u8; 16], data: &[u8]) -> [u8; 64] {
+    let mut out = [0u8; 64];
+    out.copy_from_slice(Blake2bParams::new().hash_length(64).personal(personal).hash(data).as_bytes());
+    out
+}
+
 /// Derive the fixed nullifier flavor at output creation. This value must be
 /// committed inside the note and is immutable for the note's lifetime.
 ///
@@ -21,16 +43,91 @@ const DS_TACHYGRAM_TO_FR_V1: &[u8; 16] = b"tg.to_fr.v1\0\0\0\0\0"; // exactly 16
 /// - note_commitment: canonical 32-byte note commitment `cm`
 /// - note_randomness: output-time secret randomness unique to the note
 pub fn derive_fixed_flavor(note_commitment: &[u8; 32], note_randomness: &[u8; 32]) -> NullifierFlavor {
+    derive_fixed_flavor_with_domain(FLAVOR_DOMAIN_V1, note_commitment, note_randomness)
+}
+
+/// Personalization of the original flavor derivation (`derive_fixed_flavor`).
+pub const FLAVOR_DOMAIN_V1: &[u8; 16] = DS_FLAVOR_V1;
+
+/// `derive_fixed_flavor` under another flavor domain, so later flavor versions
+/// coexist with v1. The domain is the BLAKE2b personalization, so flavors (and
+/// hence `derive_onchain_nullifier` outputs) for the same note differ across
+/// domains without the nullifier derivation taking the domain explicitly.
+pub fn derive_fixed_flavor_with_domain(domain: &[u8; 16], note_commitment: &[u8; 32], note_randomness: &[u8; 32]) -> NullifierFlavor {
     let mut input = [0u8; 64];
     input[..32].copy_from_slice(note_commitment);
     input[32..].copy_from_slice(note_randomness);
-    let hash = Blake2bParams::new()
-        .hash_length(32)
-        .personal(DS_FLAVOR_V1)
-        .hash(&input);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    NullifierFlavor(out)
+    NullifierFlavor(blake2b_32(domain, &input))
+}
+
+/// Why `FlavorRegistry::register` refused a domain.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlavorError {
+    /// The domain is a personalization used by another derivation here.
+    Reserved([u8; 16]),
+    Duplicate([u8; 16]),
+}
+
+impl fmt::Display for FlavorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (domain, why) = match self {
+            FlavorError::Reserved(d) => (d, "is reserved"),
+            FlavorError::Duplicate(d) => (d, "already registered"),
+        };
+        write!(f, "flavor domain ")?;
+        for b in domain { write!(f, "{:02x}", b)?; }
+        write!(f, " {}", why)
+    }
+}
+
+impl core::error::Error for FlavorError {}
+
+/// Flavor domains accepted for new notes. Starts with `FLAVOR_DOMAIN_V1`;
+/// further versions are registered explicitly.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FlavorRegistry {
+    domains: Vec<[u8; 16]>,
+}
+
+impl Default for FlavorRegistry {
+    fn default() -> Self {
+        Self { domains: vec![*FLAVOR_DOMAIN_V1] }
+    }
+}
+
+impl FlavorRegistry {
+    /// Registry holding only `FLAVOR_DOMAIN_V1`.
+    pub fn new() -> Self { Self::default() }
+
+    /// Add a flavor domain. Rejects duplicates and the personalizations this
+    /// module uses for other derivations.
+    pub fn register(&mut self, domain: [u8; 16]) -> Result<(), FlavorError> {
+        let reserved = [DS_NF_V1, DS_SYNC_V1, DS_TG_UNIFIED_TX_V1, DS_TACHYGRAM_TO_FR_V1];
+        if reserved.contains(&&domain) {
+            return Err(FlavorError::Reserved(domain));
+        }
+        if self.contains(&domain) {
+            return Err(FlavorError::Duplicate(domain));
+        }
+        self.domains.push(domain);
+        Ok(())
+    }
+
+    pub fn contains(&self, domain: &[u8; 16]) -> bool { self.domains.contains(domain) }
+
+    /// Registered domains in registration order.
+    pub fn domains(&self) -> &[[u8; 16]] { &self.domains }
+
+    /// `derive_fixed_flavor_with_domain`, or `None` for an unknown domain.
+    pub fn derive(&self, domain: &[u8; 16], note_commitment: &[u8; 32], note_randomness: &[u8; 32]) -> Option<NullifierFlavor> {
+        self.contains(domain).then(|| derive_fixed_flavor_with_domain(domain, note_commitment, note_randomness))
+    }
+
+    /// Whether `flavor` was derived for this note under a registered domain;
+    /// returns that domain.
+    pub fn validate(&self, flavor: &NullifierFlavor, note_commitment: &[u8; 32], note_randomness: &[u8; 32]) -> Option<[u8; 16]> {
+        self.domains.iter().copied().find(|d| derive_fixed_flavor_with_domain(d, note_commitment, note_randomness) == *flavor)
+    }
 }
 
 /// Derive the deterministic on-chain nullifier from the fixed flavor and
@@ -40,13 +137,7 @@ pub fn derive_onchain_nullifier(fixed_flavor: &NullifierFlavor, note_commitment:
     let mut input = [0u8; 64];
     input[..32].copy_from_slice(&fixed_flavor.0);
     input[32..].copy_from_slice(note_commitment);
-    let hash = Blake2bParams::new()
-        .hash_length(32)
-        .personal(DS_NF_V1)
-        .hash(&input);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    OnChainNullifier(out)
+    OnChainNullifier(blake2b_32(DS_NF_V1, &input))
 }
 
 /// Derive a wallet-private off-chain sync tag using a view key (or domain
@@ -55,13 +146,7 @@ pub fn derive_offchain_sync_tag(view_key: &[u8; 32], fixed_flavor: &NullifierFla
     let mut input = [0u8; 64];
     input[..32].copy_from_slice(view_key);
     input[32..].copy_from_slice(&fixed_flavor.0);
-    let hash = Blake2bParams::new()
-        .hash_length(32)
-        .personal(DS_SYNC_V1)
-        .hash(&input);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    OffchainSyncTag(out)
+    OffchainSyncTag(blake2b_32(DS_SYNC_V1, &input))
 }
 
 /// Derive a unified tachygram digest from a bundle's on-chain primitives.
@@ -77,18 +162,118 @@ pub fn derive_unified_tachygram_tx(bundle: &TachyonBundle) -> UnifiedTachygramDi
     // value commitment and fee
     buf.extend_from_slice(&bundle.value_commitment);
     encode_u64(bundle.fee, &mut buf);
-    let hash = Blake2bParams::new().hash_length(32).personal(DS_TG_UNIFIED_TX_V1).hash(&buf);
-    let mut out = [0u8; 32];
-    out.copy_from_slice(hash.as_bytes());
-    UnifiedTachygramDigest(out)
+    UnifiedTachygramDigest(blake2b_32(DS_TG_UNIFIED_TX_V1, &buf))
 }
 
 /// Canonical hash-to-field for 32-byte tachygrams → Fr(Vesta).
 /// Uses BLAKE2b-512 with domain separation and wide reduction.
 pub fn tachygram_to_fr(tag: &[u8; 32]) -> FrVesta {
-    let hash = Blake2bParams::new().hash_length(64).personal(DS_TACHYGRAM_TO_FR_V1).hash(tag);
-    let mut wide = [0u8; 64];
-    wide.copy_from_slice(hash.as_bytes());
+    let wide = blake2b_64(DS_TACHYGRAM_TO_FR_V1, tag);
     <FrVesta as FromUniformBytes<64>>::from_uniform_bytes(&wide)
 }
 
+/// Batch size from which `tachygrams_to_fr_batch` hashes in parallel.
+#[cfg(feature = "std")]
+const TG_TO_FR_PAR_THRESHOLD: usize = 256;
+
+/// Batched `tachygram_to_fr`: one preconfigured BLAKE2b-512 parameter set is
+/// shared across tags, hashed with rayon for large batches. Output order and
+/// values match element-wise `tachygram_to_fr`. Sequential without `std`.
+pub fn tachygrams_to_fr_batch(tags: &[[u8; 32]]) -> Vec<FrVesta> {
+    let mut params = Blake2bParams::new();
+    params.hash_length(64).personal(DS_TACHYGRAM_TO_FR_V1);
+    let map = |tag: &[u8; 32]| {
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(params.hash(tag).as_bytes());
+        <FrVesta as FromUniformBytes<64>>::from_uniform_bytes(&wide)
+    };
+    #[cfg(feature = "std")]
+    if tags.len() >= TG_TO_FR_PAR_THRESHOLD {
+        return tags.par_iter().map(map).collect();
+    }
+    tags.iter().map(map).collect()
+}
+
+#[cfg(feature = "std")]
+impl Tachystamp {
+    /// The stamp's tachygrams as block-polynomial roots, mapped like the
+    /// publisher does: `tachygram_to_fr`, sorted by `poly::canonical_root_cmp`
+    /// and deduped.
+    pub fn root_elements(&self) -> Vec<FrVesta> {
+        let grams: Vec<[u8; 32]> = self.tachygrams.iter().map(|t| t.0).collect();
+        let mut roots = tachygrams_to_fr_batch(&grams);
+        roots.sort_by(accum::poly::canonical_root_cmp);
+        roots.dedup();
+        roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ff::PrimeField;
+
+    #[test]
+    fn derivations_golden() {
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        let fl = derive_fixed_flavor(&a, &b);
+        assert_eq!(hex::encode(fl.0), "264c5fc971a6afee0c982b9705c9d200b352e40e1c0eaf3049d9c8751576200c");
+        assert_eq!(hex::encode(derive_onchain_nullifier(&fl, &a).0), "d6ad0c434f03dc673916d2ce2004874712b152fd58a8a17f937d138eb17d5878");
+        assert_eq!(hex::encode(derive_offchain_sync_tag(&b, &fl).0), "e525925070148dba8a7da3340162e573cebfe5726d4e25b40b3259f58e10ec95");
+        let mut bundle = TachyonBundle::new();
+        bundle.nullifiers.push(a);
+        bundle.commitments.push(b);
+        bundle.fee = 5;
+        assert_eq!(hex::encode(derive_unified_tachygram_tx(&bundle).0), "77270aaf2c72eca67909b71549971575ecfe1389d939d1976ccbe7eef65dd09f");
+        assert_eq!(hex::encode(tachygram_to_fr(&a).to_repr()), "b689b64c0f94fdf5d14095e5905178341691888a7220eab066d1609b4d413709");
+    }
+
+    #[test]
+    fn flavor_domains_separate_nullifiers() {
+        const V2: [u8; 16] = *b"tachyon.flavor.2";
+        let (cm, rseed) = ([5u8; 32], [6u8; 32]);
+        let mut registry = FlavorRegistry::new();
+        assert!(registry.derive(&V2, &cm, &rseed).is_none());
+        registry.register(V2).unwrap();
+        assert!(registry.register(V2).is_err());
+        assert!(registry.register(*DS_NF_V1).is_err());
+        assert_eq!(registry.domains(), &[*FLAVOR_DOMAIN_V1, V2]);
+
+        let v1 = registry.derive(FLAVOR_DOMAIN_V1, &cm, &rseed).unwrap();
+        let v2 = registry.derive(&V2, &cm, &rseed).unwrap();
+        assert_eq!(v1, derive_fixed_flavor(&cm, &rseed));
+        assert_ne!(derive_onchain_nullifier(&v1, &cm), derive_onchain_nullifier(&v2, &cm));
+        assert_eq!(registry.validate(&v2, &cm, &rseed), Some(V2));
+        assert_eq!(registry.validate(&v2, &cm, &[7u8; 32]), None);
+    }
+
+    #[test]
+    fn tachygram_batch_matches_elementwise() {
+        let tags: Vec<[u8; 32]> = (0u32..4096)
+            .map(|i| blake2b_32(b"tg.batch.test\0\0\0", &i.to_le_bytes()))
+            .collect();
+        let batch = tachygrams_to_fr_batch(&tags);
+        let single: Vec<FrVesta> = tags.iter().map(tachygram_to_fr).collect();
+        assert_eq!(batch, single);
+        assert_eq!(tachygrams_to_fr_batch(&tags[..3]), single[..3].to_vec());
+    }
+
+    #[test]
+    fn stamp_root_elements_sorted_and_deduped() {
+        let grams = [[9u8; 32], [1u8; 32], [9u8; 32], [4u8; 32]];
+        let stamp = Tachystamp {
+            range_anchor: RangeAnchor { min_pos: 0, max_pos: 1, root_min: [0u8; 32], root_max: [0u8; 32], frontier_attestation: vec![] },
+            tachygrams: grams.iter().map(|g| Tachygram(*g)).collect(),
+            auth: RedPallasSig([0u8; REDPALLAS_SIG_LEN]),
+            pcd_proof: PcdProof(vec![]),
+        };
+        let mut manual: Vec<FrVesta> = grams.iter().map(tachygram_to_fr).collect();
+        manual.sort_by(accum::poly::canonical_root_cmp);
+        manual.dedup();
+        assert_eq!(manual.len(), 3);
+        assert_eq!(stamp.root_elements(), manual);
+
+        let decoded = Tachystamp::from_canonical_bytes(&stamp.to_canonical_bytes()).unwrap();
+        assert_eq!(decoded.root_elements(), manual);
+    }
+}
@@ -1,14 +1,70 @@
 //! Canonical encoding and decoding for Tachyon types.
 
-use anyhow::{anyhow, Result};
+use alloc::vec::Vec;
+use core::fmt;
+
 use blake2b_simd::Params as Blake2bParams;
+use ff::PrimeField;
+use pasta_curves::{pallas, vesta::Scalar as FrVesta};
 
+use crate::endian;
 use crate::types::*;
 
+/// Why a canonical decoding failed. A plain enum rather than `anyhow::Error`
+/// so decoding works in `no_std` builds; it converts into `anyhow::Error`
+/// through `core::error::Error` wherever `std` callers use `?`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DecodeError {
+    UnexpectedEof,
+    UnsupportedVersion(u8),
+    /// Input left over after a complete value; names the type decoded.
+    TrailingBytes(&'static str),
+    LengthExceedsLimit { len: usize, max: usize },
+    TooManyTachygrams { len: usize, max: usize },
+    TooManyTxids { len: usize, max: usize },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof => write!(f, "unexpected EOF"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported encoding version: {}", v),
+            DecodeError::TrailingBytes(ty) => write!(f, "trailing bytes in {}", ty),
+            DecodeError::LengthExceedsLimit { len, max } => write!(f, "length {} exceeds limit {}", len, max),
+            DecodeError::TooManyTachygrams { len, max } => write!(f, "{} tachygrams exceed limit {}", len, max),
+            DecodeError::TooManyTxids { len, max } => write!(f, "{} txids exceed limit {}", len, max),
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+type Result<T> = core::result::Result<T, DecodeError>;
+
 // ——— Canonical encoding version ———
 
 const ENC_V1: u8 = 1; // version tag for canonical encodings
 
+/// Caps on length-prefixed fields, checked against the prefix before anything
+/// is allocated or copied, so a hostile prefix cannot make a validator reserve
+/// gigabytes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodeLimits {
+    /// Largest `pcd_proof` / aggregate `proof`, in bytes.
+    pub max_proof_bytes: usize,
+    /// Most tachygrams in one stamp.
+    pub max_tachygrams: usize,
+    /// Most txids in one aggregate.
+    pub max_txids: usize,
+}
+
+impl Default for DecodeLimits {
+    /// Conservative limits used by `from_canonical_bytes`.
+    fn default() -> Self {
+        Self { max_proof_bytes: 1 << 20, max_tachygrams: MAX_TACHYGRAMS, max_txids: 1 << 16 }
+    }
+}
+
 impl Tachystamp {
     pub fn to_canonical_bytes(&self) -> Vec<u8> {
         let mut out = Vec::with_capacity(1 + 2 + 2 + 2);
@@ -20,17 +76,22 @@ impl Tachystamp {
         out
     }
 
-    pub fn from_canonical_bytes(mut data: &[u8]) -> Result<Self> {
+    /// Decode under the default `DecodeLimits`.
+    pub fn from_canonical_bytes(data: &[u8]) -> Result<Self> {
+        Self::from_canonical_bytes_limited(data, &DecodeLimits::default())
+    }
+
+    pub fn from_canonical_bytes_limited(mut data: &[u8], limits: &DecodeLimits) -> Result<Self> {
         let ver = read_u8(&mut data)?;
-        if ver != ENC_V1 { return Err(anyhow!("unsupported encoding version: {}", ver)); }
+        if ver != ENC_V1 { return Err(DecodeError::UnsupportedVersion(ver)); }
         let range_anchor = decode_range_anchor(&mut data)?;
-        let tachygrams = decode_vec_tachygram(&mut data)?;
+        let tachygrams = decode_vec_tachygram(&mut data, limits.max_tachygrams)?;
         let auth = {
             let bytes = read_fixed::<REDPALLAS_SIG_LEN>(&mut data)?;
             RedPallasSig(bytes)
         };
-        let pcd_proof = PcdProof(read_vec(&mut data)?);
-        if !data.is_empty() { return Err(anyhow!("trailing bytes in Tachystamp")); }
+        let pcd_proof = PcdProof(read_vec_limited(&mut data, limits.max_proof_bytes)?);
+        if !data.is_empty() { return Err(DecodeError::TrailingBytes("Tachystamp")); }
         Ok(Tachystamp { range_anchor, tachygrams, auth, pcd_proof })
     }
 
@@ -48,40 +109,110 @@ impl Tachystamp {
     }
 }
 
+/// Size summary of an aggregate for bandwidth budgeting.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct AggregateStats {
+    pub size_bytes: usize,
+    pub txid_count: usize,
+    /// `size_bytes / txid_count`; 0.0 for an empty aggregate.
+    pub bytes_per_txid: f64,
+}
+
 impl AggregateProof {
+    /// Canonical encoded length: version || u32 count || txids || u32 len || proof.
+    fn canonical_len(&self) -> usize {
+        1 + 4 + self.txids.len() * TXID_LEN + 4 + self.proof.len()
+    }
+
+    /// Length of `to_canonical_bytes()`, computed without encoding.
+    pub fn size_bytes(&self) -> usize { self.canonical_len() }
+
+    pub fn txid_count(&self) -> usize { self.txids.len() }
+
+    pub fn stats(&self) -> AggregateStats {
+        let size_bytes = self.size_bytes();
+        let txid_count = self.txid_count();
+        let bytes_per_txid = if txid_count == 0 { 0.0 } else { size_bytes as f64 / txid_count as f64 };
+        AggregateStats { size_bytes, txid_count, bytes_per_txid }
+    }
+
     pub fn to_canonical_bytes(&self) -> Vec<u8> {
-        let mut out = Vec::with_capacity(1 + 4 + self.txids.len() * TXID_LEN);
+        let mut out = Vec::with_capacity(self.canonical_len());
         out.push(ENC_V1);
         encode_vec_txid(&self.txids, &mut out);
         encode_bytes(&self.proof, &mut out);
         out
     }
 
-    pub fn from_canonical_bytes(mut data: &[u8]) -> Result<Self> {
+    /// Decode under the default `DecodeLimits`.
+    pub fn from_canonical_bytes(data: &[u8]) -> Result<Self> {
+        Self::from_canonical_bytes_limited(data, &DecodeLimits::default())
+    }
+
+    pub fn from_canonical_bytes_limited(mut data: &[u8], limits: &DecodeLimits) -> Result<Self> {
         let ver = read_u8(&mut data)?;
-        if ver != ENC_V1 { return Err(anyhow!("unsupported encoding version: {}", ver)); }
-        let txids = decode_vec_txid(&mut data)?;
-        let proof = read_vec(&mut data)?;
-        if !data.is_empty() { return Err(anyhow!("trailing bytes in AggregateProof")); }
+        if ver != ENC_V1 { return Err(DecodeError::UnsupportedVersion(ver)); }
+        let txids = decode_vec_txid(&mut data, limits.max_txids)?;
+        let proof = read_vec_limited(&mut data, limits.max_proof_bytes)?;
+        if !data.is_empty() { return Err(DecodeError::TrailingBytes("AggregateProof")); }
         Ok(AggregateProof { txids, proof })
     }
+
+    /// Copy with the proof tampered by `corrupt_proof_bytes`.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn with_corrupt_proof(&self) -> Self {
+        let mut out = self.clone();
+        corrupt_proof_bytes(&mut out.proof);
+        out
+    }
+
+    /// Canonical encoding followed by `extra` trailing bytes.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn to_canonical_bytes_with_trailing(&self, extra: &[u8]) -> Vec<u8> {
+        let mut out = self.to_canonical_bytes();
+        out.extend_from_slice(extra);
+        out
+    }
+}
+
+/// Flip the first byte of `proof`, or append one byte if it is empty. Shared
+/// by the `with_corrupt_proof` helpers here and in `pcd`.
+#[cfg(any(test, feature = "testing"))]
+pub fn corrupt_proof_bytes(proof: &mut Vec<u8>) {
+    match proof.first_mut() {
+        Some(b) => *b ^= 1,
+        None => proof.push(1),
+    }
+}
+
+// ——— Canonical field reprs ———
+
+/// Whether `bytes` is the canonical little-endian repr of an Fr(Vesta)
+/// element (value below the modulus).
+pub fn is_canonical_fr_vesta(bytes: &[u8; 32]) -> bool {
+    bool::from(FrVesta::from_repr(*bytes).is_some())
+}
+
+/// Whether `bytes` is the canonical little-endian repr of a Pallas scalar.
+pub fn is_canonical_pallas_scalar(bytes: &[u8; 32]) -> bool {
+    bool::from(pallas::Scalar::from_repr(*bytes).is_some())
 }
 
 // ——— Encoding primitives ———
 
 pub fn encode_u8(v: u8, out: &mut Vec<u8>) { out.push(v); }
-pub fn encode_u32(v: u32, out: &mut Vec<u8>) { out.extend_from_slice(&v.to_be_bytes()); }
-pub fn encode_u64(v: u64, out: &mut Vec<u8>) { out.extend_from_slice(&v.to_be_bytes()); }
+pub fn encode_u32(v: u32, out: &mut Vec<u8>) { endian::put_u32_be(out, v); }
+pub fn encode_u64(v: u64, out: &mut Vec<u8>) { endian::put_u64_be(out, v); }
 
 pub fn read_u8(data: &mut &[u8]) -> Result<u8> {
-    if data.len() < 1 { return Err(anyhow!("unexpected EOF")); }
+    if data.is_empty() { return Err(DecodeError::UnexpectedEof); }
     let v = data[0];
     *data = &data[1..];
     Ok(v)
 }
 
 pub fn read_u32(data: &mut &[u8]) -> Result<u32> {
-    if data.len() < 4 { return Err(anyhow!("unexpected EOF")); }
+    if data.len() < 4 { return Err(DecodeError::UnexpectedEof); }
     let mut buf = [0u8; 4];
     buf.copy_from_slice(&data[..4]);
     *data = &data[4..];
@@ -89,7 +220,7 @@ pub fn read_u32(data: &mut &[u8]) -> Result<u32> {
 }
 
 pub fn read_u64(data: &mut &[u8]) -> Result<u64> {
-    if data.len() < 8 { return Err(anyhow!("unexpected EOF")); }
+    if data.len() < 8 { return Err(DecodeError::UnexpectedEof); }
     let mut buf = [0u8; 8];
     buf.copy_from_slice(&data[..8]);
     *data = &data[8..];
@@ -103,14 +234,24 @@ pub fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
 
 pub fn read_vec(data: &mut &[u8]) -> Result<Vec<u8>> {
     let len = read_u32(data)? as usize;
-    if data.len() < len { return Err(anyhow!("unexpected EOF")); }
+    if data.len() < len { return Err(DecodeError::UnexpectedEof); }
+    let v = data[..len].to_vec();
+    *data = &data[len..];
+    Ok(v)
+}
+
+/// `read_vec` that rejects a length prefix above `max` before copying.
+pub fn read_vec_limited(data: &mut &[u8], max: usize) -> Result<Vec<u8>> {
+    let len = read_u32(data)? as usize;
+    if len > max { return Err(DecodeError::LengthExceedsLimit { len, max }); }
+    if data.len() < len { return Err(DecodeError::UnexpectedEof); }
     let v = data[..len].to_vec();
     *data = &data[len..];
     Ok(v)
 }
 
 pub fn read_fixed<const N: usize>(data: &mut &[u8]) -> Result<[u8; N]> {
-    if data.len() < N { return Err(anyhow!("unexpected EOF")); }
+    if data.len() < N { return Err(DecodeError::UnexpectedEof); }
     let mut out = [0u8; N];
     out.copy_from_slice(&data[..N]);
     *data = &data[N..];
@@ -141,8 +282,9 @@ fn encode_vec_tachygram(v: &[Tachygram], out: &mut Vec<u8>) {
     for t in v { out.extend_from_slice(&t.0); }
 }
 
-fn decode_vec_tachygram(data: &mut &[u8]) -> Result<Vec<Tachygram>> {
+fn decode_vec_tachygram(data: &mut &[u8], max: usize) -> Result<Vec<Tachygram>> {
     let len = read_u32(data)? as usize;
+    if len > max { return Err(DecodeError::TooManyTachygrams { len, max }); }
     let mut v = Vec::with_capacity(len);
     for _ in 0..len {
         v.push(Tachygram(read_fixed::<TACHYGRAM_LEN>(data)?));
@@ -155,10 +297,182 @@ fn encode_vec_txid(v: &[[u8; TXID_LEN]], out: &mut Vec<u8>) {
     for id in v { out.extend_from_slice(id); }
 }
 
-fn decode_vec_txid(data: &mut &[u8]) -> Result<Vec<[u8; TXID_LEN]>> {
+fn decode_vec_txid(data: &mut &[u8], max: usize) -> Result<Vec<[u8; TXID_LEN]>> {
     let len = read_u32(data)? as usize;
+    if len > max { return Err(DecodeError::TooManyTxids { len, max }); }
     let mut v = Vec::with_capacity(len);
     for _ in 0..len { v.push(read_fixed::<TXID_LEN>(data)?); }
     Ok(v)
 }
 
+/// Streaming form of the txid list encoding (u32 `count` then each txid), for
+/// writing large aggregates straight to a socket or file. Errors if `txids`
+/// yields more or fewer than `count` items; the writer may then hold a partial
+/// encoding.
+#[cfg(feature = "std")]
+pub fn encode_txids_into<W: std::io::Write>(txids: impl Iterator<Item = [u8; TXID_LEN]>, count: u32, w: &mut W) -> anyhow::Result<()> {
+    use anyhow::anyhow;
+    let mut head = Vec::with_capacity(4);
+    endian::put_u32_be(&mut head, count);
+    w.write_all(&head)?;
+    let mut txids = txids;
+    for i in 0..count {
+        let id = txids.next().ok_or_else(|| anyhow!("txid count {} but iterator ended after {}", count, i))?;
+        w.write_all(&id)?;
+    }
+    if txids.next().is_some() { return Err(anyhow!("txid iterator yields more than count {}", count)); }
+    Ok(())
+}
+
+/// Read a txid list written by `encode_txids_into` (or `encode_vec_txid`).
+#[cfg(feature = "std")]
+pub fn decode_txids_from<R: std::io::Read>(r: &mut R) -> anyhow::Result<Vec<[u8; TXID_LEN]>> {
+    let mut len = [0u8; 4];
+    r.read_exact(&mut len)?;
+    let len = u32::from_be_bytes(len) as usize;
+    // Do not trust the count for preallocation; a short stream errors below.
+    let mut v = Vec::with_capacity(len.min(1 << 16));
+    for _ in 0..len {
+        let mut id = [0u8; TXID_LEN];
+        r.read_exact(&mut id)?;
+        v.push(id);
+    }
+    Ok(v)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregate_tamper_helpers_are_rejected() {
+        let agg = AggregateProof { txids: vec![[1u8; TXID_LEN], [2u8; TXID_LEN]], proof: vec![9, 8, 7] };
+        let bytes = agg.to_canonical_bytes();
+        assert_eq!(AggregateProof::from_canonical_bytes(&bytes).unwrap(), agg);
+        assert!(AggregateProof::from_canonical_bytes(&agg.to_canonical_bytes_with_trailing(&[0])).is_err());
+
+        let corrupt = agg.with_corrupt_proof();
+        assert_ne!(corrupt.proof, agg.proof);
+        assert_eq!(corrupt.txids, agg.txids);
+    }
+
+    #[test]
+    fn aggregate_size_matches_encoding() {
+        let proofs = [
+            AggregateProof { txids: vec![], proof: vec![] },
+            AggregateProof { txids: vec![[3u8; TXID_LEN]], proof: vec![1; 32] },
+            AggregateProof { txids: vec![[4u8; TXID_LEN]; 5], proof: vec![2; 700] },
+        ];
+        for agg in &proofs {
+            assert_eq!(agg.size_bytes(), agg.to_canonical_bytes().len());
+        }
+        let stats = proofs[2].stats();
+        assert_eq!(stats.txid_count, 5);
+        assert_eq!(stats.bytes_per_txid, stats.size_bytes as f64 / 5.0);
+        assert_eq!(proofs[0].stats().bytes_per_txid, 0.0);
+    }
+
+    #[test]
+    fn tachystamp_byte_layout_is_pinned() {
+        let stamp = Tachystamp {
+            range_anchor: RangeAnchor {
+                min_pos: 0x0102,
+                max_pos: 0x0a0b_0c0d,
+                root_min: [0x11; ROOT_LEN],
+                root_max: [0x22; ROOT_LEN],
+                frontier_attestation: vec![0xf0, 0xf1],
+            },
+            tachygrams: vec![Tachygram([0x33; 32])],
+            auth: RedPallasSig([0x44; REDPALLAS_SIG_LEN]),
+            pcd_proof: PcdProof(vec![0x55, 0x56, 0x57]),
+        };
+        let mut expected = vec![ENC_V1, ENC_V1];
+        expected.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0x01, 0x02]); // min_pos, u64 BE
+        expected.extend_from_slice(&[0, 0, 0, 0, 0x0a, 0x0b, 0x0c, 0x0d]); // max_pos, u64 BE
+        expected.extend_from_slice(&[0x11; 32]);
+        expected.extend_from_slice(&[0x22; 32]);
+        expected.extend_from_slice(&[0, 0, 0, 2, 0xf0, 0xf1]); // u32 BE length prefix
+        expected.extend_from_slice(&[0, 0, 0, 1]);
+        expected.extend_from_slice(&[0x33; 32]);
+        expected.extend_from_slice(&[0x44; 64]);
+        expected.extend_from_slice(&[0, 0, 0, 3, 0x55, 0x56, 0x57]);
+        assert_eq!(stamp.to_canonical_bytes(), expected);
+        assert_eq!(Tachystamp::from_canonical_bytes(&expected).unwrap(), stamp);
+    }
+
+    #[test]
+    fn canonical_field_reprs() {
+        assert!(!is_canonical_fr_vesta(&[0xFF; 32]));
+        assert!(!is_canonical_pallas_scalar(&[0xFF; 32]));
+        let minus_one = (-FrVesta::from(1u64)).to_repr();
+        assert!(is_canonical_fr_vesta(&minus_one));
+        assert!(is_canonical_pallas_scalar(&(-pallas::Scalar::from(1u64)).to_repr()));
+        assert!(is_canonical_fr_vesta(&[0u8; 32]));
+    }
+
+    #[test]
+    fn streaming_txids_match_slice_encoding() {
+        let txids: Vec<[u8; TXID_LEN]> = (0..10_000u32)
+            .map(|i| {
+                let mut id = [0u8; TXID_LEN];
+                id[..4].copy_from_slice(&i.to_be_bytes());
+                id[31] = (i % 251) as u8;
+                id
+            })
+            .collect();
+        let mut expected = Vec::new();
+        encode_vec_txid(&txids, &mut expected);
+        let mut streamed = Vec::new();
+        encode_txids_into(txids.iter().copied(), txids.len() as u32, &mut streamed).unwrap();
+        assert_eq!(streamed, expected);
+        assert_eq!(decode_txids_from(&mut streamed.as_slice()).unwrap(), txids);
+    }
+
+    #[test]
+    fn oversized_length_prefixes_rejected_by_limits() {
+        // A proof claiming 2 GiB with no body behind it.
+        let mut huge = vec![ENC_V1];
+        encode_u32(0, &mut huge);
+        encode_u32(1 << 31, &mut huge);
+        let err = AggregateProof::from_canonical_bytes(&huge).unwrap_err();
+        assert!(err.to_string().contains("exceeds limit"), "{err}");
+
+        let mut many_txids = vec![ENC_V1];
+        encode_u32(u32::MAX, &mut many_txids);
+        assert!(AggregateProof::from_canonical_bytes(&many_txids).is_err());
+
+        let agg = AggregateProof { txids: vec![[5u8; TXID_LEN]; 3], proof: vec![7; 64] };
+        let bytes = agg.to_canonical_bytes();
+        let tight = DecodeLimits { max_proof_bytes: 63, ..DecodeLimits::default() };
+        assert!(AggregateProof::from_canonical_bytes_limited(&bytes, &tight).is_err());
+        let few = DecodeLimits { max_txids: 2, ..DecodeLimits::default() };
+        assert!(AggregateProof::from_canonical_bytes_limited(&bytes, &few).is_err());
+        assert_eq!(AggregateProof::from_canonical_bytes(&bytes).unwrap(), agg);
+
+        let stamp = Tachystamp {
+            range_anchor: RangeAnchor { min_pos: 0, max_pos: 1, root_min: [0u8; 32], root_max: [1u8; 32], frontier_attestation: vec![] },
+            tachygrams: vec![Tachygram([2u8; 32]); 4],
+            auth: RedPallasSig([3u8; REDPALLAS_SIG_LEN]),
+            pcd_proof: PcdProof(vec![4; 128]),
+        };
+        let bytes = stamp.to_canonical_bytes();
+        assert_eq!(Tachystamp::from_canonical_bytes(&bytes).unwrap(), stamp);
+        let tight = DecodeLimits { max_tachygrams: 3, ..DecodeLimits::default() };
+        assert!(Tachystamp::from_canonical_bytes_limited(&bytes, &tight).is_err());
+        let tight = DecodeLimits { max_proof_bytes: 127, ..DecodeLimits::default() };
+        assert!(Tachystamp::from_canonical_bytes_limited(&bytes, &tight).is_err());
+    }
+
+    #[test]
+    fn streaming_txids_count_mismatch_errors() {
+        let txids = [[1u8; TXID_LEN], [2u8; TXID_LEN], [3u8; TXID_LEN]];
+        assert!(encode_txids_into(txids.iter().copied(), 4, &mut Vec::new()).is_err());
+        assert!(encode_txids_into(txids.iter().copied(), 2, &mut Vec::new()).is_err());
+
+        let mut buf = Vec::new();
+        encode_vec_txid(&txids, &mut buf);
+        buf.truncate(buf.len() - 1);
+        assert!(decode_txids_from(&mut buf.as_slice()).is_err());
+    }
+}
@@ -0,0 +1,35 @@
+//! Byte-order convention for Tachyon encodings.
+//!
+//! - Integers in canonical wire encodings (`encode`: versions aside, every
+//!   length prefix, count and position) are big-endian.
+//! - Field elements and curve points use their `to_repr()` / compressed form,
+//!   which is little-endian.
+//! - `accum::ser::serialize_batch` prefixes its item count as a little-endian
+//!   u64. It predates this module and is hashed into window roots, so it stays
+//!   as is; accum cannot depend on this crate, and a test pins its layout.
+//! - Counters hashed into PRF inputs (e.g. the signing nonce stream) are
+//!   little-endian; they never appear on the wire.
+//!
+//! New encodings should write integers through these helpers so the choice
+//! is explicit at the call site.
+
+use alloc::vec::Vec;
+
+/// Append `v` as 4 big-endian bytes.
+pub fn put_u32_be(out: &mut Vec<u8>, v: u32) { out.extend_from_slice(&v.to_be_bytes()); }
+
+/// Append `v` as 8 big-endian bytes.
+pub fn put_u64_be(out: &mut Vec<u8>, v: u64) { out.extend_from_slice(&v.to_be_bytes()); }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn helpers_write_expected_order() {
+        let mut out = Vec::new();
+        put_u32_be(&mut out, 0x0102_0304);
+        put_u64_be(&mut out, 0x0102_0304_0506_0708);
+        assert_eq!(out, [1, 2, 3, 4, 1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
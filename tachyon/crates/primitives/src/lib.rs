@@ -1,10 +1,30 @@
 //! Tachyon transaction types: tachyactions, tachygrams, tachystamps.
+//!
+//! With the default `std` feature off (`--no-default-features --features
+//! no_std`) only the alloc-only core is built: `types`, `encode`, `digest`
+//! and `endian`. The builder, spend authorization, hex formatting, rayon
+//! batching and `std::io` streaming need `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod types;
 pub mod encode;
 pub mod digest;
+#[cfg(feature = "std")]
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod auth;
+pub mod endian;
+#[cfg(all(feature = "std", any(test, feature = "testvectors")))]
+pub mod testvectors;
 
 // Re-export all public items from modules for convenience
 pub use types::*;
 pub use encode::*;
 pub use digest::*;
+#[cfg(feature = "std")]
+pub use builder::*;
+#[cfg(feature = "std")]
+pub use auth::*;
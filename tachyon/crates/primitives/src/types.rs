@@ -1,5 +1,8 @@
 //! Core transaction types for Tachyon.
 
+use alloc::{vec, vec::Vec};
+use core::fmt;
+
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
@@ -14,6 +17,46 @@ pub enum Tachyaction {
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct TachystampBytes(pub Vec<u8>);
 
+const DS_STAMP_CHECKSUM_V1: &[u8; 16] = b"tachyon.stampck\0"; // 15 + 1 = 16
+/// Length of the checksum appended by `TachystampBytes::with_checksum`.
+pub const STAMP_CHECKSUM_LEN: usize = 4;
+
+fn stamp_checksum(payload: &[u8]) -> [u8; STAMP_CHECKSUM_LEN] {
+    let h = blake2b_simd::Params::new()
+        .hash_length(STAMP_CHECKSUM_LEN)
+        .personal(DS_STAMP_CHECKSUM_V1)
+        .hash(payload);
+    let mut out = [0u8; STAMP_CHECKSUM_LEN];
+    out.copy_from_slice(h.as_bytes());
+    out
+}
+
+impl TachystampBytes {
+    /// `payload` followed by a 4-byte BLAKE2b checksum of it.
+    ///
+    /// Transport-layer integrity only: it catches truncated or corrupted blobs
+    /// before `Tachystamp::from_canonical_bytes`, but anyone can recompute it,
+    /// so it is not authentication and consensus never relies on it.
+    pub fn with_checksum(payload: &[u8]) -> Self {
+        let mut out = Vec::with_capacity(payload.len() + STAMP_CHECKSUM_LEN);
+        out.extend_from_slice(payload);
+        out.extend_from_slice(&stamp_checksum(payload));
+        Self(out)
+    }
+
+    /// Whether the trailing checksum matches the bytes before it.
+    pub fn verify_checksum(&self) -> bool {
+        self.payload().is_some()
+    }
+
+    /// The bytes before the checksum, if it verifies.
+    pub fn payload(&self) -> Option<&[u8]> {
+        let split = self.0.len().checked_sub(STAMP_CHECKSUM_LEN)?;
+        let (payload, checksum) = self.0.split_at(split);
+        (stamp_checksum(payload) == checksum).then_some(payload)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct AnchorRange { pub start: [u8; 32], pub end: [u8; 32] }
 
@@ -30,6 +73,34 @@ pub struct TachyonBundle {
     pub fee: u64,
 }
 
+/// Why `TachyonBundle::check_consistency` rejected a bundle.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BundleError {
+    SpendOverflow,
+    OutputOverflow,
+    NullifierMismatch,
+    CommitmentMismatch,
+    FeeOverflow,
+    ValueImbalance { value_in: u64, value_out: u64, fee: u64 },
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::SpendOverflow => write!(f, "spend value overflow"),
+            BundleError::OutputOverflow => write!(f, "output value overflow"),
+            BundleError::NullifierMismatch => write!(f, "nullifiers do not match spend actions"),
+            BundleError::CommitmentMismatch => write!(f, "commitments do not match output actions"),
+            BundleError::FeeOverflow => write!(f, "fee overflow"),
+            BundleError::ValueImbalance { value_in, value_out, fee } => {
+                write!(f, "value imbalance: spends {} != outputs {} + fee {}", value_in, value_out, fee)
+            }
+        }
+    }
+}
+
+impl core::error::Error for BundleError {}
+
 impl TachyonBundle {
     pub fn new() -> Self {
         Self {
@@ -43,6 +114,58 @@ impl TachyonBundle {
             fee: 0,
         }
     }
+
+    /// Check that the explicit on-chain primitives agree with `actions`:
+    /// spend `nf`s equal `nullifiers` and output `cm`s equal `commitments` (as
+    /// multisets), and spend values equal output values plus `fee`.
+    pub fn check_consistency(&self) -> Result<(), BundleError> {
+        let mut nfs = Vec::new();
+        let mut cms = Vec::new();
+        let mut value_in: u64 = 0;
+        let mut value_out: u64 = 0;
+        for action in &self.actions {
+            match action {
+                Tachyaction::Spend { nf, value } => {
+                    nfs.push(*nf);
+                    value_in = value_in.checked_add(*value).ok_or(BundleError::SpendOverflow)?;
+                }
+                Tachyaction::Output { cm, value } => {
+                    cms.push(*cm);
+                    value_out = value_out.checked_add(*value).ok_or(BundleError::OutputOverflow)?;
+                }
+            }
+        }
+        let mut nullifiers = self.nullifiers.clone();
+        nfs.sort();
+        nullifiers.sort();
+        if nfs != nullifiers { return Err(BundleError::NullifierMismatch); }
+        let mut commitments = self.commitments.clone();
+        cms.sort();
+        commitments.sort();
+        if cms != commitments { return Err(BundleError::CommitmentMismatch); }
+        let required = value_out.checked_add(self.fee).ok_or(BundleError::FeeOverflow)?;
+        if value_in != required {
+            return Err(BundleError::ValueImbalance { value_in, value_out, fee: self.fee });
+        }
+        Ok(())
+    }
+
+    /// Put the bundle in canonical form: spends before outputs, each group
+    /// sorted by `nf`/`cm` bytes then value, identical actions deduped, and
+    /// `nullifiers`/`commitments` sorted and deduped to match. Consensus
+    /// requires canonical bundles, so encodings and `derive_unified_tachygram_tx`
+    /// do not depend on how a wallet happened to order its actions.
+    pub fn canonicalize(&mut self) {
+        self.actions.sort_by_key(|action| match action {
+            Tachyaction::Spend { nf, value } => (0u8, *nf, *value),
+            Tachyaction::Output { cm, value } => (1u8, *cm, *value),
+        });
+        self.actions.dedup();
+        self.nullifiers.sort();
+        self.nullifiers.dedup();
+        self.commitments.sort();
+        self.commitments.dedup();
+    }
 }
 
 impl Default for TachyonBundle {
@@ -59,6 +182,12 @@ pub const TACHYGRAM_LEN: usize = 32;
 pub const ROOT_LEN: usize = 32;
 pub const REDPALLAS_SIG_LEN: usize = 64;
 pub const TXID_LEN: usize = 32;
+/// Maximum tachygrams carried by one stamp: the per-block degree bound
+/// `accum::params::DEGREE_N`. Spelled out because `accum` needs `std`; the
+/// `std` build checks the two agree at compile time.
+pub const MAX_TACHYGRAMS: usize = 4096;
+#[cfg(feature = "std")]
+const _: () = assert!(MAX_TACHYGRAMS == accum::params::DEGREE_N);
 
 // ————————————————————————————————————————————————————————————————————————————
 // Nullifier flavoring (fixed at output creation) and deterministic derivations
@@ -76,6 +205,9 @@ pub struct OnChainNullifier(pub [u8; 32]);
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct OffchainSyncTag(pub [u8; 32]);
 
+#[cfg(feature = "std")]
+accum::impl_hex32!(NullifierFlavor, OnChainNullifier, OffchainSyncTag);
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct RedPallasSig(pub [u8; REDPALLAS_SIG_LEN]);
@@ -116,8 +248,8 @@ impl<'de> serde::Deserialize<'de> for RedPallasSig {
                 A: serde::de::SeqAccess<'de>,
             {
                 let mut out = [0u8; REDPALLAS_SIG_LEN];
-                for i in 0..REDPALLAS_SIG_LEN {
-                    out[i] = match seq.next_element::<u8>()? {
+                for (i, byte) in out.iter_mut().enumerate() {
+                    *byte = match seq.next_element::<u8>()? {
                         Some(b) => b,
                         None => return Err(serde::de::Error::invalid_length(i, &self)),
                     };
@@ -145,6 +277,18 @@ pub struct RangeAnchor {
     pub frontier_attestation: Vec<u8>,
 }
 
+impl RangeAnchor {
+    /// `max_pos - min_pos`, or `None` for an inverted range (never wraps).
+    pub fn span(&self) -> Option<u64> {
+        self.max_pos.checked_sub(self.min_pos)
+    }
+
+    /// Whether `pos` lies in `[min_pos, max_pos]`; always false for an inverted range.
+    pub fn contains(&self, pos: u64) -> bool {
+        self.span().is_some() && self.min_pos <= pos && pos <= self.max_pos
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
 #[serde(transparent)]
 pub struct PcdProof(pub Vec<u8>);
@@ -170,3 +314,117 @@ pub struct AggregateProof {
 #[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug, Default)]
 pub struct UnifiedTachygramDigest(pub [u8; 32]);
 
+#[cfg(feature = "std")]
+accum::impl_hex32!(Tachygram, UnifiedTachygramDigest);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle() -> TachyonBundle {
+        let mut b = TachyonBundle::new();
+        b.actions.push(Tachyaction::Spend { nf: [1u8; 32], value: 10 });
+        b.actions.push(Tachyaction::Output { cm: [2u8; 32], value: 7 });
+        b.nullifiers.push([1u8; 32]);
+        b.commitments.push([2u8; 32]);
+        b.fee = 3;
+        b
+    }
+
+    #[test]
+    fn consistent_bundle_passes() {
+        bundle().check_consistency().unwrap();
+    }
+
+    #[test]
+    fn missing_spend_nullifier_rejected() {
+        let mut b = bundle();
+        b.nullifiers.clear();
+        assert!(b.check_consistency().is_err());
+    }
+
+    #[test]
+    fn value_imbalance_rejected() {
+        let mut b = bundle();
+        b.fee = 4;
+        assert!(b.check_consistency().is_err());
+    }
+
+    #[test]
+    fn stamp_checksum_detects_corruption() {
+        let payload: Vec<u8> = (0u8..100).collect();
+        let blob = TachystampBytes::with_checksum(&payload);
+        assert_eq!(blob.0.len(), payload.len() + STAMP_CHECKSUM_LEN);
+        assert!(blob.verify_checksum());
+        assert_eq!(blob.payload(), Some(&payload[..]));
+        assert!(TachystampBytes::with_checksum(&[]).verify_checksum());
+
+        for bit in [0, 7, 8 * 50 + 3, 8 * (blob.0.len() - 1)] {
+            let mut flipped = blob.clone();
+            flipped.0[bit / 8] ^= 1 << (bit % 8);
+            assert!(!flipped.verify_checksum(), "bit {}", bit);
+        }
+        let mut truncated = blob.clone();
+        truncated.0.pop();
+        assert!(!truncated.verify_checksum());
+        assert!(!TachystampBytes(vec![1, 2, 3]).verify_checksum());
+    }
+
+    #[test]
+    fn canonical_bundles_agree_on_bytes_and_digest() {
+        let spend = |b: u8, value| Tachyaction::Spend { nf: [b; 32], value };
+        let output = |b: u8, value| Tachyaction::Output { cm: [b; 32], value };
+        let mut a = TachyonBundle::new();
+        a.actions = vec![output(7, 3), spend(2, 4), output(5, 1), spend(1, 2), output(5, 1)];
+        a.nullifiers = vec![[2u8; 32], [1u8; 32]];
+        a.commitments = vec![[7u8; 32], [5u8; 32], [5u8; 32]];
+        a.fee = 2;
+        let mut b = TachyonBundle::new();
+        b.actions = vec![spend(1, 2), output(5, 1), spend(2, 4), output(7, 3)];
+        b.nullifiers = vec![[1u8; 32], [2u8; 32]];
+        b.commitments = vec![[5u8; 32], [7u8; 32]];
+        b.fee = 2;
+        assert_ne!(crate::digest::derive_unified_tachygram_tx(&a), crate::digest::derive_unified_tachygram_tx(&b));
+
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(a.actions, vec![spend(1, 2), spend(2, 4), output(5, 1), output(7, 3)]);
+        assert_eq!(serde_json::to_vec(&a).unwrap(), serde_json::to_vec(&b).unwrap());
+        assert_eq!(crate::digest::derive_unified_tachygram_tx(&a), crate::digest::derive_unified_tachygram_tx(&b));
+        a.check_consistency().unwrap();
+    }
+
+    fn anchor(min_pos: u64, max_pos: u64) -> RangeAnchor {
+        RangeAnchor { min_pos, max_pos, root_min: [0u8; 32], root_max: [0u8; 32], frontier_attestation: vec![] }
+    }
+
+    #[test]
+    fn range_anchor_span() {
+        assert_eq!(anchor(10, 25).span(), Some(15));
+        assert_eq!(anchor(0, u64::MAX).span(), Some(u64::MAX));
+        assert_eq!(anchor(26, 25).span(), None);
+    }
+
+    #[test]
+    fn range_anchor_contains_bounds() {
+        let a = anchor(10, 25);
+        assert!(a.contains(10) && a.contains(25));
+        assert!(!a.contains(9) && !a.contains(26));
+        assert!(anchor(u64::MAX, u64::MAX).contains(u64::MAX));
+        let inverted = anchor(26, 25);
+        assert!(!inverted.contains(25) && !inverted.contains(26));
+    }
+
+    #[test]
+    fn hex_newtypes_roundtrip() {
+        let b = [0xa7u8; 32];
+        assert_eq!(Tachygram::from_hex(&Tachygram(b).hex()).unwrap(), Tachygram(b));
+        assert_eq!(NullifierFlavor::from_hex(&NullifierFlavor(b).hex()).unwrap(), NullifierFlavor(b));
+        assert_eq!(OnChainNullifier::from_hex(&OnChainNullifier(b).hex()).unwrap(), OnChainNullifier(b));
+        assert_eq!(OffchainSyncTag::from_hex(&OffchainSyncTag(b).hex()).unwrap(), OffchainSyncTag(b));
+        assert_eq!(UnifiedTachygramDigest::from_hex(&UnifiedTachygramDigest(b).hex()).unwrap(), UnifiedTachygramDigest(b));
+        assert_eq!(OnChainNullifier(b).to_string(), "a7".repeat(32));
+        assert!(OnChainNullifier::from_hex(&"a7".repeat(33)).is_err());
+        assert!(OnChainNullifier::from_hex(&"zz".repeat(32)).is_err());
+    }
+}
@@ -0,0 +1,56 @@
+//! Alloc-only build of the canonical core:
+//! `cargo test -p primitives --no-default-features --features no_std --test no_std`.
+#![cfg(feature = "no_std")]
+
+use primitives::*;
+
+fn stamp() -> Tachystamp {
+    Tachystamp {
+        range_anchor: RangeAnchor { min_pos: 3, max_pos: 9, root_min: [1u8; ROOT_LEN], root_max: [2u8; ROOT_LEN], frontier_attestation: vec![7, 7] },
+        tachygrams: vec![Tachygram([4u8; 32]), Tachygram([5u8; 32])],
+        auth: RedPallasSig([6u8; REDPALLAS_SIG_LEN]),
+        pcd_proof: PcdProof(vec![8; 40]),
+    }
+}
+
+#[test]
+fn canonical_encodings_roundtrip_without_std() {
+    let stamp = stamp();
+    let bytes = stamp.to_canonical_bytes();
+    assert_eq!(Tachystamp::from_canonical_bytes(&bytes).unwrap(), stamp);
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(Tachystamp::from_canonical_bytes(&trailing), Err(DecodeError::TrailingBytes("Tachystamp")));
+    assert_eq!(Tachystamp::from_canonical_bytes(&bytes[..bytes.len() - 1]), Err(DecodeError::UnexpectedEof));
+
+    let agg = AggregateProof { txids: vec![[9u8; TXID_LEN]; 2], proof: vec![1, 2, 3] };
+    let bytes = agg.to_canonical_bytes();
+    assert_eq!(AggregateProof::from_canonical_bytes(&bytes).unwrap(), agg);
+    let few = DecodeLimits { max_txids: 1, ..DecodeLimits::default() };
+    assert_eq!(
+        AggregateProof::from_canonical_bytes_limited(&bytes, &few),
+        Err(DecodeError::TooManyTxids { len: 2, max: 1 })
+    );
+}
+
+#[test]
+fn digests_and_consistency_without_std() {
+    let mut bundle = TachyonBundle::new();
+    bundle.actions.push(Tachyaction::Spend { nf: [1u8; 32], value: 5 });
+    bundle.actions.push(Tachyaction::Output { cm: [2u8; 32], value: 4 });
+    bundle.nullifiers.push([1u8; 32]);
+    bundle.commitments.push([2u8; 32]);
+    bundle.fee = 1;
+    bundle.check_consistency().unwrap();
+    bundle.fee = 2;
+    assert_eq!(bundle.check_consistency(), Err(BundleError::ValueImbalance { value_in: 5, value_out: 4, fee: 2 }));
+
+    let tags = [[3u8; 32], [4u8; 32]];
+    let batch = tachygrams_to_fr_batch(&tags);
+    assert_eq!(batch, vec![tachygram_to_fr(&tags[0]), tachygram_to_fr(&tags[1])]);
+
+    let mut registry = FlavorRegistry::new();
+    assert_eq!(registry.register(*FLAVOR_DOMAIN_V1), Err(FlavorError::Duplicate(*FLAVOR_DOMAIN_V1)));
+    let flavor = derive_fixed_flavor(&[1u8; 32], &[2u8; 32]);
+    assert_eq!(registry.validate(&flavor, &[1u8; 32], &[2u8; 32]), Some(*FLAVOR_DOMAIN_V1));
+}
@@ -34,7 +34,7 @@ fn main() {
     let old = FrVesta::random(&mut rng);
     let meta = FrVesta::random(&mut rng);
     let new = old + meta * folded;
-    let data = PcdData { old_root: old, new_root: new, metadata: meta, accumulator: folded };
+    let data = PcdData::new(old, new, meta, folded);
 
     let backend = TranscriptBackend;
     let driver = CpuDriver::<FrVesta>::new();
@@ -2,6 +2,15 @@
 //! This is a simple additive accumulator with split-fold merging.
 
 use ff::PrimeField;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AccumError {
+    #[error("cannot fold an empty accumulator")]
+    Empty,
+    #[error("malformed accumulator checkpoint")]
+    Checkpoint,
+}
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Accumulator<F: PrimeField> {
@@ -9,6 +18,7 @@ pub struct Accumulator<F: PrimeField> {
 }
 
 impl<F: PrimeField> Accumulator<F> {
+    /// Identity for `merge`: folding it in leaves any accumulator unchanged.
     pub fn zero() -> Self { Self { v: F::ZERO } }
     pub fn unit(x: F) -> Self { Self { v: x } }
     pub fn merge(self, other: Self) -> Self { Self { v: self.v + other.v } }
@@ -17,13 +27,60 @@ impl<F: PrimeField> Accumulator<F> {
 #[derive(Clone, Debug)]
 pub struct SplitAccumulator<F: PrimeField> {
     pub leaves: Vec<Accumulator<F>>,
+    /// Units folded into `leaves[0]` by `resume_from`; zero when not resumed.
+    resumed_units: usize,
 }
 
 impl<F: PrimeField> SplitAccumulator<F> {
-    pub fn new() -> Self { Self { leaves: Vec::new() } }
+    pub fn new() -> Self { Self { leaves: Vec::new(), resumed_units: 0 } }
     pub fn push(&mut self, a: Accumulator<F>) { self.leaves.push(a); }
 
+    /// Continue from a checkpoint: `folded` stands for `count` already-folded
+    /// units, so `split_fold` and `count` carry on as if they were replayed.
+    pub fn resume_from(folded: F, count: usize) -> Self {
+        if count == 0 { return Self::new(); }
+        Self { leaves: vec![Accumulator::unit(folded)], resumed_units: count }
+    }
+
+    /// Units folded so far, including those behind a resumed checkpoint.
+    pub fn count(&self) -> usize {
+        match self.resumed_units {
+            0 => self.leaves.len(),
+            n => n + self.leaves.len().saturating_sub(1),
+        }
+    }
+
+    /// Checkpoint as `split_fold().v` then `count()`, each a field repr.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.split_fold().v.to_repr().as_ref().to_vec();
+        out.extend_from_slice(F::from(self.count() as u64).to_repr().as_ref());
+        out
+    }
+
+    /// Restore a `to_bytes` checkpoint with `resume_from`. Rejects wrong
+    /// lengths, non-canonical reprs and counts that do not fit a `usize`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, AccumError> {
+        let n = F::Repr::default().as_ref().len();
+        if bytes.len() != 2 * n { return Err(AccumError::Checkpoint); }
+        let read = |b: &[u8]| {
+            let mut repr = F::Repr::default();
+            repr.as_mut().copy_from_slice(b);
+            Option::<F>::from(F::from_repr(repr)).ok_or(AccumError::Checkpoint)
+        };
+        let folded = read(&bytes[..n])?;
+        let count_field = read(&bytes[n..])?;
+        // Counts are written as F::from(u64); re-encode to reject anything else.
+        let mut low = [0u8; 8];
+        low.copy_from_slice(&bytes[n..n + 8]);
+        let count = u64::from_le_bytes(low);
+        if F::from(count) != count_field { return Err(AccumError::Checkpoint); }
+        let count = usize::try_from(count).map_err(|_| AccumError::Checkpoint)?;
+        if count == 0 && folded != F::ZERO { return Err(AccumError::Checkpoint); }
+        Ok(Self::resume_from(folded, count))
+    }
+
     /// Fold in two passes: pairwise then linear, to reflect "split accumulation".
+    /// An empty accumulator folds to the identity `Accumulator::zero()`.
     pub fn split_fold(&self) -> Accumulator<F> {
         if self.leaves.is_empty() { return Accumulator::zero(); }
         let mut layer = self.leaves.clone();
@@ -41,6 +98,11 @@ impl<F: PrimeField> SplitAccumulator<F> {
         }
         layer[0]
     }
-}
-
 
+    /// Like `split_fold`, but rejects an empty accumulator instead of silently
+    /// returning the identity.
+    pub fn try_fold(&self) -> Result<Accumulator<F>, AccumError> {
+        if self.leaves.is_empty() { return Err(AccumError::Empty); }
+        Ok(self.split_fold())
+    }
+}
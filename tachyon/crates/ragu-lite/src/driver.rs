@@ -10,6 +10,10 @@ pub enum SynthesisError {
     InstanceLength,
     #[error("verification failed")]
     Verification,
+    #[error("no steps to fold")]
+    EmptyChain,
+    #[error("step depth {depth} exceeds maximum {max}")]
+    DepthExceeded { depth: u64, max: u64 },
 }
 
 #[derive(Clone, Debug)]
@@ -21,14 +21,17 @@ pub mod pcd_blog;
 #[cfg(feature = "blog_api")]
 pub mod wallet_blog;
 
-pub use accum::{Accumulator, SplitAccumulator};
+pub use accum::{AccumError, Accumulator, SplitAccumulator};
 pub use cs::{Constraint, ConstraintSystem, LinComb, Var};
 pub use driver::{Circuit, CpuDriver, Driver, Instance, SynthesisError};
 pub use maybe::Maybe;
-pub use pcd::{prove_step, verify_step, Pcd, PcdData, RecursionBackend, TranscriptBackend};
-pub use pasta::{FrPallas, FrVesta};
+pub use pcd::{
+    fold_steps, prove_cycle_step, prove_step, prove_step_bounded, verify_cycle_step, verify_step, BackendPcd, InnerStep, OuterStep, Pcd, PcdData,
+    RecursionBackend, TranscriptBackend,
+};
+pub use pasta::{CycleField, FrPallas, FrVesta};
 pub use wallet::{
-    Batch, Note, TachyObj, Wallet, WalletCircuit, WalletParams,
+    Address, AddressError, Batch, BatchEffect, Note, TachyObj, Wallet, StateKdfParams, WalletCircuit, WalletParams, WalletStateError,
 };
 pub use transcript::FsTranscript;
 
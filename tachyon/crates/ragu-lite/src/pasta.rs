@@ -29,3 +29,29 @@ impl FromBytesWide for FrPallas {
 }
 
 
+
+/// A field of the Pasta cycle, paired with the other one. Proofs over `Self`
+/// are verified by circuits over `Self::Other`.
+pub trait CycleField: PrimeField {
+    type Other: CycleField<Other = Self>;
+}
+
+impl CycleField for FrVesta {
+    type Other = FrPallas;
+}
+
+impl CycleField for FrPallas {
+    type Other = FrVesta;
+}
+
+/// Split a field element into two 128-bit limbs (low first) of another field.
+/// Both limbs fit in either Pasta field, so this never loses information.
+pub fn foreign_limbs<S: PrimeField, T: PrimeField>(x: &S) -> [T; 2] {
+    let repr = x.to_repr();
+    let bytes = repr.as_ref();
+    let mut lo = [0u8; 16];
+    let mut hi = [0u8; 16];
+    lo.copy_from_slice(&bytes[..16]);
+    hi.copy_from_slice(&bytes[16..32]);
+    [T::from_u128(u128::from_le_bytes(lo)), T::from_u128(u128::from_le_bytes(hi))]
+}
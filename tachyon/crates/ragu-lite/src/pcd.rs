@@ -1,6 +1,7 @@
 //! PCD container and mock recursion backend.
 
 use crate::driver::{Circuit, Driver, Instance, SynthesisError};
+use crate::pasta::{foreign_limbs, CycleField, FrPallas, FrVesta};
 use crate::transcript::FsTranscript;
 use ff::PrimeField;
 
@@ -11,6 +12,23 @@ pub struct PcdData<F: PrimeField> {
     pub new_root: F,
     pub metadata: F,
     pub accumulator: F,
+    /// Extra public values (e.g. a fee commitment), exposed in order right
+    /// after the four fixed fields.
+    pub extra: Vec<F>,
+}
+
+impl<F: PrimeField> PcdData<F> {
+    /// Transition data with no extra public values.
+    pub fn new(old_root: F, new_root: F, metadata: F, accumulator: F) -> Self {
+        Self { old_root, new_root, metadata, accumulator, extra: Vec::new() }
+    }
+
+    /// Public inputs in instance order: the four fixed fields, then `extra`.
+    pub fn public_inputs(&self) -> Vec<F> {
+        let mut out = vec![self.old_root, self.new_root, self.metadata, self.accumulator];
+        out.extend_from_slice(&self.extra);
+        out
+    }
 }
 
 /// Generic "proof" that carries data plus a transcript commitment.
@@ -21,6 +39,16 @@ pub struct Pcd<F: PrimeField, Inner> {
     pub instance: Instance<F>,
     pub inner: Inner,
     pub depth: u64,
+    /// Index of `data.old_root` in `instance.inputs`: past whatever
+    /// `allocate_prev` exposed for `prove_step`, past the previous instance's
+    /// limbs for `prove_cycle_step`. Private so
+    /// only the provers here set it; `verify_step` trusts it to locate `data`.
+    data_offset: usize,
+}
+
+impl<F: PrimeField, Inner> Pcd<F, Inner> {
+    /// Index of `data.old_root` in `instance.inputs`.
+    pub fn data_offset(&self) -> usize { self.data_offset }
 }
 
 pub trait RecursionBackend<F: PrimeField> {
@@ -36,8 +64,15 @@ pub trait RecursionBackend<F: PrimeField> {
     /// Produce a new outer proof from the synthesized instance.
     fn prove(&self, inst: &Instance<F>, tr: &FsTranscript) -> Self::Proof;
 
-    /// Verify a proof against an instance.
-    fn verify(&self, inst: &Instance<F>, proof: &Self::Proof) -> bool;
+    /// Verify a proof against an instance and the step transcript it was proven under.
+    fn verify(&self, inst: &Instance<F>, tr: &FsTranscript, proof: &Self::Proof) -> bool;
+}
+
+/// Step transcript binding a proof to the depth of the proof it extends.
+fn step_transcript(prev_depth: u64) -> FsTranscript {
+    let mut tr = FsTranscript::new(b"ragu-lite/step");
+    tr.absorb(&u64::to_le_bytes(prev_depth));
+    tr
 }
 
 /// A simple transcript-only backend. Not a SNARK. Useful to exercise the API.
@@ -65,16 +100,8 @@ impl<F: PrimeField> RecursionBackend<F> for TranscriptBackend {
         t.challenge_bytes(b"proof")
     }
 
-    fn verify(&self, inst: &Instance<F>, proof: &Self::Proof) -> bool {
-        let recomputed = {
-            let mut t = FsTranscript::new(b"ragu-lite/pcd");
-            for x in &inst.inputs {
-                t.absorb_field(x);
-            }
-            t.absorb(&FsTranscript::default().challenge_bytes(b"context"));
-            t.challenge_bytes(b"proof")
-        };
-        &recomputed == proof
+    fn verify(&self, inst: &Instance<F>, tr: &FsTranscript, proof: &Self::Proof) -> bool {
+        &self.prove(inst, tr) == proof
     }
 }
 
@@ -93,14 +120,63 @@ where
     B: RecursionBackend<F>,
     D: Driver<F, Var = crate::cs::Var>,
 {
-    // Allocate previous proof's instance in-circuit (mocked here).
+    // Allocate previous proof's instance in-circuit (mocked here). Anything it
+    // exposes comes first; `data` starts right after.
     backend.allocate_prev(&mut driver, prev)?;
+    let data_offset = driver.instance().inputs.len();
+    synthesize_transition(&mut driver, circuit, &data);
+
+    let instance = driver.instance();
+    let tr = step_transcript(prev.map(|p| p.depth).unwrap_or(0));
+    let proof = backend.prove(&instance, &tr);
+
+    Ok(Pcd {
+        data,
+        instance,
+        inner: proof,
+        depth: prev.map(|p| p.depth + 1).unwrap_or(1),
+        data_offset,
+    })
+}
 
-    // Public inputs are circuit-defined: old_root, new_root, metadata, accumulator.
+/// `prove_step` that refuses to extend a chain past `max_depth` steps, so
+/// verifiers are never handed pathologically deep chains.
+pub fn prove_step_bounded<F, C, B, D>(
+    backend: &B,
+    circuit: &C,
+    driver: D,
+    prev: Option<&Pcd<F, B::Proof>>,
+    data: PcdData<F>,
+    max_depth: u64,
+) -> Result<Pcd<F, B::Proof>, SynthesisError>
+where
+    F: PrimeField,
+    C: Circuit<F, Input = PcdData<F>, Output = ()>,
+    B: RecursionBackend<F>,
+    D: Driver<F, Var = crate::cs::Var>,
+{
+    let depth = prev.map(|p| p.depth.saturating_add(1)).unwrap_or(1);
+    if depth > max_depth {
+        return Err(SynthesisError::DepthExceeded { depth, max: max_depth });
+    }
+    prove_step(backend, circuit, driver, prev, data)
+}
+
+/// Transition rule shared by `prove_step` and `prove_cycle_step`.
+fn synthesize_transition<F, C, D>(driver: &mut D, circuit: &C, data: &PcdData<F>)
+where
+    F: PrimeField,
+    C: Circuit<F, Input = PcdData<F>, Output = ()>,
+    D: Driver<F, Var = crate::cs::Var>,
+{
+    // Public inputs are circuit-defined: old_root, new_root, metadata, accumulator, extra...
     let inp_old = driver.input_public(data.old_root);
     let inp_new = driver.input_public(data.new_root);
     let inp_meta = driver.input_public(data.metadata);
     let inp_acc = driver.input_public(data.accumulator);
+    for x in &data.extra {
+        driver.input_public(*x);
+    }
 
     // Example transition rule: enforce new_root = old_root + metadata * accumulator.
     let prod = driver.mul(inp_meta, inp_acc);
@@ -111,29 +187,130 @@ where
     );
 
     // Let the user circuit add more rules if desired.
-    circuit.synthesize(&mut driver, data.clone());
+    circuit.synthesize(driver, data.clone());
+}
+
+/// Verify `p`'s proof and that its instance carries `p.data` (fixed fields
+/// then `extra`) at `p.data_offset`.
+pub fn verify_step<F, B: RecursionBackend<F>>(
+    backend: &B,
+    p: &Pcd<F, B::Proof>,
+) -> Result<(), SynthesisError>
+where
+    F: PrimeField,
+{
+    let expected = p.data.public_inputs();
+    let exposed = p.instance.inputs.get(p.data_offset..).ok_or(SynthesisError::InstanceLength)?;
+    if exposed.len() < expected.len() {
+        return Err(SynthesisError::InstanceLength);
+    }
+    if exposed[..expected.len()] != expected[..] {
+        return Err(SynthesisError::Verification);
+    }
+    let tr = step_transcript(p.depth.saturating_sub(1));
+    if backend.verify(&p.instance, &tr, &p.inner) { Ok(()) } else { Err(SynthesisError::Verification) }
+}
+
+/// Fold a chain of steps over one field into a single PCD spanning the first
+/// `old_root` to the last `new_root`, at depth `steps.len()`. Each step must
+/// verify and link to the next (`new_root == next.old_root`).
+///
+/// The folded data has metadata one and accumulator `sum(metadata * accumulator)`
+/// over the steps, so it satisfies the same transition rule. With
+/// `TranscriptBackend` the steps are checked natively, not in-circuit.
+pub fn fold_steps<F, B>(backend: &B, steps: &[Pcd<F, B::Proof>]) -> Result<Pcd<F, B::Proof>, SynthesisError>
+where
+    F: PrimeField,
+    B: RecursionBackend<F>,
+{
+    let (first, last) = match (steps.first(), steps.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return Err(SynthesisError::EmptyChain),
+    };
+    for step in steps {
+        verify_step(backend, step)?;
+    }
+    if steps.windows(2).any(|pair| pair[0].data.new_root != pair[1].data.old_root) {
+        return Err(SynthesisError::Verification);
+    }
+
+    let accumulator = steps.iter().map(|s| s.data.metadata * s.data.accumulator).sum();
+    let data = PcdData::new(first.data.old_root, last.data.new_root, F::ONE, accumulator);
+    let instance = Instance { inputs: data.public_inputs() };
+    let depth = steps.len() as u64;
+    let proof = backend.prove(&instance, &step_transcript(depth - 1));
+    Ok(Pcd { data, instance, inner: proof, depth, data_offset: 0 })
+}
+
+/// Step proved over `FrVesta`; its proof is verified by the next `InnerStep`.
+pub type OuterStep<P> = Pcd<FrVesta, P>;
+
+/// Step proved over `FrPallas`; its proof is verified by the next `OuterStep`.
+pub type InnerStep<P> = Pcd<FrPallas, P>;
+
+/// PCD over `F` carrying `B`'s proof for that field.
+pub type BackendPcd<F, B> = Pcd<F, <B as RecursionBackend<F>>::Proof>;
+
+/// Prove one turn of the Pasta cycle: a step over `F` that verifies `prev`,
+/// proved over the other field. The previous instance is exposed as 128-bit
+/// limbs ahead of the transition inputs, so the new instance commits to it.
+/// With `TranscriptBackend` the verification itself is native, not in-circuit.
+pub fn prove_cycle_step<F, C, B, D>(
+    backend: &B,
+    circuit: &C,
+    mut driver: D,
+    prev: Option<&BackendPcd<F::Other, B>>,
+    data: PcdData<F>,
+) -> Result<BackendPcd<F, B>, SynthesisError>
+where
+    F: CycleField,
+    C: Circuit<F, Input = PcdData<F>, Output = ()>,
+    B: RecursionBackend<F> + RecursionBackend<F::Other>,
+    D: Driver<F, Var = crate::cs::Var>,
+{
+    let mut data_offset = 0;
+    if let Some(p) = prev {
+        verify_step::<F::Other, B>(backend, p)?;
+        data_offset = 2 * p.instance.inputs.len();
+        for x in &p.instance.inputs {
+            for limb in foreign_limbs::<F::Other, F>(x) {
+                driver.input_public(limb);
+            }
+        }
+    }
+    synthesize_transition(&mut driver, circuit, &data);
 
     let instance = driver.instance();
-    let mut tr = FsTranscript::new(b"ragu-lite/step");
-    tr.absorb(&u64::to_le_bytes(prev.map(|p| p.depth).unwrap_or(0)));
-    let proof = backend.prove(&instance, &tr);
+    let tr = step_transcript(prev.map(|p| p.depth).unwrap_or(0));
+    let proof = <B as RecursionBackend<F>>::prove(backend, &instance, &tr);
 
     Ok(Pcd {
         data,
         instance,
         inner: proof,
         depth: prev.map(|p| p.depth + 1).unwrap_or(1),
+        data_offset,
     })
 }
 
-pub fn verify_step<F, B: RecursionBackend<F>>(
+/// Verify `step` over `F` and that it extends `prev`, proved over the other field.
+pub fn verify_cycle_step<F, B>(
     backend: &B,
-    p: &Pcd<F, B::Proof>,
+    prev: &BackendPcd<F::Other, B>,
+    step: &BackendPcd<F, B>,
 ) -> Result<(), SynthesisError>
 where
-    F: PrimeField,
+    F: CycleField,
+    B: RecursionBackend<F> + RecursionBackend<F::Other>,
 {
-    if backend.verify(&p.instance, &p.inner) { Ok(()) } else { Err(SynthesisError::Verification) }
+    verify_step::<F::Other, B>(backend, prev)?;
+    verify_step::<F, B>(backend, step)?;
+    let limbs: Vec<F> = prev.instance.inputs.iter().flat_map(foreign_limbs::<F::Other, F>).collect();
+    if step.instance.inputs.len() < limbs.len() {
+        return Err(SynthesisError::InstanceLength);
+    }
+    if step.depth != prev.depth + 1 || step.data_offset != limbs.len() || step.instance.inputs[..limbs.len()] != limbs[..] {
+        return Err(SynthesisError::Verification);
+    }
+    Ok(())
 }
-
-
@@ -11,10 +11,14 @@ use crate::{
     pasta::{FromBytesWide, FrVesta},
     pcd::{prove_step, verify_step, Pcd, PcdData, RecursionBackend, TranscriptBackend},
 };
+use argon2::{Algorithm, Argon2, Params, Version};
 use blake3::{hash, keyed_hash};
+use chacha20poly1305::{aead::{Aead, KeyInit, Payload}, ChaCha20Poly1305, Key, Nonce};
 use ff::{Field, PrimeField};
 use rand_core::{CryptoRng, RngCore};
 use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
 
 /// Unified 32-byte object (tachygram/tachystamp).
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
@@ -36,6 +40,142 @@ impl TachyObj {
     }
 }
 
+/// Canonical wallet address (the 32-byte `Wallet::address` object).
+///
+/// String form is Bech32 (BIP-173 checksum) with HRP `tachy`, e.g. `tachy1...`.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Address(pub [u8; 32]);
+
+/// Errors from decoding an `Address`.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("address must be 32 bytes, got {0}")]
+    Length(usize),
+    #[error("address has wrong human-readable part")]
+    Hrp,
+    #[error("invalid address character {0:?}")]
+    Char(char),
+    #[error("mixed-case address")]
+    MixedCase,
+    #[error("address checksum mismatch")]
+    Checksum,
+    #[error("malformed address")]
+    Malformed,
+}
+
+const ADDRESS_HRP: &str = "tachy";
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: impl IntoIterator<Item = u8>) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+    let mut chk = 1u32;
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x01ff_ffff) << 5) ^ v as u32;
+        for (i, g) in GEN.iter().enumerate() {
+            if (top >> i) & 1 == 1 { chk ^= g; }
+        }
+    }
+    chk
+}
+
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let b = hrp.as_bytes();
+    b.iter().map(|c| c >> 5).chain(std::iter::once(0)).chain(b.iter().map(|c| c & 31)).collect()
+}
+
+fn bech32_checksum(hrp: &str, data: &[u8]) -> [u8; 6] {
+    let values = bech32_hrp_expand(hrp).into_iter().chain(data.iter().copied()).chain([0u8; 6]);
+    let pm = bech32_polymod(values) ^ 1;
+    let mut out = [0u8; 6];
+    for (i, o) in out.iter_mut().enumerate() {
+        *o = ((pm >> (5 * (5 - i))) & 31) as u8;
+    }
+    out
+}
+
+/// Regroup `data` from `from`-bit to `to`-bit words; `pad` controls trailing bits.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let maxv = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &v in data {
+        if (v as u32) >> from != 0 { return None; }
+        acc = (acc << from) | v as u32;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & maxv) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 { out.push(((acc << (to - bits)) & maxv) as u8); }
+    } else if bits >= from || (acc << (to - bits)) & maxv != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+impl Address {
+    #[inline]
+    pub fn to_bytes(&self) -> [u8; 32] { self.0 }
+
+    pub fn from_bytes(b: &[u8]) -> Result<Self, AddressError> {
+        let arr: [u8; 32] = b.try_into().map_err(|_| AddressError::Length(b.len()))?;
+        Ok(Address(arr))
+    }
+
+    #[inline]
+    pub fn to_obj(&self) -> TachyObj { TachyObj(self.0) }
+
+    /// Bech32 string form with HRP `tachy`.
+    pub fn encode(&self) -> String {
+        let data = convert_bits(&self.0, 8, 5, true).expect("8-bit input");
+        let checksum = bech32_checksum(ADDRESS_HRP, &data);
+        let mut s = String::with_capacity(ADDRESS_HRP.len() + 1 + data.len() + 6);
+        s.push_str(ADDRESS_HRP);
+        s.push('1');
+        s.extend(data.iter().chain(checksum.iter()).map(|&d| BECH32_CHARSET[d as usize] as char));
+        s
+    }
+
+    /// Parse the Bech32 string form, rejecting a wrong HRP, bad characters or checksum.
+    pub fn decode(s: &str) -> Result<Self, AddressError> {
+        let has_lower = s.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = s.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper { return Err(AddressError::MixedCase); }
+        let s = s.to_ascii_lowercase();
+        let sep = s.rfind('1').ok_or(AddressError::Malformed)?;
+        let (hrp, rest) = (&s[..sep], &s[sep + 1..]);
+        if hrp != ADDRESS_HRP { return Err(AddressError::Hrp); }
+        if rest.len() < 6 { return Err(AddressError::Malformed); }
+        let values = rest
+            .chars()
+            .map(|c| {
+                BECH32_CHARSET.iter().position(|&x| x as char == c).map(|p| p as u8).ok_or(AddressError::Char(c))
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        if bech32_polymod(bech32_hrp_expand(hrp).into_iter().chain(values.iter().copied())) != 1 {
+            return Err(AddressError::Checksum);
+        }
+        let bytes = convert_bits(&values[..values.len() - 6], 5, 8, false).ok_or(AddressError::Malformed)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+impl From<TachyObj> for Address {
+    fn from(obj: TachyObj) -> Self { Address(obj.0) }
+}
+
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.encode()) }
+}
+
+impl std::str::FromStr for Address {
+    type Err = AddressError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Self::decode(s) }
+}
+
 /// Wallet parameters affecting accumulation and metadata.
 #[derive(Clone, Debug)]
 pub struct WalletParams {
@@ -57,7 +197,8 @@ impl Default for WalletParams {
     }
 }
 
-/// A received note tracked by the wallet.
+/// A received note tracked by the wallet. `rseed` and `value` are secret;
+/// `zeroize` wipes them (a `Wallet` does so for its notes on drop).
 #[derive(Clone, Debug)]
 pub struct Note {
     pub commitment: TachyObj,
@@ -65,6 +206,13 @@ pub struct Note {
     pub rseed: [u8; 32],
 }
 
+impl Zeroize for Note {
+    fn zeroize(&mut self) {
+        self.value.zeroize();
+        self.rseed.zeroize();
+    }
+}
+
 impl Note {
     /// Toy commitment. Real Orchard uses Pedersen+Poseidon; here we use BLAKE3.
     pub fn commit(addr: &TachyObj, value: u64, rseed: [u8; 32]) -> TachyObj {
@@ -119,12 +267,102 @@ impl Batch {
     }
 }
 
+/// Owned notes a batch would spend and commitments it would add, as seen by a wallet.
+#[derive(Clone, Debug, Default)]
+pub struct BatchEffect {
+    /// Owned, unspent notes whose derived nullifier appears in the batch.
+    pub spends: Vec<Note>,
+    /// Batch commitments the wallet does not already track.
+    pub receives: Vec<TachyObj>,
+}
+
 /// Derive a nullifier using a secret spend key and a commitment.
 pub fn derive_nullifier(spend_key: &[u8; 32], commitment: &TachyObj) -> TachyObj {
     TachyObj(*keyed_hash(spend_key, &commitment.0).as_bytes())
 }
 
+/// Errors from importing an exported wallet state.
+#[derive(Debug, Error)]
+pub enum WalletStateError {
+    #[error("unsupported wallet state version {0}")]
+    Version(u8),
+    #[error("wallet state decryption failed")]
+    Decrypt,
+    #[error("malformed wallet state")]
+    Malformed,
+    #[error("invalid wallet state key derivation parameters")]
+    Kdf,
+}
+
+/// Argon2id cost parameters for the wallet state key, stored in the export
+/// header so `import_state` re-derives the same key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateKdfParams {
+    /// Memory cost in KiB.
+    pub m_cost_kib: u32,
+    /// Number of passes.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl Default for StateKdfParams {
+    /// OWASP's Argon2id baseline: 19 MiB, 2 passes, 1 lane.
+    fn default() -> Self {
+        Self { m_cost_kib: 19 * 1024, t_cost: 2, p_cost: 1 }
+    }
+}
+
+const STATE_VERSION: u8 = 2;
+const STATE_SALT_LEN: usize = 16;
+const STATE_HEADER_LEN: usize = 1 + 3 * 4 + STATE_SALT_LEN;
+// Caps what a hostile header can make `import_state` spend: 1 GiB, 16 passes, 16 lanes.
+const MAX_STATE_M_COST_KIB: u32 = 1 << 20;
+const MAX_STATE_T_COST: u32 = 16;
+const MAX_STATE_P_COST: u32 = 16;
+const SEED_KEY_CONTEXT: &str = "ragu-wallet 2024 spend key from seed v1";
+
+fn derive_state_key(passphrase: &[u8], salt: &[u8], kdf: &StateKdfParams) -> Result<Zeroizing<[u8; 32]>, WalletStateError> {
+    if kdf.m_cost_kib > MAX_STATE_M_COST_KIB || kdf.t_cost > MAX_STATE_T_COST || kdf.p_cost > MAX_STATE_P_COST {
+        return Err(WalletStateError::Kdf);
+    }
+    let params = Params::new(kdf.m_cost_kib, kdf.t_cost, kdf.p_cost, Some(32)).map_err(|_| WalletStateError::Kdf)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, key.as_mut())
+        .map_err(|_| WalletStateError::Kdf)?;
+    Ok(key)
+}
+
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8], WalletStateError> {
+    if data.len() < n { return Err(WalletStateError::Malformed); }
+    let (head, rest) = data.split_at(n);
+    *data = rest;
+    Ok(head)
+}
+
+fn take32(data: &mut &[u8]) -> Result<[u8; 32], WalletStateError> {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(take(data, 32)?);
+    Ok(out)
+}
+
+fn take_u64(data: &mut &[u8]) -> Result<u64, WalletStateError> {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(take(data, 8)?);
+    Ok(u64::from_le_bytes(out))
+}
+
+fn take_u32(data: &mut &[u8]) -> Result<u32, WalletStateError> {
+    let mut out = [0u8; 4];
+    out.copy_from_slice(take(data, 4)?);
+    Ok(u32::from_le_bytes(out))
+}
+
 /// Wallet that carries its own recursive state proof.
+///
+/// Secret: `spend_key` and each note's `rseed` and `value`, wiped on drop.
+/// The root, spent set and proofs are public state.
 pub struct Wallet<B: RecursionBackend<FrVesta> = TranscriptBackend> {
     params: WalletParams,
     backend: B,
@@ -137,20 +375,52 @@ pub struct Wallet<B: RecursionBackend<FrVesta> = TranscriptBackend> {
 
     // The current recursive proof of spendability/history.
     pub pcd: Option<Pcd<FrVesta, B::Proof>>,
+
+    // Every step proof produced by this wallet, depth 1 first.
+    pub history: Vec<Pcd<FrVesta, B::Proof>>,
+
+    // Depth restored by `import_state` while no proof is held.
+    imported_depth: u64,
+}
+
+impl<B: RecursionBackend<FrVesta>> Zeroize for Wallet<B> {
+    fn zeroize(&mut self) {
+        self.spend_key.zeroize();
+        self.notes.values_mut().for_each(Zeroize::zeroize);
+    }
 }
 
+impl<B: RecursionBackend<FrVesta>> Drop for Wallet<B> {
+    fn drop(&mut self) { self.zeroize(); }
+}
+
+impl<B: RecursionBackend<FrVesta>> ZeroizeOnDrop for Wallet<B> {}
+
 impl<B: RecursionBackend<FrVesta> + Default> Wallet<B> {
     pub fn new<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
-        let mut sk = [0u8; 32];
-        rng.fill_bytes(&mut sk);
+        let mut sk = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(sk.as_mut());
+        Self::from_spend_key(*sk)
+    }
+
+    /// Deterministic wallet: the spend key (and so the address and nullifiers)
+    /// is derived from `seed` via BLAKE3 key derivation.
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        let sk = Zeroizing::new(blake3::derive_key(SEED_KEY_CONTEXT, seed));
+        Self::from_spend_key(*sk)
+    }
+
+    fn from_spend_key(spend_key: [u8; 32]) -> Self {
         Self {
             params: WalletParams::default(),
             backend: B::default(),
-            spend_key: sk,
+            spend_key,
             root: FrVesta::ZERO,
             notes: BTreeMap::new(),
             spent: BTreeSet::new(),
             pcd: None,
+            history: Vec::new(),
+            imported_depth: 0,
         }
     }
 
@@ -159,6 +429,9 @@ impl<B: RecursionBackend<FrVesta> + Default> Wallet<B> {
         TachyObj(*hash(&self.spend_key).as_bytes())
     }
 
+    /// Canonical, shareable form of `address()`.
+    pub fn canonical_address(&self) -> Address { Address::from(self.address()) }
+
     pub fn receive(&mut self, note: Note) {
         self.notes.insert(note.commitment.0, note);
     }
@@ -184,7 +457,7 @@ impl<B: RecursionBackend<FrVesta> + Default> Wallet<B> {
         let old = self.root;
         let new = old + meta * folded;
 
-        let data = PcdData { old_root: old, new_root: new, metadata: meta, accumulator: folded };
+        let data = PcdData::new(old, new, meta, folded);
 
         let circuit = WalletCircuit;
         let driver = CpuDriver::<FrVesta>::new();
@@ -192,9 +465,159 @@ impl<B: RecursionBackend<FrVesta> + Default> Wallet<B> {
         let p = prove_step(&self.backend, &circuit, driver, self.pcd.as_ref(), data).expect("prove step");
         self.root = new;
         self.pcd = Some(p.clone());
+        self.history.push(p.clone());
         p
     }
 
+    /// Report which owned notes `batch` spends and which commitments are new,
+    /// without changing wallet state.
+    pub fn preview_batch(&self, batch: &Batch) -> BatchEffect {
+        let nfs: BTreeSet<TachyObj> = batch.nullifiers.iter().copied().collect();
+        let spends = self
+            .notes
+            .values()
+            .filter(|n| !self.spent.contains(&n.commitment.0))
+            .filter(|n| nfs.contains(&derive_nullifier(&self.spend_key, &n.commitment)))
+            .cloned()
+            .collect();
+        let receives = batch
+            .commitments
+            .iter()
+            .filter(|c| !self.notes.contains_key(&c.0))
+            .copied()
+            .collect();
+        BatchEffect { spends, receives }
+    }
+
+    /// Sum of values of owned notes not yet spent.
+    pub fn balance(&self) -> u64 {
+        self.notes.iter().filter(|(cm, _)| !self.spent.contains(*cm)).map(|(_, n)| n.value).sum()
+    }
+
+    /// Depth of the latest recursive proof (or the depth restored on import).
+    pub fn depth(&self) -> u64 {
+        self.pcd.as_ref().map(|p| p.depth).unwrap_or(self.imported_depth)
+    }
+
+    /// Serialize the spend key, root, notes, spent set and proof depth, encrypted
+    /// with ChaCha20-Poly1305 under an Argon2id key with the default
+    /// [`StateKdfParams`]. See [`Self::export_state_with`].
+    pub fn export_state<R: RngCore + CryptoRng>(&self, passphrase: &[u8], rng: &mut R) -> Vec<u8> {
+        self.export_state_with(passphrase, &StateKdfParams::default(), rng)
+            .expect("default KDF parameters are valid")
+    }
+
+    /// Like [`Self::export_state`] with explicit Argon2id costs.
+    ///
+    /// Layout: version || m_cost u32 || t_cost u32 || p_cost u32 || salt(16) ||
+    /// nonce(12) || ciphertext, integers little-endian. Salt and nonce are drawn
+    /// from `rng`, and the header before the nonce is authenticated as associated
+    /// data. The recursive proof is not persisted.
+    pub fn export_state_with<R: RngCore + CryptoRng>(
+        &self,
+        passphrase: &[u8],
+        kdf: &StateKdfParams,
+        rng: &mut R,
+    ) -> Result<Vec<u8>, WalletStateError> {
+        // Holds the spend key and note secrets; wiped when dropped.
+        let mut plain = Zeroizing::new(Vec::with_capacity(32 + 32 + 8 + 4 + self.notes.len() * 72 + 4 + self.spent.len() * 32));
+        plain.extend_from_slice(&self.spend_key);
+        plain.extend_from_slice(self.root.to_repr().as_ref());
+        plain.extend_from_slice(&self.depth().to_le_bytes());
+        plain.extend_from_slice(&(self.notes.len() as u32).to_le_bytes());
+        for note in self.notes.values() {
+            plain.extend_from_slice(&note.commitment.0);
+            plain.extend_from_slice(&note.value.to_le_bytes());
+            plain.extend_from_slice(&note.rseed);
+        }
+        plain.extend_from_slice(&(self.spent.len() as u32).to_le_bytes());
+        for cm in &self.spent { plain.extend_from_slice(cm); }
+
+        let mut salt = [0u8; STATE_SALT_LEN];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; 12];
+        rng.fill_bytes(&mut nonce);
+        let key = derive_state_key(passphrase, &salt, kdf)?;
+
+        let mut out = Vec::with_capacity(STATE_HEADER_LEN + 12 + plain.len() + 16);
+        out.push(STATE_VERSION);
+        out.extend_from_slice(&kdf.m_cost_kib.to_le_bytes());
+        out.extend_from_slice(&kdf.t_cost.to_le_bytes());
+        out.extend_from_slice(&kdf.p_cost.to_le_bytes());
+        out.extend_from_slice(&salt);
+        let cipher = ChaCha20Poly1305::new(&Key::from(*key));
+        let ct = cipher
+            .encrypt(&Nonce::from(nonce), Payload { msg: plain.as_slice(), aad: &out })
+            .expect("in-memory encryption");
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ct);
+        Ok(out)
+    }
+
+    /// Restore a wallet from `export_state` output. KDF costs above the import caps
+    /// are rejected before any key derivation. The restored wallet holds no
+    /// proof; the next `apply_batch_and_prove` starts a new chain from the restored root.
+    pub fn import_state(bytes: &[u8], passphrase: &[u8]) -> Result<Self, WalletStateError> {
+        let mut data = bytes;
+        let version = take(&mut data, 1)?[0];
+        if version != STATE_VERSION { return Err(WalletStateError::Version(version)); }
+        let kdf = StateKdfParams { m_cost_kib: take_u32(&mut data)?, t_cost: take_u32(&mut data)?, p_cost: take_u32(&mut data)? };
+        let salt = take(&mut data, STATE_SALT_LEN)?;
+        let nonce: Nonce = <[u8; 12]>::try_from(take(&mut data, 12)?).map_err(|_| WalletStateError::Malformed)?.into();
+        let key = derive_state_key(passphrase, salt, &kdf)?;
+        let cipher = ChaCha20Poly1305::new(&Key::from(*key));
+        let plain = Zeroizing::new(cipher
+            .decrypt(&nonce, Payload { msg: data, aad: &bytes[..STATE_HEADER_LEN] })
+            .map_err(|_| WalletStateError::Decrypt)?);
+
+        let mut data = plain.as_slice();
+        let spend_key = take32(&mut data)?;
+        let mut repr = <FrVesta as PrimeField>::Repr::default();
+        repr.as_mut().copy_from_slice(&take32(&mut data)?);
+        let root = Option::<FrVesta>::from(FrVesta::from_repr(repr)).ok_or(WalletStateError::Malformed)?;
+        let depth = take_u64(&mut data)?;
+        let mut notes = BTreeMap::new();
+        for _ in 0..take_u32(&mut data)? {
+            let commitment = TachyObj(take32(&mut data)?);
+            let value = take_u64(&mut data)?;
+            let rseed = take32(&mut data)?;
+            notes.insert(commitment.0, Note { commitment, value, rseed });
+        }
+        let mut spent = BTreeSet::new();
+        for _ in 0..take_u32(&mut data)? { spent.insert(take32(&mut data)?); }
+        if !data.is_empty() { return Err(WalletStateError::Malformed); }
+
+        Ok(Self {
+            params: WalletParams::default(),
+            backend: B::default(),
+            spend_key,
+            root,
+            notes,
+            spent,
+            pcd: None,
+            history: Vec::new(),
+            imported_depth: depth,
+        })
+    }
+
+    /// Verify every stored step from depth 1 upward: each step passes
+    /// `verify_step` (which also binds its data to its public inputs) and its
+    /// `old_root` equals the previous step's `new_root`. Returns the depth of the first failing step.
+    /// Imported wallets carry no history, so only steps proven since count.
+    pub fn verify_chain(&self) -> Result<(), usize> {
+        let mut prev_root: Option<FrVesta> = None;
+        for (i, p) in self.history.iter().enumerate() {
+            let depth = i + 1;
+            let d = &p.data;
+            let linked = prev_root.is_none_or(|r| r == d.old_root);
+            if p.depth != depth as u64 || !linked || verify_step(&self.backend, p).is_err() {
+                return Err(depth);
+            }
+            prev_root = Some(d.new_root);
+        }
+        Ok(())
+    }
+
     pub fn verify_latest(&self) -> bool {
         match &self.pcd {
             None => true,
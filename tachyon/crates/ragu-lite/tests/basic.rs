@@ -1,8 +1,10 @@
 use ff::Field;
+use subtle::ConstantTimeEq;
 use rand::{rngs::StdRng, SeedableRng};
 use ragu_lite::{
-    prove_step, verify_step, Accumulator, Circuit, CpuDriver, FrPallas, FrVesta, Instance,
-    Pcd, PcdData, SplitAccumulator, TranscriptBackend,
+    fold_steps, prove_cycle_step, prove_step, prove_step_bounded, verify_cycle_step, verify_step, AccumError, Accumulator, Circuit, CpuDriver,
+    FrPallas, FrVesta, FsTranscript, InnerStep, Instance, OuterStep, Pcd, PcdData, RecursionBackend, SplitAccumulator,
+    SynthesisError, TranscriptBackend,
 };
 
 struct Noop;
@@ -27,7 +29,7 @@ fn split_accum_and_step_vesta() {
     let meta = FrVesta::random(&mut rng);
     let new = old + meta * folded;
 
-    let data = PcdData { old_root: old, new_root: new, metadata: meta, accumulator: folded };
+    let data = PcdData::new(old, new, meta, folded);
 
     let backend = TranscriptBackend;
     let circuit = Noop;
@@ -50,4 +52,189 @@ fn nested_cycle_types_compile() {
     assert!(bool::from(b.ct_eq(&FrVesta::from(2u64))));
 }
 
+#[test]
+fn try_fold_rejects_empty() {
+    let mut acc = SplitAccumulator::<FrVesta>::new();
+    assert_eq!(acc.try_fold(), Err(AccumError::Empty));
+    let x = FrVesta::from(17u64);
+    acc.push(Accumulator::unit(x));
+    assert_eq!(acc.try_fold().unwrap().v, x);
+}
+
+#[test]
+fn cycle_steps_alternate_fields() {
+    let backend = TranscriptBackend;
+    let outer_data = PcdData::new(FrVesta::from(3u64), FrVesta::from(3u64 + 5 * 7), FrVesta::from(5u64), FrVesta::from(7u64));
+    let outer: OuterStep<[u8; 32]> =
+        prove_cycle_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), None, outer_data).unwrap();
+    verify_step(&backend, &outer).unwrap();
+
+    let inner_data = PcdData::new(FrPallas::from(11u64), FrPallas::from(11u64 + 2 * 4), FrPallas::from(2u64), FrPallas::from(4u64));
+    let inner: InnerStep<[u8; 32]> =
+        prove_cycle_step(&backend, &Noop, CpuDriver::<FrPallas>::new(), Some(&outer), inner_data).unwrap();
+    assert_eq!(inner.depth, 2);
+    // Four Vesta inputs as eight Pallas limbs, then the step's own four.
+    assert_eq!(inner.instance.inputs.len(), 12);
+    verify_cycle_step(&backend, &outer, &inner).unwrap();
+
+    // And back to Vesta, verifying the Pallas step.
+    let next: OuterStep<[u8; 32]> =
+        prove_cycle_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), Some(&inner), outer.data.clone()).unwrap();
+    verify_cycle_step(&backend, &inner, &next).unwrap();
+
+    // A step does not verify against an outer step it did not extend.
+    let mut other = outer.clone();
+    other.instance.inputs[0] += FrVesta::ONE;
+    other.inner = backend_proof(&other);
+    assert!(matches!(verify_cycle_step(&backend, &other, &inner), Err(SynthesisError::Verification)));
+}
+
+fn backend_proof(p: &OuterStep<[u8; 32]>) -> [u8; 32] {
+    use ragu_lite::{FsTranscript, RecursionBackend};
+    let mut tr = FsTranscript::new(b"ragu-lite/step");
+    tr.absorb(&u64::to_le_bytes(p.depth - 1));
+    RecursionBackend::<FrVesta>::prove(&TranscriptBackend, &p.instance, &tr)
+}
+
+#[test]
+fn extra_public_inputs_follow_fixed_fields() {
+    let backend = TranscriptBackend;
+    let (old, meta, acc) = (FrVesta::from(2u64), FrVesta::from(3u64), FrVesta::from(4u64));
+    let fee = FrVesta::from(1000u64);
+    let tag = FrVesta::from(77u64);
+    let data = PcdData { extra: vec![fee, tag], ..PcdData::new(old, old + meta * acc, meta, acc) };
+
+    let proof: Pcd<FrVesta, _> = prove_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), None, data).unwrap();
+    assert_eq!(proof.instance.inputs, vec![old, old + meta * acc, meta, acc, fee, tag]);
+    verify_step(&backend, &proof).unwrap();
+
+    // The data must match what the instance exposes.
+    let mut swapped = proof.clone();
+    swapped.data.extra.swap(0, 1);
+    assert!(matches!(verify_step(&backend, &swapped), Err(SynthesisError::Verification)));
+    let mut longer = proof;
+    longer.data.extra.push(FrVesta::ONE);
+    assert!(matches!(verify_step(&backend, &longer), Err(SynthesisError::InstanceLength)));
+
+    // Extras also sit right after the fixed fields in a cycle step, past the limbs.
+    let outer: OuterStep<[u8; 32]> =
+        prove_cycle_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), None, PcdData::new(old, old + meta * acc, meta, acc)).unwrap();
+    let one = FrPallas::ONE;
+    let inner_data = PcdData { extra: vec![FrPallas::from(9u64), FrPallas::from(8u64)], ..PcdData::new(one, one + one, one, one) };
+    let inner: InnerStep<[u8; 32]> =
+        prove_cycle_step(&backend, &Noop, CpuDriver::<FrPallas>::new(), Some(&outer), inner_data.clone()).unwrap();
+    assert_eq!(inner.data_offset(), 8);
+    assert_eq!(inner.instance.inputs[8..], inner_data.public_inputs()[..]);
+    verify_cycle_step(&backend, &outer, &inner).unwrap();
+}
+
+/// `TranscriptBackend` that exposes the previous instance as public inputs
+/// first, as a real in-circuit verifier would.
+struct ExposePrev;
+impl RecursionBackend<FrVesta> for ExposePrev {
+    type Proof = [u8; 32];
+    fn allocate_prev<D: ragu_lite::Driver<FrVesta>>(&self, d: &mut D, prev: Option<&Pcd<FrVesta, [u8; 32]>>) -> Result<(), SynthesisError> {
+        for x in prev.map(|p| p.instance.inputs.as_slice()).unwrap_or(&[]) {
+            d.input_public(*x);
+        }
+        Ok(())
+    }
+    fn prove(&self, inst: &Instance<FrVesta>, tr: &FsTranscript) -> [u8; 32] { TranscriptBackend.prove(inst, tr) }
+    fn verify(&self, inst: &Instance<FrVesta>, tr: &FsTranscript, proof: &[u8; 32]) -> bool { TranscriptBackend.verify(inst, tr, proof) }
+}
 
+#[test]
+fn step_data_follows_inputs_exposed_by_allocate_prev() {
+    let one = FrVesta::ONE;
+    let first = prove_step(&ExposePrev, &Noop, CpuDriver::<FrVesta>::new(), None, PcdData::new(one, one + one, one, one)).unwrap();
+    assert_eq!(first.data_offset(), 0);
+    let data = PcdData { extra: vec![FrVesta::from(5u64)], ..PcdData::new(one + one, one + one + one, one, one) };
+    let second = prove_step(&ExposePrev, &Noop, CpuDriver::<FrVesta>::new(), Some(&first), data.clone()).unwrap();
+    assert_eq!(second.data_offset(), 4);
+    assert_eq!(second.instance.inputs[..4], first.instance.inputs[..]);
+    assert_eq!(second.instance.inputs[4..], data.public_inputs()[..]);
+    verify_step(&ExposePrev, &second).unwrap();
+
+    let mut wrong = second;
+    wrong.data.old_root = first.data.old_root;
+    assert!(matches!(verify_step(&ExposePrev, &wrong), Err(SynthesisError::Verification)));
+}
+
+#[test]
+fn fold_steps_spans_linked_chain() {
+    let backend = TranscriptBackend;
+    let mut steps: Vec<Pcd<FrVesta, [u8; 32]>> = Vec::new();
+    let mut root = FrVesta::from(10u64);
+    for i in 1..=4u64 {
+        let (meta, acc) = (FrVesta::from(i), FrVesta::from(i + 1));
+        let data = PcdData::new(root, root + meta * acc, meta, acc);
+        root = data.new_root;
+        let step = prove_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), steps.last(), data).unwrap();
+        steps.push(step);
+    }
+
+    let folded = fold_steps(&backend, &steps).unwrap();
+    assert_eq!(folded.depth, 4);
+    assert_eq!(folded.data.old_root, FrVesta::from(10u64));
+    assert_eq!(folded.data.new_root, root);
+    assert_eq!(folded.data.old_root + folded.data.metadata * folded.data.accumulator, folded.data.new_root);
+    verify_step(&backend, &folded).unwrap();
+
+    // A step whose old_root does not follow its predecessor breaks the chain.
+    let mut broken = steps.clone();
+    let (meta, acc) = (FrVesta::from(2u64), FrVesta::from(2u64));
+    let stray = PcdData::new(root + FrVesta::ONE, root + FrVesta::ONE + meta * acc, meta, acc);
+    broken[2] = prove_step(&backend, &Noop, CpuDriver::<FrVesta>::new(), Some(&steps[1]), stray).unwrap();
+    assert!(matches!(fold_steps(&backend, &broken), Err(SynthesisError::Verification)));
+    assert!(matches!(fold_steps::<FrVesta, _>(&backend, &[]), Err(SynthesisError::EmptyChain)));
+}
+
+#[test]
+fn bounded_step_stops_at_max_depth() {
+    let backend = TranscriptBackend;
+    let one = FrVesta::ONE;
+    let data = || PcdData::new(one, one + one, one, one);
+    let mut prev: Option<Pcd<FrVesta, [u8; 32]>> = None;
+    for _ in 0..3 {
+        let step = prove_step_bounded(&backend, &Noop, CpuDriver::<FrVesta>::new(), prev.as_ref(), data(), 3).unwrap();
+        verify_step(&backend, &step).unwrap();
+        prev = Some(step);
+    }
+    assert_eq!(prev.as_ref().unwrap().depth, 3);
+    assert!(matches!(
+        prove_step_bounded(&backend, &Noop, CpuDriver::<FrVesta>::new(), prev.as_ref(), data(), 3),
+        Err(SynthesisError::DepthExceeded { depth: 4, max: 3 })
+    ));
+    assert!(matches!(
+        prove_step_bounded::<FrVesta, _, _, _>(&backend, &Noop, CpuDriver::new(), None, data(), 0),
+        Err(SynthesisError::DepthExceeded { depth: 1, max: 0 })
+    ));
+}
+
+#[test]
+fn split_accumulator_checkpoint_roundtrip() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut acc = SplitAccumulator::<FrVesta>::new();
+    for _ in 0..7 {
+        acc.push(Accumulator::unit(FrVesta::random(&mut rng)));
+    }
+    let bytes = acc.to_bytes();
+    let mut restored = SplitAccumulator::<FrVesta>::from_bytes(&bytes).unwrap();
+    assert_eq!(restored.split_fold().v, acc.split_fold().v);
+    assert_eq!(restored.count(), 7);
+    assert_eq!(restored.to_bytes(), bytes);
+
+    // Further pushes continue from the checkpoint.
+    let next = Accumulator::unit(FrVesta::random(&mut rng));
+    acc.push(next);
+    restored.push(next);
+    assert_eq!(restored.split_fold().v, acc.split_fold().v);
+    assert_eq!(restored.count(), acc.count());
+
+    let empty = SplitAccumulator::<FrVesta>::new().to_bytes();
+    assert_eq!(SplitAccumulator::<FrVesta>::from_bytes(&empty).unwrap().count(), 0);
+    assert_eq!(SplitAccumulator::<FrVesta>::from_bytes(&bytes[..40]).unwrap_err(), AccumError::Checkpoint);
+    let mut bad = bytes.clone();
+    bad[32..].copy_from_slice(&[0xff; 32]);
+    assert_eq!(SplitAccumulator::<FrVesta>::from_bytes(&bad).unwrap_err(), AccumError::Checkpoint);
+}
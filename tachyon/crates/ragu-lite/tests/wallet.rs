@@ -1,8 +1,9 @@
 use rand::{rngs::StdRng, RngCore, SeedableRng};
 use ragu_lite::{
-    wallet::{derive_nullifier}, Batch, Note, TachyObj, Wallet,
+    wallet::{derive_nullifier, StateKdfParams, WalletStateError}, Address, AddressError, Batch, Note, TachyObj, Wallet,
 };
 use ragu_lite::FrVesta;
+use ragu_lite::pasta::FromBytesWide;
 
 fn rand32(rng: &mut StdRng) -> [u8; 32] {
     let mut b = [0u8; 32];
@@ -13,7 +14,7 @@ fn rand32(rng: &mut StdRng) -> [u8; 32] {
 #[test]
 fn wallet_end_to_end_recursive() {
     let mut rng = StdRng::seed_from_u64(42);
-    let mut w = Wallet::new(&mut rng);
+    let mut w: Wallet = Wallet::new(&mut rng);
 
     // Create two incoming notes.
     let addr = w.address();
@@ -60,7 +61,7 @@ fn wallet_end_to_end_recursive() {
         let mut wide = [0u8; 64];
         wide[..32].copy_from_slice(h1.as_bytes());
         wide[32..].copy_from_slice(h2.as_bytes());
-        FrVesta::from_bytes_wide(&wide)
+        FrVesta::from_bytes_wide_src(&wide)
     };
     let meta2 = {
         let m = b2.derive_metadata_bytes();
@@ -69,7 +70,7 @@ fn wallet_end_to_end_recursive() {
         let mut wide = [0u8; 64];
         wide[..32].copy_from_slice(h1.as_bytes());
         wide[32..].copy_from_slice(h2.as_bytes());
-        FrVesta::from_bytes_wide(&wide)
+        FrVesta::from_bytes_wide_src(&wide)
     };
 
     let root1 = meta1 * folded1; // old root = 0
@@ -80,4 +81,149 @@ fn wallet_end_to_end_recursive() {
     assert_eq!(p2.data.new_root, root2);
 }
 
+#[test]
+fn wallet_state_export_import_roundtrip() {
+    let mut rng = StdRng::seed_from_u64(9);
+    let mut w: Wallet = Wallet::new(&mut rng);
+    let addr = w.address();
+    let n1 = Note { commitment: Note::commit(&addr, 40, rand32(&mut rng)), value: 40, rseed: rand32(&mut rng) };
+    let n2 = Note { commitment: Note::commit(&addr, 2, rand32(&mut rng)), value: 2, rseed: rand32(&mut rng) };
+    let mut b1 = Batch::default();
+    b1.commitments.push(n1.commitment);
+    b1.commitments.push(n2.commitment);
+    w.apply_batch_and_prove(&b1);
+    w.receive(n1.clone());
+    w.receive(n2.clone());
+    let nf = w.spend(&n2.commitment);
+    let mut b2 = Batch::default();
+    b2.nullifiers.push(nf);
+    w.apply_batch_and_prove(&b2);
+
+    // Light Argon2id costs keep the test fast; the header carries them to import.
+    let kdf = StateKdfParams { m_cost_kib: 64, t_cost: 1, p_cost: 1 };
+    let blob = w.export_state_with(b"correct horse", &kdf, &mut rng).unwrap();
+    assert!(Wallet::<ragu_lite::TranscriptBackend>::import_state(&blob, b"wrong horse").is_err());
+
+    // Fresh salt and nonce per export.
+    let again = w.export_state_with(b"correct horse", &kdf, &mut rng).unwrap();
+    assert_ne!(blob, again);
+    let twin: Wallet = Wallet::import_state(&again, b"correct horse").unwrap();
+    assert_eq!(twin.balance(), 40);
+
+    // The KDF header is authenticated, and hostile costs are refused up front.
+    let mut tampered = blob.clone();
+    tampered[1] ^= 1;
+    assert!(matches!(Wallet::<ragu_lite::TranscriptBackend>::import_state(&tampered, b"correct horse"), Err(WalletStateError::Decrypt)));
+    let mut hostile = blob.clone();
+    hostile[1..5].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(Wallet::<ragu_lite::TranscriptBackend>::import_state(&hostile, b"correct horse"), Err(WalletStateError::Kdf)));
+
+    let restored: Wallet = Wallet::import_state(&blob, b"correct horse").unwrap();
+    assert!(restored.verify_latest());
+    assert_eq!(restored.balance(), 40);
+    assert_eq!(restored.balance(), w.balance());
+    assert_eq!(restored.depth(), 2);
+    assert_eq!(restored.root, w.root);
+    assert_eq!(restored.address(), w.address());
+    assert_eq!(restored.spent, w.spent);
+}
+
+#[test]
+fn wallet_from_seed_is_deterministic() {
+    let seed = [0x5au8; 32];
+    let w1: Wallet = Wallet::from_seed(&seed);
+    let w2: Wallet = Wallet::from_seed(&seed);
+    assert_eq!(w1.address(), w2.address());
+
+    let cm = Note::commit(&w1.address(), 7, [3u8; 32]);
+    assert_eq!(derive_nullifier(&w1.spend_key, &cm), derive_nullifier(&w2.spend_key, &cm));
+
+    let other: Wallet = Wallet::from_seed(&[0xa5u8; 32]);
+    assert_ne!(other.address(), w1.address());
+}
+
+#[test]
+fn wallet_preview_batch_does_not_mutate() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut w: Wallet = Wallet::from_seed(&[1u8; 32]);
+    let addr = w.address();
+    let n1 = Note { commitment: Note::commit(&addr, 10, rand32(&mut rng)), value: 10, rseed: rand32(&mut rng) };
+    let n2 = Note { commitment: Note::commit(&addr, 20, rand32(&mut rng)), value: 20, rseed: rand32(&mut rng) };
+    w.receive(n1.clone());
+    w.receive(n2.clone());
 
+    let incoming = TachyObj([9u8; 32]);
+    let mut b = Batch::default();
+    b.nullifiers.push(derive_nullifier(&w.spend_key, &n1.commitment));
+    b.commitments.push(incoming);
+
+    let root_before = w.root;
+    let effect = w.preview_batch(&b);
+    assert_eq!(effect.spends.len(), 1);
+    assert_eq!(effect.spends[0].commitment, n1.commitment);
+    assert_eq!(effect.receives, vec![incoming]);
+    assert!(w.spent.is_empty());
+    assert_eq!(w.root, root_before);
+    assert_eq!(w.balance(), 30);
+}
+
+#[test]
+fn address_string_roundtrip_and_checksum() {
+    let w: Wallet = Wallet::from_seed(&[3u8; 32]);
+    let addr = w.canonical_address();
+    assert_eq!(addr.to_obj(), w.address());
+    assert_eq!(Address::from_bytes(&addr.to_bytes()).unwrap(), addr);
+
+    let s = addr.to_string();
+    assert!(s.starts_with("tachy1"));
+    assert_eq!(s.parse::<Address>().unwrap(), addr);
+    assert_eq!(Address::decode(&s.to_ascii_uppercase()).unwrap(), addr);
+
+    // Flip one data character to a different valid one.
+    let mut chars: Vec<char> = s.chars().collect();
+    let i = 10;
+    chars[i] = if chars[i] == 'q' { 'p' } else { 'q' };
+    let corrupted: String = chars.into_iter().collect();
+    assert_eq!(Address::decode(&corrupted), Err(AddressError::Checksum));
+
+    assert_eq!(Address::decode(&s.replacen("tachy", "zcash", 1)), Err(AddressError::Hrp));
+    assert_eq!(Address::from_bytes(&[0u8; 31]), Err(AddressError::Length(31)));
+}
+
+#[test]
+fn wallet_verify_chain_locates_broken_link() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut w: Wallet = Wallet::from_seed(&[9u8; 32]);
+    let addr = w.address();
+    for v in [5u64, 6, 7] {
+        let mut b = Batch::default();
+        b.commitments.push(Note::commit(&addr, v, rand32(&mut rng)));
+        w.apply_batch_and_prove(&b);
+    }
+    assert_eq!(w.history.len(), 3);
+    assert!(w.verify_latest());
+    assert_eq!(w.verify_chain(), Ok(()));
+
+    w.history[1].data.old_root += FrVesta::from(1u64);
+    assert_eq!(w.verify_chain(), Err(2));
+}
+
+#[test]
+fn wallet_secrets_zeroize() {
+    use zeroize::{Zeroize, ZeroizeOnDrop};
+    fn assert_zeroize_on_drop<T: Zeroize + ZeroizeOnDrop>() {}
+    assert_zeroize_on_drop::<Wallet>();
+
+    let mut rng = StdRng::seed_from_u64(9);
+    let mut w: Wallet = Wallet::new(&mut rng);
+    let addr = w.address();
+    let rseed = rand32(&mut rng);
+    let note = Note { commitment: Note::commit(&addr, 5, rseed), value: 5, rseed };
+    w.receive(note.clone());
+    w.zeroize();
+    assert_eq!(w.spend_key, [0u8; 32]);
+    let kept = &w.notes[&note.commitment.0];
+    assert_eq!((kept.value, kept.rseed), (0, [0u8; 32]));
+    // Dropping runs the same wipe.
+    drop(Wallet::<ragu_lite::TranscriptBackend>::from_seed(&[3u8; 32]));
+}